@@ -38,5 +38,5 @@ pub extern "C" fn some_new_fancy_function() {
 }
 
 fn state_key() -> Vec<u8> {
-    storage::bytes_to_key(storage::KeyPrefix::Config, b"STATE")
+    storage::bytes_to_key(storage::KeyPrefix::Config, b"VERSIONED_STATE")
 }
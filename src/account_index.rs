@@ -0,0 +1,110 @@
+//! List of the addresses of the EVM accounts, in the order they were first stored.
+//!
+//! Contracts cannot iterate over the NEAR state, so the accounts are listed in the storage for
+//! `dump_state` and for the migrations which go through all of them. An address is appended
+//! when a record of the account is first written and is never removed, so that positions in
+//! the list can be used as cursors. Accounts stored before the list existed are added to it
+//! by the owner, with `index_accounts`, who then confirms with `finish_account_indexing` that
+//! all of them are listed. Until then, the list is not known to be complete, and the migrations
+//! and exports which go through it do not complete either.
+#[cfg(feature = "contract")]
+use crate::account_migration;
+#[cfg(feature = "contract")]
+use crate::engine::{Account, Engine};
+#[cfg(feature = "contract")]
+use crate::parameters::IndexedAccount;
+#[cfg(feature = "contract")]
+use crate::prelude::H256;
+use crate::prelude::{Address, Vec};
+use crate::sdk;
+use crate::storage::{bytes_to_key, KeyPrefix};
+#[cfg(feature = "contract")]
+use crate::storage_cleanup;
+
+const LENGTH_ID: u8 = 0;
+const ENTRY_ID: u8 = 1;
+const POSITION_ID: u8 = 2;
+const COMPLETE_ID: u8 = 3;
+
+/// Appends an address to the list, unless it is listed already.
+pub fn insert(address: &Address) {
+    let position_key = position_key(address);
+    if sdk::storage_has_key(&position_key) {
+        return;
+    }
+    let position = len();
+    sdk::write_storage(&position_key, &position.to_le_bytes());
+    sdk::write_storage(&entry_key(position), address.as_bytes());
+    sdk::write_storage(&length_key(), &(position + 1).to_le_bytes());
+}
+
+/// Adds an account stored before the list existed, moving it to a record if it is not migrated
+/// yet (see `account_migration`), and records the given slots of its current
+/// storage generation for them to be listed and cleaned up. Accounts which are not stored and
+/// slots which are not set are left out.
+#[cfg(feature = "contract")]
+pub fn index_account(account: &IndexedAccount) {
+    let address = Address(account.address);
+    if account_migration::has_legacy_keys(&address) {
+        account_migration::migrate_account(&address);
+    }
+    let record = Engine::get_account(&address);
+    if record == Account::default() {
+        return;
+    }
+    insert(&address);
+    for key in account.storage_keys.iter() {
+        let key = H256(*key);
+        if !Engine::get_storage(&address, &key, record.generation).is_zero() {
+            storage_cleanup::record_slot(&address, &key, record.generation);
+        }
+    }
+}
+
+/// Whether all the accounts stored before the list existed were added to it.
+#[cfg(feature = "contract")]
+pub fn is_complete() -> bool {
+    sdk::storage_has_key(&complete_key())
+}
+
+/// Records that all the accounts stored before the list existed were added to it, e.g. for a
+/// new engine which has no such accounts.
+#[cfg(feature = "contract")]
+pub fn set_complete() {
+    sdk::write_storage(&complete_key(), &[]);
+}
+
+/// Returns the number of listed addresses.
+#[cfg(feature = "contract")]
+pub fn len() -> u64 {
+    sdk::read_u64(&length_key()).unwrap_or(0)
+}
+
+/// Returns the address at the given position in the list.
+#[cfg(feature = "contract")]
+pub fn get(position: u64) -> Option<Address> {
+    sdk::read_storage(&entry_key(position)).map(|value| Address::from_slice(&value))
+}
+
+pub(crate) fn length_key() -> Vec<u8> {
+    bytes_to_key(KeyPrefix::AccountIndex, &[LENGTH_ID])
+}
+
+pub(crate) fn entry_key(position: u64) -> Vec<u8> {
+    bytes_to_key(
+        KeyPrefix::AccountIndex,
+        &[&[ENTRY_ID][..], &position.to_le_bytes()].concat(),
+    )
+}
+
+pub(crate) fn position_key(address: &Address) -> Vec<u8> {
+    bytes_to_key(
+        KeyPrefix::AccountIndex,
+        &[&[POSITION_ID][..], address.as_bytes()].concat(),
+    )
+}
+
+#[cfg(any(feature = "contract", test))]
+pub(crate) fn complete_key() -> Vec<u8> {
+    bytes_to_key(KeyPrefix::AccountIndex, &[COMPLETE_ID])
+}
@@ -0,0 +1,148 @@
+//! Migration of the accounts to `Account` records.
+//!
+//! The nonce, balance and storage generation of an account used to be stored under keys of
+//! their own, and its code under its address rather than by hash. Accounts are moved to records
+//! in batches, in the order of the account index (see `account_index`), and the accounts without
+//! a record are read from the former keys until all of them are moved. Accounts stored before
+//! the account index existed are only part of it once added with `index_accounts`, which moves
+//! them right away, and the migration completes only once the owner confirms with
+//! `finish_account_indexing` that all of them were added.
+#[cfg(feature = "contract")]
+use crate::account_index;
+use crate::engine::Account;
+#[cfg(feature = "contract")]
+use crate::engine::Engine;
+#[cfg(feature = "contract")]
+use crate::migration::MigrationBatch;
+use crate::migration::{self, MigrationId};
+use crate::prelude::{Address, Vec, H256, U256};
+use crate::sdk;
+use crate::storage::{address_to_key, KeyPrefix};
+use crate::types::{keccak, u256_to_arr, EMPTY_CODE_HASH};
+
+/// Prefixes of the keys under which accounts used to be stored.
+#[cfg(feature = "contract")]
+const LEGACY_PREFIXES: [KeyPrefix; 4] = [
+    KeyPrefix::Nonce,
+    KeyPrefix::Balance,
+    KeyPrefix::Code,
+    KeyPrefix::Generation,
+];
+
+/// Stands for the hash of a code stored under the address of its account in the records read
+/// from the former keys, so that the code is only read and hashed when its hash is needed.
+/// It is never written to a record.
+pub const LEGACY_CODE_HASH: H256 = H256([0xff; 32]);
+
+/// Whether all the accounts are migrated.
+pub fn is_done() -> bool {
+    migration::is_completed(MigrationId::AccountRecords)
+}
+
+/// Migrates at most `limit` accounts, from the position in the account index given by `cursor`.
+/// The migration only completes once the index is complete (see `account_index`): until then,
+/// accounts which are not listed yet may still be stored under their former keys, from which
+/// they keep being read.
+#[cfg(feature = "contract")]
+pub fn migrate(cursor: Option<&[u8]>, limit: u32) -> MigrationBatch {
+    let start = cursor
+        .map(|cursor| {
+            let mut position = [0u8; 8];
+            position.copy_from_slice(cursor);
+            u64::from_le_bytes(position)
+        })
+        .unwrap_or(0);
+    let len = account_index::len();
+    let end = len.min(start.saturating_add(u64::from(limit)));
+    for position in start..end {
+        let address = account_index::get(position).expect("ERR_CORRUPTED_STORAGE");
+        migrate_account(&address);
+    }
+    MigrationBatch {
+        steps: (end - start) as u32,
+        cursor: if end < len || !account_index::is_complete() {
+            Some(end.to_le_bytes().to_vec())
+        } else {
+            None
+        },
+    }
+}
+
+/// Whether an account is stored under any of the keys used before the migration.
+#[cfg(feature = "contract")]
+pub fn has_legacy_keys(address: &Address) -> bool {
+    LEGACY_PREFIXES
+        .iter()
+        .any(|prefix| sdk::storage_has_key(&address_to_key(*prefix, address)))
+}
+
+/// Reads an account from the keys it was stored under before the migration.
+pub fn legacy_account(address: &Address) -> Account {
+    let read_u256 = |prefix| {
+        sdk::read_storage(&address_to_key(prefix, address))
+            .map(|value| u256_to_arr(&U256::from_big_endian(&value)))
+            .unwrap_or_default()
+    };
+    let generation = sdk::read_storage(&address_to_key(KeyPrefix::Generation, address))
+        .map(|value| {
+            let mut bytes = [0u8; 4];
+            bytes.copy_from_slice(&value[0..4]);
+            u32::from_be_bytes(bytes)
+        })
+        .unwrap_or(0);
+    let code_hash = if sdk::storage_has_key(&address_to_key(KeyPrefix::Code, address)) {
+        LEGACY_CODE_HASH
+    } else {
+        EMPTY_CODE_HASH
+    };
+    Account {
+        nonce: read_u256(KeyPrefix::Nonce),
+        balance: read_u256(KeyPrefix::Balance),
+        generation,
+        code_hash: code_hash.0,
+    }
+}
+
+/// Returns the hash of the code of an account stored under its address, for `LEGACY_CODE_HASH`.
+pub fn legacy_code_hash(address: &Address) -> H256 {
+    legacy_code(address)
+        .map(|code| keccak(&code))
+        .unwrap_or(EMPTY_CODE_HASH)
+}
+
+/// Returns the code of an account stored under its address, if it is not migrated yet.
+pub fn legacy_code(address: &Address) -> Option<Vec<u8>> {
+    sdk::read_storage(&address_to_key(KeyPrefix::Code, address))
+}
+
+/// Removes the code of an account stored under its address, and returns whether there was one.
+pub fn remove_legacy_code(address: &Address) -> bool {
+    let key = address_to_key(KeyPrefix::Code, address);
+    let exists = sdk::storage_has_key(&key);
+    if exists {
+        sdk::remove_storage(&key);
+    }
+    exists
+}
+
+/// Removes the keys an account was stored under before the migration, except for its code
+/// which is moved separately.
+pub fn remove_legacy_keys(address: &Address) {
+    sdk::remove_storage(&address_to_key(KeyPrefix::Nonce, address));
+    sdk::remove_storage(&address_to_key(KeyPrefix::Balance, address));
+    sdk::remove_storage(&address_to_key(KeyPrefix::Generation, address));
+}
+
+/// Moves an account to a record, if it is not migrated yet.
+#[cfg(feature = "contract")]
+pub fn migrate_account(address: &Address) {
+    // Accounts written since the migration started already have a record.
+    if !sdk::storage_has_key(&address_to_key(KeyPrefix::Account, address)) {
+        Engine::set_account(address, &legacy_account(address));
+    }
+    remove_legacy_keys(address);
+    if let Some(code) = legacy_code(address) {
+        Engine::retain_code(&keccak(&code), &code);
+        sdk::remove_storage(&address_to_key(KeyPrefix::Code, address));
+    }
+}
@@ -0,0 +1,108 @@
+use criterion::{BatchSize, Criterion};
+use secp256k1::SecretKey;
+
+use crate::test_utils::{
+    address_from_secret_key, create_eth_transaction, deploy_evm, deploy_evm_with_code, SUBMIT,
+};
+use crate::types::Wei;
+
+const INITIAL_BALANCE: Wei = Wei::new_u64(1000);
+const INITIAL_NONCE: u64 = 0;
+const TRANSFER_AMOUNT: Wei = Wei::new_u64(123);
+/// Test build of the engine from before account records, to measure the baseline layout.
+const BASELINE_WASM: &str = "baseline-test.wasm";
+
+pub(crate) fn eth_account_records_benchmark(c: &mut Criterion) {
+    let mut runner = deploy_evm();
+    let mut rng = rand::thread_rng();
+    let source_account = SecretKey::random(&mut rng);
+    let dest_account = address_from_secret_key(&SecretKey::random(&mut rng));
+    runner.create_legacy_address(
+        address_from_secret_key(&source_account),
+        INITIAL_BALANCE,
+        INITIAL_NONCE.into(),
+    );
+    runner.create_legacy_address(dest_account, INITIAL_BALANCE, INITIAL_NONCE.into());
+    let transaction = create_eth_transaction(
+        Some(dest_account),
+        TRANSFER_AMOUNT,
+        vec![],
+        Some(runner.chain_id),
+        &source_account,
+    );
+    let input = rlp::encode(&transaction).to_vec();
+    let calling_account_id = "some-account.near".to_string();
+
+    // measure gas usage with the baseline layout, i.e. with an engine from before account
+    // records, if one was built (e.g. with `make betanet-test-build` at that version)
+    let baseline_gas = std::fs::read(BASELINE_WASM).ok().map(|code| {
+        let mut baseline_runner = deploy_evm_with_code(code);
+        baseline_runner.create_legacy_address(
+            address_from_secret_key(&source_account),
+            INITIAL_BALANCE,
+            INITIAL_NONCE.into(),
+        );
+        baseline_runner.create_legacy_address(dest_account, INITIAL_BALANCE, INITIAL_NONCE.into());
+        let (output, maybe_err) =
+            baseline_runner
+                .one_shot()
+                .call(SUBMIT, calling_account_id.clone(), input.clone());
+        assert!(maybe_err.is_none());
+        output.unwrap().burnt_gas
+    });
+
+    // measure gas usage with the accounts under their former keys, before they are migrated;
+    // this includes the lookup of the missing records
+    let (output, maybe_err) =
+        runner
+            .one_shot()
+            .call(SUBMIT, calling_account_id.clone(), input.clone());
+    assert!(maybe_err.is_none());
+    let legacy_gas = output.unwrap().burnt_gas;
+
+    // measure gas usage with account records
+    let mut records_runner = deploy_evm();
+    records_runner.create_address(
+        address_from_secret_key(&source_account),
+        INITIAL_BALANCE,
+        INITIAL_NONCE.into(),
+    );
+    records_runner.create_address(dest_account, INITIAL_BALANCE, INITIAL_NONCE.into());
+    let (output, maybe_err) =
+        records_runner
+            .one_shot()
+            .call(SUBMIT, calling_account_id.clone(), input.clone());
+    assert!(maybe_err.is_none());
+    let gas = output.unwrap().burnt_gas;
+    // TODO(#45): capture this in a file
+    match baseline_gas {
+        Some(baseline_gas) => {
+            println!("ETH_TRANSFER BASELINE NEAR GAS: {:?}", baseline_gas);
+            println!(
+                "ACCOUNT RECORDS NEAR GAS SAVED: {:?}",
+                baseline_gas.saturating_sub(gas)
+            );
+        }
+        None => println!("ETH_TRANSFER BASELINE NEAR GAS: no {} found", BASELINE_WASM),
+    }
+    println!(
+        "ETH_TRANSFER UNMIGRATED ACCOUNTS NEAR GAS: {:?}",
+        legacy_gas
+    );
+    println!("ETH_TRANSFER ACCOUNT RECORDS NEAR GAS: {:?}", gas);
+
+    // measure wall-clock time
+    c.bench_function("eth_transfer_account_records", |b| {
+        b.iter_batched(
+            || {
+                (
+                    records_runner.one_shot(),
+                    calling_account_id.clone(),
+                    input.clone(),
+                )
+            },
+            |(r, c, i)| r.call(SUBMIT, c, i),
+            BatchSize::SmallInput,
+        )
+    });
+}
@@ -1,5 +1,6 @@
 use criterion::Criterion;
 
+mod eth_account_records;
 mod eth_deploy_code;
 mod eth_erc20;
 mod eth_standard_precompiles;
@@ -11,6 +12,7 @@ mod eth_transfer;
 fn benches() {
     let mut c = Criterion::default();
 
+    eth_account_records::eth_account_records_benchmark(&mut c);
     eth_deploy_code::eth_deploy_code_benchmark(&mut c);
     eth_erc20::eth_erc20_benchmark(&mut c);
     eth_standard_precompiles::eth_standard_precompiles_benchmark(&mut c);
@@ -1,32 +1,74 @@
 use borsh::{BorshDeserialize, BorshSerialize};
+#[cfg(feature = "evm_bully")]
+use core::cell::RefCell;
 use core::mem;
 use evm::backend::{Apply, ApplyBackend, Backend, Basic, Log};
-use evm::executor::{StackExecutor, StackSubstateMetadata};
+use evm::executor::{StackExecutor, StackState, StackSubstateMetadata};
 use evm::ExitFatal;
 use evm::{Config, CreateScheme, ExitError, ExitReason};
 
+use crate::account_index;
+use crate::account_migration;
 use crate::connector::EthConnectorContract;
 #[cfg(feature = "contract")]
 use crate::contract::current_address;
+use crate::evm_event;
 use crate::map::{BijectionMap, LookupMap};
+#[cfg(feature = "tracing")]
+use crate::parameters::TraceCallArgs;
+#[cfg(feature = "evm_bully")]
+use crate::parameters::{BeginBlockArgs, GenesisAccount};
 use crate::parameters::{
-    FunctionCallArgs, NEP141FtOnTransferArgs, NewCallArgs, PromiseCreateArgs, SubmitResult,
-    TransactionStatus, ViewCallArgs,
+    BlockReceiptsSummary, CreateAccessListResult, EstimateGasArgs, EstimateGasResult,
+    FunctionCallArgs, NEP141FtOnTransferArgs, NewCallArgs, PromiseCreateArgs, ResultAccessTuple,
+    ResultLog, StateOverride, SubmitResult, TransactionStatus, ViewCallArgs, ViewCallArgsV2,
 };
 
 use crate::precompiles::Precompiles;
-use crate::prelude::{is_valid_account_id, Address, TryInto, Vec, H256, U256};
+use crate::prelude::{is_valid_account_id, Address, BTreeMap, TryInto, Vec, H256, U256};
+use crate::receipt;
 use crate::sdk;
 use crate::state::AuroraStackState;
+use crate::state_commitment;
 use crate::storage::{address_to_key, bytes_to_key, storage_to_key, KeyPrefix, KeyPrefixU8};
-use crate::types::{u256_to_arr, AccountId, Wei, ERC20_MINT_SELECTOR};
+use crate::storage_cleanup;
+#[cfg(feature = "tracing")]
+use crate::tracing::{self, AnyTracer};
+use crate::trie::{self, StorageNodeStore};
+use crate::types::{
+    keccak, u256_to_arr, AccountId, RawAddress, RawH256, RawU256, Wei, EMPTY_CODE_HASH,
+    ERC20_MINT_SELECTOR,
+};
 
 /// Used as the first byte in the concatenation of data used to compute the blockhash.
 /// Could be useful in the future as a version byte, or to distinguish different types of blocks.
 const BLOCK_HASH_PREFIX: u8 = 0;
+/// Same as `BLOCK_HASH_PREFIX`, but for block hashes recorded from the NEAR block data.
+const RECORDED_BLOCK_HASH_PREFIX: u8 = 1;
 const BLOCK_HASH_PREFIX_SIZE: usize = 1;
 const BLOCK_HEIGHT_SIZE: usize = 8;
 const CHAIN_ID_SIZE: usize = 32;
+/// Used as the first byte of the keys of `BlockReceiptsSummary` values.
+const BLOCK_RECEIPTS_SUMMARY_ID: u8 = 0;
+/// Used as the first byte of the keys of the nodes of the receipts trie of the latest block.
+const BLOCK_RECEIPTS_NODE_ID: u8 = 1;
+/// Used as the first byte of the keys of the reference counts of the receipts trie nodes.
+const BLOCK_RECEIPTS_NODE_REFCOUNT_ID: u8 = 2;
+/// Used as the key of the height of the latest block with receipts.
+const LATEST_RECEIPTS_BLOCK_ID: u8 = 3;
+/// Used as the first byte of the keys of contract codes.
+const CODE_ID: u8 = 0;
+/// Used as the first byte of the keys of the number of accounts using a contract code.
+const CODE_REFCOUNT_ID: u8 = 1;
+/// Number of most recent blocks for which the BLOCKHASH opcode returns a hash.
+pub const BLOCK_HASH_HISTORY: u64 = 256;
+/// The EVM address for the Aurora account, being 0x4444588443C3a91288c5002483449Aba1054192b.
+///
+/// See: https://doc.aurora.dev/develop/compat/evm#coinbase
+pub const DEFAULT_COINBASE: RawAddress = [
+    0x44, 0x44, 0x58, 0x84, 0x43, 0xC3, 0xa9, 0x12, 0x88, 0xc5, 0x00, 0x24, 0x83, 0x44, 0x9A, 0xba,
+    0x10, 0x54, 0x19, 0x2b,
+];
 
 #[cfg(not(feature = "contract"))]
 pub fn current_address() -> Address {
@@ -107,6 +149,7 @@ impl EngineErrorKind {
             EvmError(ExitError::OutOfOffset) => "ERR_OUT_OF_OFFSET",
             EvmError(ExitError::OutOfGas) => "ERR_OUT_OF_GAS",
             EvmError(ExitError::OutOfFund) => "ERR_OUT_OF_FUND",
+            EvmError(ExitError::InvalidCode) => "ERR_INVALID_CODE",
             EvmError(ExitError::Other(m)) => m,
             EvmError(_) => unreachable!(), // unused misc
             EvmFatal(ExitFatal::NotSupported) => "ERR_NOT_SUPPORTED",
@@ -196,6 +239,30 @@ impl From<BalanceOverflow> for GasPaymentError {
     }
 }
 
+/// Errors resulting from trying to pay for storage
+pub enum StoragePaymentError {
+    /// Overflow adding ETH to an account balance (should never happen)
+    BalanceOverflow(BalanceOverflow),
+    /// Overflow in bytes * byte_price calculation
+    EthAmountOverflow,
+    /// Not enough balance for account to cover the storage cost
+    OutOfFund,
+}
+impl AsRef<[u8]> for StoragePaymentError {
+    fn as_ref(&self) -> &[u8] {
+        match self {
+            Self::BalanceOverflow(overflow) => overflow.as_ref(),
+            Self::EthAmountOverflow => b"ERR_STORAGE_ETH_AMOUNT_OVERFLOW",
+            Self::OutOfFund => b"ERR_OUT_OF_FUND_FOR_STORAGE",
+        }
+    }
+}
+impl From<BalanceOverflow> for StoragePaymentError {
+    fn from(overflow: BalanceOverflow) -> Self {
+        Self::BalanceOverflow(overflow)
+    }
+}
+
 pub const ERR_INVALID_NEP141_ACCOUNT_ID: &str = "ERR_INVALID_NEP141_ACCOUNT_ID";
 
 #[derive(Debug)]
@@ -255,6 +322,96 @@ impl AsRef<[u8]> for EngineStateError {
     }
 }
 
+#[derive(Debug)]
+pub enum ScheduleHardForkError {
+    /// The scheduled hard fork is not newer than the current one.
+    Downgrade,
+}
+
+impl AsRef<[u8]> for ScheduleHardForkError {
+    fn as_ref(&self) -> &[u8] {
+        match self {
+            Self::Downgrade => b"ERR_HARD_FORK_DOWNGRADE",
+        }
+    }
+}
+
+/// Ethereum hard forks the engine is able to execute transactions with.
+/// The variants are ordered from the oldest to the newest.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd)]
+pub enum HardForkId {
+    Istanbul,
+    Berlin,
+    London,
+}
+
+impl HardForkId {
+    /// The newest supported hard fork, used for new deployments.
+    pub const LATEST: Self = Self::London;
+
+    pub(crate) fn config(self) -> &'static Config {
+        match self {
+            Self::Istanbul => ISTANBUL_CONFIG,
+            Self::Berlin => BERLIN_CONFIG,
+            Self::London => LONDON_CONFIG,
+        }
+    }
+
+    pub(crate) fn precompiles(self) -> Precompiles {
+        match self {
+            Self::Istanbul => Precompiles::new_istanbul(),
+            Self::Berlin => Precompiles::new_berlin(),
+            Self::London => Precompiles::new_london(),
+        }
+    }
+}
+
+impl Default for HardForkId {
+    fn default() -> Self {
+        Self::LATEST
+    }
+}
+
+/// Source of the block hashes returned by the BLOCKHASH opcode.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, Eq, PartialEq)]
+pub enum BlockHashMode {
+    /// Block hashes are derived from the block height (see `Engine::compute_block_hash`).
+    Derived,
+    /// Block hashes are recorded from the NEAR block data the first time the engine executes
+    /// a transaction in a block, keeping the last `BLOCK_HASH_HISTORY` of them.
+    /// Blocks in which no transaction was executed have a zero hash.
+    Recorded,
+}
+
+impl Default for BlockHashMode {
+    fn default() -> Self {
+        Self::Derived
+    }
+}
+
+/// Source of the address returned by the COINBASE opcode.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, Eq, PartialEq)]
+pub enum CoinbasePolicy {
+    /// Always the given address.
+    Fixed(RawAddress),
+    /// The EVM address registered (see `register_relayer`) by the account submitting
+    /// the transaction, or `DEFAULT_COINBASE` if the account has not registered one.
+    Relayer,
+}
+
+impl Default for CoinbasePolicy {
+    fn default() -> Self {
+        Self::Fixed(DEFAULT_COINBASE)
+    }
+}
+
+/// A hard fork which will be used from the given block height onwards.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, Eq, PartialEq)]
+pub struct ScheduledHardFork {
+    pub hard_fork: HardForkId,
+    pub block_height: u64,
+}
+
 /// Engine internal state, mostly configuration.
 /// Should not contain anything large or enumerable.
 #[derive(BorshSerialize, BorshDeserialize, Default)]
@@ -271,6 +428,57 @@ pub struct EngineState {
     pub upgrade_delay_blocks: u64,
     /// Mapping between relayer account id and relayer evm address
     pub relayers_evm_addresses: LookupMap<{ KeyPrefix::RelayerEvmAddressMap as KeyPrefixU8 }>,
+    /// Hard fork used to execute transactions.
+    pub hard_fork: HardForkId,
+    /// Hard fork which replaces `hard_fork` once its block height is reached.
+    pub scheduled_hard_fork: Option<ScheduledHardFork>,
+    /// Source of the block hashes returned by the BLOCKHASH opcode.
+    pub block_hash_mode: BlockHashMode,
+    /// Whether the TIMESTAMP opcode returns seconds, as on Ethereum, rather than the
+    /// microseconds returned by earlier versions of the engine.
+    pub block_timestamp_in_seconds: bool,
+    /// Gas limit returned by the GASLIMIT opcode. `None` stands for `U256::MAX`.
+    pub block_gas_limit: Option<u64>,
+    /// Source of the address returned by the COINBASE opcode.
+    pub coinbase_policy: CoinbasePolicy,
+    /// Whether the EVM logs of transactions are also written as NEAR logs (see NEP-297).
+    pub evm_log_events: bool,
+    /// Share, in percent, of the storage staking released by `cleanup_storage` which is paid
+    /// to its caller.
+    pub storage_cleanup_reward_percent: u8,
+    /// Price in wei of a byte of NEAR storage used by an Ethereum transaction, charged to its
+    /// sender. `None` stands for the storage not being charged.
+    pub storage_byte_price: Option<RawU256>,
+}
+
+impl EngineState {
+    /// Returns the hard fork in effect at the given block height.
+    pub fn hard_fork_at(&self, block_height: u64) -> HardForkId {
+        match self.scheduled_hard_fork {
+            Some(scheduled) if scheduled.block_height <= block_height => scheduled.hard_fork,
+            _ => self.hard_fork,
+        }
+    }
+
+    /// Schedules a switch to `hard_fork` at `block_height`, replacing any switch which has not
+    /// happened yet at `current_block_height`.
+    pub fn schedule_hard_fork(
+        &mut self,
+        hard_fork: HardForkId,
+        block_height: u64,
+        current_block_height: u64,
+    ) -> Result<(), ScheduleHardForkError> {
+        let current_hard_fork = self.hard_fork_at(current_block_height);
+        if hard_fork <= current_hard_fork {
+            return Err(ScheduleHardForkError::Downgrade);
+        }
+        self.hard_fork = current_hard_fork;
+        self.scheduled_hard_fork = Some(ScheduledHardFork {
+            hard_fork,
+            block_height,
+        });
+        Ok(())
+    }
 }
 
 impl From<NewCallArgs> for EngineState {
@@ -281,6 +489,142 @@ impl From<NewCallArgs> for EngineState {
             bridge_prover_id: args.bridge_prover_id,
             upgrade_delay_blocks: args.upgrade_delay_blocks,
             relayers_evm_addresses: LookupMap::new(),
+            hard_fork: HardForkId::LATEST,
+            scheduled_hard_fork: None,
+            block_hash_mode: BlockHashMode::Derived,
+            block_timestamp_in_seconds: true,
+            block_gas_limit: None,
+            coinbase_policy: CoinbasePolicy::default(),
+            evm_log_events: false,
+            storage_cleanup_reward_percent: DEFAULT_STORAGE_CLEANUP_REWARD_PERCENT,
+            storage_byte_price: None,
+        }
+    }
+}
+
+/// Layouts of `EngineState` as stored, tagged with their version so that a stored state is
+/// always decoded with the layout it was written with. Changing the fields of `EngineState`
+/// requires keeping its previous layout as a variant converted by `Engine::get_state`.
+#[derive(BorshSerialize, BorshDeserialize)]
+enum VersionedEngineState {
+    V1(EngineState),
+}
+
+/// Layout of `EngineState` used by deployments prior to per-deployment hard fork selection,
+/// which the unversioned layouts extend (see `decode_unversioned_state`).
+#[derive(BorshDeserialize)]
+struct LegacyEngineState {
+    chain_id: [u8; 32],
+    owner_id: AccountId,
+    bridge_prover_id: AccountId,
+    upgrade_delay_blocks: u64,
+    relayers_evm_addresses: LookupMap<{ KeyPrefix::RelayerEvmAddressMap as KeyPrefixU8 }>,
+}
+
+impl From<LegacyEngineState> for EngineState {
+    fn from(state: LegacyEngineState) -> Self {
+        EngineState {
+            chain_id: state.chain_id,
+            owner_id: state.owner_id,
+            bridge_prover_id: state.bridge_prover_id,
+            upgrade_delay_blocks: state.upgrade_delay_blocks,
+            relayers_evm_addresses: state.relayers_evm_addresses,
+            // Legacy deployments were executing transactions with Istanbul rules,
+            // the switch to newer hard forks must be scheduled explicitly.
+            hard_fork: HardForkId::Istanbul,
+            scheduled_hard_fork: None,
+            block_hash_mode: BlockHashMode::Derived,
+            // Contracts may rely on the timestamp being in microseconds.
+            block_timestamp_in_seconds: false,
+            block_gas_limit: None,
+            coinbase_policy: CoinbasePolicy::default(),
+            evm_log_events: false,
+            storage_cleanup_reward_percent: DEFAULT_STORAGE_CLEANUP_REWARD_PERCENT,
+            storage_byte_price: None,
+        }
+    }
+}
+
+/// Decodes a state stored before its layout was versioned. Fields were appended to the
+/// legacy layout in several steps, and a state may end after any of them: the fields it lacks
+/// keep the values matching the behaviour of the engine before they were added.
+fn decode_unversioned_state(bytes: &[u8]) -> Result<EngineState, EngineStateError> {
+    fn read<T: BorshDeserialize>(buf: &mut &[u8]) -> Result<T, EngineStateError> {
+        T::deserialize(buf).map_err(|_| EngineStateError::DeserializationFailed)
+    }
+
+    let buf = &mut &bytes[..];
+    let mut state = EngineState::from(read::<LegacyEngineState>(buf)?);
+    if buf.is_empty() {
+        return Ok(state);
+    }
+    state.hard_fork = read(buf)?;
+    state.scheduled_hard_fork = read(buf)?;
+    if buf.is_empty() {
+        return Ok(state);
+    }
+    state.block_hash_mode = read(buf)?;
+    if buf.is_empty() {
+        return Ok(state);
+    }
+    state.block_timestamp_in_seconds = read(buf)?;
+    if buf.is_empty() {
+        return Ok(state);
+    }
+    state.block_gas_limit = read(buf)?;
+    state.coinbase_policy = read(buf)?;
+    if buf.is_empty() {
+        return Ok(state);
+    }
+    state.evm_log_events = read(buf)?;
+    if buf.is_empty() {
+        return Ok(state);
+    }
+    state.storage_cleanup_reward_percent = read(buf)?;
+    if buf.is_empty() {
+        return Ok(state);
+    }
+    state.storage_byte_price = read(buf)?;
+    if !buf.is_empty() {
+        return Err(EngineStateError::DeserializationFailed);
+    }
+    Ok(state)
+}
+
+/// State of an EVM account other than its code and storage, kept in a single record so that
+/// it is read with a single storage access.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Account {
+    pub nonce: RawU256,
+    pub balance: RawU256,
+    /// Generation of the storage of the account (see `storage_to_key`).
+    pub generation: u32,
+    /// Hash of the code of the account, under which the code is stored.
+    pub code_hash: RawH256,
+}
+
+impl Account {
+    pub fn nonce(&self) -> U256 {
+        U256::from_big_endian(&self.nonce)
+    }
+
+    pub fn balance(&self) -> Wei {
+        Wei::new(U256::from_big_endian(&self.balance))
+    }
+
+    /// Whether the account has no nonce, balance or code (see EIP-161).
+    pub fn is_empty(&self) -> bool {
+        self.nonce().is_zero() && self.balance().is_zero() && self.code_hash == EMPTY_CODE_HASH.0
+    }
+}
+
+impl Default for Account {
+    fn default() -> Self {
+        Self {
+            nonce: [0; 32],
+            balance: [0; 32],
+            generation: 0,
+            code_hash: EMPTY_CODE_HASH.0,
         }
     }
 }
@@ -288,13 +632,55 @@ impl From<NewCallArgs> for EngineState {
 pub struct Engine {
     state: EngineState,
     origin: Address,
+    /// Hash of the Ethereum transaction being executed, if any.
+    transaction_hash: Option<H256>,
+    /// Type of the Ethereum transaction being executed (see EIP-2718), if it is a typed one.
+    transaction_type: Option<u8>,
+    /// Storage usage before and after the changes to the EVM state were applied, if they were.
+    applied_storage_usage: Option<(u64, u64)>,
+    /// Properties of the replayed Ethereum block set by `begin_block`, if any. Only read from
+    /// the storage once the EVM asks for a property of the block.
+    #[cfg(feature = "evm_bully")]
+    block: RefCell<Option<Option<BeginBlockArgs>>>,
 }
 
-// TODO: upgrade to Berlin HF
-pub(crate) const CONFIG: &Config = &Config::istanbul();
+const ISTANBUL_CONFIG: &Config = &Config::istanbul();
+const BERLIN_CONFIG: &Config = &Config::berlin();
+const LONDON_CONFIG: &Config = &Config::london();
+
+/// Base fee per unit of gas (see EIP-1559), also returned by the `BASEFEE` opcode (see EIP-3198).
+/// Aurora relayers are paid the full effective gas price of a transaction, so there is
+/// currently no base fee.
+pub(crate) const BLOCK_BASE_FEE_PER_GAS: U256 = U256([0, 0, 0, 0]);
+
+/// Highest gas limit tried by `estimate_gas` when there is neither a gas cap nor a block gas limit.
+pub const DEFAULT_ESTIMATE_GAS_CAP: u64 = 50_000_000;
+
+/// Least gas spent by an Ethereum transaction per byte of NEAR storage it uses, which bounds
+/// the storage payment reserved from the gas limit of a transaction (see
+/// `Engine::reserve_storage`). A new storage slot costs 20000 gas, for a few hundred bytes
+/// along with its records and trie nodes.
+pub const GAS_PER_STORAGE_BYTE: u64 = 20;
+
+/// Default share of the released storage staking paid to the callers of `cleanup_storage`.
+/// The storage of old generations was not necessarily paid for by the users, so there is no
+/// reward unless the owner sets one.
+pub const DEFAULT_STORAGE_CLEANUP_REWARD_PERCENT: u8 = 0;
+
+/// Key for storing the state of the engine, see `VersionedEngineState`.
+const STATE_KEY: &[u8; 15] = b"VERSIONED_STATE";
 
-/// Key for storing the state of the engine.
-const STATE_KEY: &[u8; 5] = b"STATE";
+/// Key of the state stored before its layout was versioned, only read by
+/// `Engine::migrate_state`.
+const UNVERSIONED_STATE_KEY: &[u8; 5] = b"STATE";
+
+/// Key for storing the properties of the replayed block, see `Engine::set_begin_block`.
+#[cfg(feature = "evm_bully")]
+const BLOCK_KEY: &[u8; 5] = b"BLOCK";
+
+/// Key prefix for storing the hashes of the replayed blocks, by number.
+#[cfg(feature = "evm_bully")]
+const BLOCK_HASH_KEY: &[u8; 10] = b"BLOCK_HASH";
 
 impl Engine {
     pub fn new(origin: Address) -> Result<Self, EngineStateError> {
@@ -302,14 +688,42 @@ impl Engine {
     }
 
     pub fn new_with_state(state: EngineState, origin: Address) -> Self {
-        Self { state, origin }
+        Self {
+            state,
+            origin,
+            transaction_hash: None,
+            transaction_type: None,
+            applied_storage_usage: None,
+            #[cfg(feature = "evm_bully")]
+            block: RefCell::new(None),
+        }
+    }
+
+    /// Sets the hash of the Ethereum transaction being executed, for use in log events.
+    pub fn set_transaction_hash(&mut self, transaction_hash: H256) {
+        self.transaction_hash = Some(transaction_hash);
+    }
+
+    /// Sets the type of the Ethereum transaction being executed, for the encoding of its receipt.
+    pub fn set_transaction_type(&mut self, transaction_type: Option<u8>) {
+        self.transaction_type = transaction_type;
+    }
+
+    /// Returns the storage usage before and after the changes made to the EVM state by the
+    /// execution were applied, if they were. This includes the records kept for the accounts
+    /// and slots changed, such as the account index and the state commitment, but not the
+    /// records kept for each block, such as the receipts.
+    pub fn applied_storage_usage(&self) -> Option<(u64, u64)> {
+        self.applied_storage_usage
     }
 
     /// Saves state into the storage.
     pub fn set_state(state: EngineState) {
         sdk::write_storage(
             &bytes_to_key(KeyPrefix::Config, STATE_KEY),
-            &state.try_to_vec().expect("ERR_SER"),
+            &VersionedEngineState::V1(state)
+                .try_to_vec()
+                .expect("ERR_SER"),
         );
     }
 
@@ -336,16 +750,292 @@ impl Engine {
         data.extend_from_slice(account_id);
         data.extend_from_slice(&block_height.to_be_bytes());
 
+        Self::sha256(&data)
+    }
+
+    /// Computes the hash recorded for a block in the `BlockHashMode::Recorded` mode.
+    /// Unlike `compute_block_hash`, it also depends on data of the NEAR block:
+    /// ```text
+    /// block_hash = sha256(concat(
+    ///     RECORDED_BLOCK_HASH_PREFIX,
+    ///     chain_id,
+    ///     engine_account_id,
+    ///     block_height as u64,
+    ///     block_timestamp_ns as u64,
+    ///     random_seed,
+    /// ))
+    /// ```
+    pub fn compute_recorded_block_hash(
+        chain_id: [u8; 32],
+        block_height: u64,
+        account_id: &[u8],
+        block_timestamp_ns: u64,
+        random_seed: &[u8],
+    ) -> H256 {
+        let mut data = Vec::with_capacity(
+            BLOCK_HASH_PREFIX_SIZE
+                + CHAIN_ID_SIZE
+                + account_id.len()
+                + 2 * BLOCK_HEIGHT_SIZE
+                + random_seed.len(),
+        );
+        data.push(RECORDED_BLOCK_HASH_PREFIX);
+        data.extend_from_slice(&chain_id);
+        data.extend_from_slice(account_id);
+        data.extend_from_slice(&block_height.to_be_bytes());
+        data.extend_from_slice(&block_timestamp_ns.to_be_bytes());
+        data.extend_from_slice(random_seed);
+
+        Self::sha256(&data)
+    }
+
+    fn sha256(data: &[u8]) -> H256 {
         #[cfg(not(feature = "contract"))]
         {
             use sha2::Digest;
 
-            let output = sha2::Sha256::digest(&data);
+            let output = sha2::Sha256::digest(data);
             H256(output.into())
         }
 
         #[cfg(feature = "contract")]
-        sdk::sha256(&data)
+        sdk::sha256(data)
+    }
+
+    /// Records the hash of the current block if the engine is in the `BlockHashMode::Recorded`
+    /// mode and it was not recorded yet, overwriting the hash recorded `BLOCK_HASH_HISTORY`
+    /// blocks earlier.
+    pub fn record_block_hash(&self) {
+        if self.state.block_hash_mode != BlockHashMode::Recorded {
+            return;
+        }
+        let block_height = sdk::block_index();
+        if Self::get_recorded_block_hash(block_height).is_some() {
+            return;
+        }
+        let block_hash = Self::compute_recorded_block_hash(
+            self.state.chain_id,
+            block_height,
+            &sdk::current_account_id(),
+            sdk::block_timestamp(),
+            &sdk::random_seed(),
+        );
+        let mut value = [0u8; BLOCK_HEIGHT_SIZE + 32];
+        value[..BLOCK_HEIGHT_SIZE].copy_from_slice(&block_height.to_le_bytes());
+        value[BLOCK_HEIGHT_SIZE..].copy_from_slice(block_hash.as_bytes());
+        sdk::write_storage(&Self::block_hash_key(block_height), &value);
+    }
+
+    /// Returns the hash recorded for the given block height, if it is still stored.
+    pub fn get_recorded_block_hash(block_height: u64) -> Option<H256> {
+        let value = sdk::read_storage(&Self::block_hash_key(block_height))?;
+        if value.len() != BLOCK_HEIGHT_SIZE + 32 {
+            return None;
+        }
+        let mut recorded_height = [0u8; BLOCK_HEIGHT_SIZE];
+        recorded_height.copy_from_slice(&value[..BLOCK_HEIGHT_SIZE]);
+        if u64::from_le_bytes(recorded_height) != block_height {
+            return None;
+        }
+        Some(H256::from_slice(&value[BLOCK_HEIGHT_SIZE..]))
+    }
+
+    /// Sets the properties of the Ethereum block being replayed, which the EVM then sees
+    /// instead of the ones of the NEAR block. The hash of the block is recorded for `BLOCKHASH`
+    /// in the following blocks.
+    #[cfg(feature = "evm_bully")]
+    pub fn set_begin_block(args: &BeginBlockArgs) {
+        sdk::write_storage(
+            &bytes_to_key(KeyPrefix::Config, BLOCK_KEY),
+            &args.try_to_vec().expect("ERR_SER"),
+        );
+        let number = U256::from_big_endian(&args.number).low_u64();
+        let mut value = [0u8; BLOCK_HEIGHT_SIZE + 32];
+        value[..BLOCK_HEIGHT_SIZE].copy_from_slice(&number.to_le_bytes());
+        value[BLOCK_HEIGHT_SIZE..].copy_from_slice(&args.hash);
+        sdk::write_storage(&Self::begin_block_hash_key(number), &value);
+    }
+
+    /// Imports an account of a genesis state. Only the given fields and storage slots are set.
+    #[cfg(feature = "evm_bully")]
+    pub fn import_genesis_account(account: GenesisAccount) {
+        let address = Address(account.address);
+        let mut record = Self::get_account(&address);
+        if let Some(balance) = account.balance {
+            record.balance = u256_to_arr(&U256::from(balance));
+        }
+        if let Some(nonce) = account.nonce {
+            record.nonce = u256_to_arr(&U256::from(nonce));
+        }
+        if let Some(code) = account.code.as_ref() {
+            Self::replace_code(&address, &mut record, code);
+        }
+        Self::set_account(&address, &record);
+        let generation = record.generation;
+        let storage: Vec<(H256, H256)> = account
+            .storage
+            .into_iter()
+            .map(|(key, value)| (H256(key), H256(value)))
+            .collect();
+        for (key, value) in storage.iter() {
+            if value.is_zero() {
+                Self::remove_storage(&address, key, generation);
+            } else {
+                Self::set_storage(&address, key, value, generation);
+            }
+        }
+        let code_hash = account.code.as_ref().map(|_| H256(record.code_hash));
+        state_commitment::update_account(&address, code_hash, false, &storage);
+    }
+
+    /// Returns the properties of the Ethereum block being replayed, if any.
+    #[cfg(feature = "evm_bully")]
+    pub fn get_begin_block() -> Option<BeginBlockArgs> {
+        sdk::read_storage(&bytes_to_key(KeyPrefix::Config, BLOCK_KEY))
+            .map(|value| BeginBlockArgs::try_from_slice(&value).expect("ERR_DESER"))
+    }
+
+    /// Same as `get_begin_block`, read once per engine.
+    #[cfg(feature = "evm_bully")]
+    fn begin_block(&self) -> Option<BeginBlockArgs> {
+        self.block
+            .borrow_mut()
+            .get_or_insert_with(Self::get_begin_block)
+            .clone()
+    }
+
+    /// Returns the hash of a replayed block among the `BLOCK_HASH_HISTORY` ones before the
+    /// current replayed block, or zero.
+    #[cfg(feature = "evm_bully")]
+    fn get_begin_block_hash(current_number: u64, number: u64) -> H256 {
+        if number >= current_number || number < current_number.saturating_sub(BLOCK_HASH_HISTORY) {
+            return H256::zero();
+        }
+        match sdk::read_storage(&Self::begin_block_hash_key(number)) {
+            Some(value) if value[..BLOCK_HEIGHT_SIZE] == number.to_le_bytes() => {
+                H256::from_slice(&value[BLOCK_HEIGHT_SIZE..])
+            }
+            _ => H256::zero(),
+        }
+    }
+
+    #[cfg(feature = "evm_bully")]
+    fn begin_block_hash_key(number: u64) -> Vec<u8> {
+        bytes_to_key(
+            KeyPrefix::Config,
+            &[
+                &BLOCK_HASH_KEY[..],
+                &(number % BLOCK_HASH_HISTORY).to_le_bytes(),
+            ]
+            .concat(),
+        )
+    }
+
+    fn block_hash_key(block_height: u64) -> Vec<u8> {
+        bytes_to_key(
+            KeyPrefix::BlockHash,
+            &(block_height % BLOCK_HASH_HISTORY).to_le_bytes(),
+        )
+    }
+
+    /// Adds the receipt of a transaction to the `BlockReceiptsSummary` of the current block,
+    /// overwriting the summary recorded `BLOCK_HASH_HISTORY` blocks earlier.
+    ///
+    /// Only the trie of the receipts of the latest block is stored, to insert the following
+    /// receipts of the block into it.
+    fn record_receipt(&self, status: &TransactionStatus, gas_used: u64, logs: &[Log]) {
+        let block_height = sdk::block_index();
+        let mut store = Self::block_receipts_node_store();
+        let mut summary = Self::get_block_receipts_summary(block_height);
+        if summary.receipts_count == 0 {
+            let latest_key = bytes_to_key(KeyPrefix::BlockReceipts, &[LATEST_RECEIPTS_BLOCK_ID]);
+            if let Ok(latest_height) = sdk::read_u64(&latest_key) {
+                let latest_summary = Self::get_block_receipts_summary(latest_height);
+                trie::clear(&mut store, &H256(latest_summary.receipts_root));
+            }
+            sdk::write_storage(&latest_key, &block_height.to_le_bytes());
+        }
+
+        let logs_bloom = receipt::logs_bloom(logs);
+        for (a, b) in summary.logs_bloom.iter_mut().zip(logs_bloom.iter()) {
+            *a |= b;
+        }
+        summary.gas_used = summary.gas_used.saturating_add(gas_used);
+        let encoded_receipt = receipt::encode_receipt(
+            self.transaction_type,
+            status.is_ok(),
+            summary.gas_used,
+            &logs_bloom,
+            logs,
+        );
+        summary.receipts_root = trie::insert(
+            &mut store,
+            &H256(summary.receipts_root),
+            &rlp::encode(&summary.receipts_count),
+            encoded_receipt,
+        )
+        .0;
+        summary.receipts_count += 1;
+
+        let mut value = block_height.to_le_bytes().to_vec();
+        summary.serialize(&mut value).expect("ERR_SER");
+        sdk::write_storage(&Self::block_receipts_summary_key(block_height), &value);
+    }
+
+    /// Returns the `BlockReceiptsSummary` of the given block, which is empty if no
+    /// transaction was executed in it or if it is no longer stored.
+    pub fn get_block_receipts_summary(block_height: u64) -> BlockReceiptsSummary {
+        sdk::read_storage(&Self::block_receipts_summary_key(block_height))
+            .filter(|value| {
+                value.len() > BLOCK_HEIGHT_SIZE
+                    && value[..BLOCK_HEIGHT_SIZE] == block_height.to_le_bytes()
+            })
+            .and_then(|value| {
+                BlockReceiptsSummary::try_from_slice(&value[BLOCK_HEIGHT_SIZE..]).ok()
+            })
+            .unwrap_or_default()
+    }
+
+    fn block_receipts_summary_key(block_height: u64) -> Vec<u8> {
+        let mut key = [0u8; 1 + BLOCK_HEIGHT_SIZE];
+        key[0] = BLOCK_RECEIPTS_SUMMARY_ID;
+        key[1..].copy_from_slice(&(block_height % BLOCK_HASH_HISTORY).to_le_bytes());
+        bytes_to_key(KeyPrefix::BlockReceipts, &key)
+    }
+
+    fn block_receipts_node_store() -> StorageNodeStore {
+        StorageNodeStore {
+            prefix: KeyPrefix::BlockReceipts,
+            node_id: BLOCK_RECEIPTS_NODE_ID,
+            refcount_id: BLOCK_RECEIPTS_NODE_REFCOUNT_ID,
+        }
+    }
+
+    /// Returns the hash of the given block according to the configured `BlockHashMode`,
+    /// or zero if the block is not one of the `BLOCK_HASH_HISTORY` blocks preceding the
+    /// current one.
+    pub fn get_block_hash(&self, block_height: u64) -> H256 {
+        let current_height = sdk::block_index();
+        if block_height >= current_height
+            || block_height < current_height.saturating_sub(BLOCK_HASH_HISTORY)
+        {
+            return H256::zero();
+        }
+        match self.state.block_hash_mode {
+            BlockHashMode::Derived => {
+                #[cfg(feature = "contract")]
+                {
+                    let account_id = sdk::current_account_id();
+                    Self::compute_block_hash(self.state.chain_id, block_height, &account_id)
+                }
+
+                #[cfg(not(feature = "contract"))]
+                Self::compute_block_hash(self.state.chain_id, block_height, b"aurora")
+            }
+            BlockHashMode::Recorded => {
+                Self::get_recorded_block_hash(block_height).unwrap_or_default()
+            }
+        }
     }
 
     pub fn charge_gas_limit(
@@ -369,10 +1059,84 @@ impl Engine {
             .ok_or(GasPaymentError::OutOfFund)?;
 
         Self::set_balance(sender, &remaining_balance);
+        state_commitment::touch_account(sender);
 
         Ok(payment_for_gas)
     }
 
+    /// Reserves the payment for the storage a transaction may use, at the given price per byte,
+    /// as is done for gas: a transaction uses at most one byte per `GAS_PER_STORAGE_BYTE` units
+    /// of its gas limit. The payment is then settled by `charge_storage`.
+    pub fn reserve_storage(
+        sender: &Address,
+        gas_limit: U256,
+        byte_price: U256,
+    ) -> Result<Wei, StoragePaymentError> {
+        let reserved_amount = Wei::new(
+            byte_price
+                .checked_mul(gas_limit / GAS_PER_STORAGE_BYTE)
+                .ok_or(StoragePaymentError::EthAmountOverflow)?,
+        );
+        if reserved_amount.is_zero() {
+            return Ok(reserved_amount);
+        }
+        let remaining_balance = Self::get_balance(sender)
+            .checked_sub(reserved_amount)
+            .ok_or(StoragePaymentError::OutOfFund)?;
+        Self::set_balance(sender, &remaining_balance);
+        state_commitment::touch_account(sender);
+        Ok(reserved_amount)
+    }
+
+    /// Charges the sender of a transaction for the storage used by applying its changes to the
+    /// EVM state (see `Engine::applied_storage_usage`) out of the payment reserved by
+    /// `reserve_storage`, by moving the amount to the engine address, and refunds the rest.
+    /// The storage used beyond the reserved payment is not charged. The storage freed by the
+    /// transaction is refunded from the engine address instead, up to its balance.
+    pub fn charge_storage(
+        sender: &Address,
+        reserved_amount: Wei,
+        storage_usage_before: u64,
+        storage_usage_after: u64,
+        byte_price: U256,
+    ) -> Result<(), StoragePaymentError> {
+        let engine_address = current_address();
+        if storage_usage_after >= storage_usage_before {
+            let payment_for_storage = byte_price
+                .saturating_mul(U256::from(storage_usage_after - storage_usage_before))
+                .min(reserved_amount.raw());
+            let payment_for_storage = Wei::new(payment_for_storage);
+            Self::add_balance(sender, reserved_amount - payment_for_storage)?;
+            if !payment_for_storage.is_zero() {
+                Self::add_balance(&engine_address, payment_for_storage)?;
+            }
+            Ok(())
+        } else {
+            let refund = byte_price
+                .saturating_mul(U256::from(storage_usage_before - storage_usage_after))
+                .min(Self::get_balance(&engine_address).raw());
+            Self::add_balance(sender, reserved_amount)?;
+            Self::transfer_balance(&engine_address, sender, Wei::new(refund))
+        }
+    }
+
+    fn transfer_balance(
+        sender: &Address,
+        receiver: &Address,
+        amount: Wei,
+    ) -> Result<(), StoragePaymentError> {
+        if amount.is_zero() {
+            return Ok(());
+        }
+        let remaining_balance = Self::get_balance(sender)
+            .checked_sub(amount)
+            .ok_or(StoragePaymentError::OutOfFund)?;
+        Self::set_balance(sender, &remaining_balance);
+        state_commitment::touch_account(sender);
+        Self::add_balance(receiver, amount)?;
+        Ok(())
+    }
+
     pub fn refund_unused_gas(
         sender: &Address,
         relayer: &Address,
@@ -404,36 +1168,162 @@ impl Engine {
     pub fn get_state() -> Result<EngineState, EngineStateError> {
         match sdk::read_storage(&bytes_to_key(KeyPrefix::Config, STATE_KEY)) {
             None => Err(EngineStateError::NotFound),
-            Some(bytes) => EngineState::try_from_slice(&bytes)
-                .map_err(|_| EngineStateError::DeserializationFailed),
+            Some(bytes) => match VersionedEngineState::try_from_slice(&bytes) {
+                Ok(VersionedEngineState::V1(state)) => Ok(state),
+                Err(_) => Err(EngineStateError::DeserializationFailed),
+            },
+        }
+    }
+
+    /// Rewrites a state stored before its layout was versioned (see
+    /// `decode_unversioned_state`) with the current layout. Does nothing if the state is
+    /// already versioned.
+    pub fn migrate_state() -> Result<(), EngineStateError> {
+        if sdk::storage_has_key(&bytes_to_key(KeyPrefix::Config, STATE_KEY)) {
+            return Ok(());
+        }
+        let unversioned_key = bytes_to_key(KeyPrefix::Config, UNVERSIONED_STATE_KEY);
+        let bytes = sdk::read_storage(&unversioned_key).ok_or(EngineStateError::NotFound)?;
+        Self::set_state(decode_unversioned_state(&bytes)?);
+        sdk::remove_storage(&unversioned_key);
+        Ok(())
+    }
+
+    /// Returns the hard fork used to execute transactions in the current block.
+    pub fn hard_fork(&self) -> HardForkId {
+        self.state.hard_fork_at(sdk::block_index())
+    }
+
+    /// Returns the record of an account. Accounts without a record are read from the keys
+    /// they were stored under before, until all accounts are migrated (see
+    /// `account_migration`).
+    pub fn get_account(address: &Address) -> Account {
+        match sdk::read_storage(&address_to_key(KeyPrefix::Account, address)) {
+            Some(value) => Account::try_from_slice(&value).expect("ERR_DESER"),
+            None if account_migration::is_done() => Account::default(),
+            None => account_migration::legacy_account(address),
+        }
+    }
+
+    pub fn set_account(address: &Address, account: &Account) {
+        let key = address_to_key(KeyPrefix::Account, address);
+        let resolved_account;
+        let account = if account.code_hash == account_migration::LEGACY_CODE_HASH.0 {
+            resolved_account = Account {
+                code_hash: account_migration::legacy_code_hash(address).0,
+                ..account.clone()
+            };
+            &resolved_account
+        } else {
+            account
+        };
+        if *account == Account::default() {
+            sdk::remove_storage(&key);
+            // Otherwise the account would be read from its former keys again.
+            if !account_migration::is_done() {
+                account_migration::remove_legacy_keys(address);
+            }
+        } else {
+            sdk::write_storage(&key, &account.try_to_vec().expect("ERR_SER"));
+            account_index::insert(address);
         }
     }
 
     pub fn set_code(address: &Address, code: &[u8]) {
-        sdk::write_storage(&address_to_key(KeyPrefix::Code, address), code);
+        let mut account = Self::get_account(address);
+        Self::replace_code(address, &mut account, code);
+        Self::set_account(address, &account);
+    }
+
+    /// Sets the code of an account record, which is then to be written.
+    ///
+    /// Codes are stored once by hash, with the number of accounts using them, so that the
+    /// accounts with the same code (e.g. deployed by a factory) share it.
+    fn replace_code(address: &Address, account: &mut Account, code: &[u8]) {
+        let code_hash = keccak(code);
+        if code_hash.0 != account.code_hash {
+            Self::release_code(address, &H256(account.code_hash));
+            Self::retain_code(&code_hash, code);
+            account.code_hash = code_hash.0;
+        }
     }
 
-    pub fn remove_code(address: &Address) {
-        sdk::remove_storage(&address_to_key(KeyPrefix::Code, address))
+    /// Counts one more account using a code, and stores the code if it is the first one.
+    pub(crate) fn retain_code(code_hash: &H256, code: &[u8]) {
+        if *code_hash == EMPTY_CODE_HASH {
+            return;
+        }
+        let key = Self::code_refcount_key(code_hash);
+        let refcount = sdk::read_u64(&key).unwrap_or(0);
+        if refcount == 0 {
+            sdk::write_storage(&Self::code_key(code_hash), code);
+        }
+        sdk::write_storage(&key, &(refcount + 1).to_le_bytes());
+    }
+
+    /// Counts one less account using a code, which is removed once no account uses it.
+    fn release_code(address: &Address, code_hash: &H256) {
+        if *code_hash == EMPTY_CODE_HASH {
+            return;
+        }
+        // Codes stored under the address of their account are not counted.
+        if !account_migration::is_done() && account_migration::remove_legacy_code(address) {
+            return;
+        }
+        let key = Self::code_refcount_key(code_hash);
+        match sdk::read_u64(&key).unwrap_or(0) {
+            0 | 1 => {
+                sdk::remove_storage(&Self::code_key(code_hash));
+                sdk::remove_storage(&key);
+            }
+            refcount => sdk::write_storage(&key, &(refcount - 1).to_le_bytes()),
+        }
     }
 
     pub fn get_code(address: &Address) -> Vec<u8> {
-        sdk::read_storage(&address_to_key(KeyPrefix::Code, address)).unwrap_or_else(Vec::new)
+        let code_hash = H256(Self::get_account(address).code_hash);
+        if code_hash == EMPTY_CODE_HASH {
+            return Vec::new();
+        }
+        if code_hash == account_migration::LEGACY_CODE_HASH {
+            return account_migration::legacy_code(address).unwrap_or_else(Vec::new);
+        }
+        sdk::read_storage(&Self::code_key(&code_hash))
+            .or_else(|| account_migration::legacy_code(address))
+            .unwrap_or_else(Vec::new)
     }
 
-    pub fn get_code_size(address: &Address) -> usize {
-        sdk::read_storage_len(&address_to_key(KeyPrefix::Code, address)).unwrap_or(0)
+    pub fn get_code_hash(address: &Address) -> H256 {
+        Self::account_code_hash(address, &Self::get_account(address))
     }
 
-    pub fn set_nonce(address: &Address, nonce: &U256) {
-        sdk::write_storage(
-            &address_to_key(KeyPrefix::Nonce, address),
-            &u256_to_arr(nonce),
-        );
+    /// Returns the hash of the code of an account, given its record.
+    pub fn account_code_hash(address: &Address, account: &Account) -> H256 {
+        if account.code_hash == account_migration::LEGACY_CODE_HASH.0 {
+            account_migration::legacy_code_hash(address)
+        } else {
+            H256(account.code_hash)
+        }
+    }
+
+    fn code_key(code_hash: &H256) -> Vec<u8> {
+        bytes_to_key(
+            KeyPrefix::CodeByHash,
+            &[&[CODE_ID][..], code_hash.as_bytes()].concat(),
+        )
+    }
+
+    fn code_refcount_key(code_hash: &H256) -> Vec<u8> {
+        bytes_to_key(
+            KeyPrefix::CodeByHash,
+            &[&[CODE_REFCOUNT_ID][..], code_hash.as_bytes()].concat(),
+        )
     }
 
-    pub fn remove_nonce(address: &Address) {
-        sdk::remove_storage(&address_to_key(KeyPrefix::Nonce, address))
+    pub fn set_nonce(address: &Address, nonce: &U256) {
+        let mut account = Self::get_account(address);
+        account.nonce = u256_to_arr(nonce);
+        Self::set_account(address, &account);
     }
 
     /// Checks the nonce to ensure that the address matches the transaction
@@ -450,37 +1340,25 @@ impl Engine {
     }
 
     pub fn get_nonce(address: &Address) -> U256 {
-        sdk::read_storage(&address_to_key(KeyPrefix::Nonce, address))
-            .map(|value| U256::from_big_endian(&value))
-            .unwrap_or_else(U256::zero)
+        Self::get_account(address).nonce()
     }
 
     pub fn add_balance(address: &Address, amount: Wei) -> Result<(), BalanceOverflow> {
         let current_balance = Self::get_balance(address);
         let new_balance = current_balance.checked_add(amount).ok_or(BalanceOverflow)?;
         Self::set_balance(address, &new_balance);
+        state_commitment::touch_account(address);
         Ok(())
     }
 
     pub fn set_balance(address: &Address, balance: &Wei) {
-        sdk::write_storage(
-            &address_to_key(KeyPrefix::Balance, address),
-            &balance.to_bytes(),
-        );
-    }
-
-    pub fn remove_balance(address: &Address) {
-        let balance = Self::get_balance(address);
-        // Apply changes for eth-conenctor
-        EthConnectorContract::get_instance().internal_remove_eth(address, &balance.raw());
-        sdk::remove_storage(&address_to_key(KeyPrefix::Balance, address))
+        let mut account = Self::get_account(address);
+        account.balance = balance.to_bytes();
+        Self::set_account(address, &account);
     }
 
     pub fn get_balance(address: &Address) -> Wei {
-        let raw = sdk::read_storage(&address_to_key(KeyPrefix::Balance, address))
-            .map(|value| U256::from_big_endian(&value))
-            .unwrap_or_else(U256::zero);
-        Wei::new(raw)
+        Self::get_account(address).balance()
     }
 
     pub fn remove_storage(address: &Address, key: &H256, generation: u32) {
@@ -488,7 +1366,11 @@ impl Engine {
     }
 
     pub fn set_storage(address: &Address, key: &H256, value: &H256, generation: u32) {
-        sdk::write_storage(storage_to_key(address, key, generation).as_ref(), &value.0);
+        let storage_key = storage_to_key(address, key, generation);
+        if !sdk::storage_has_key(storage_key.as_ref()) {
+            storage_cleanup::record_slot(address, key, generation);
+        }
+        sdk::write_storage(storage_key.as_ref(), &value.0);
     }
 
     pub fn get_storage(address: &Address, key: &H256, generation: u32) -> H256 {
@@ -498,49 +1380,42 @@ impl Engine {
     }
 
     pub fn is_account_empty(address: &Address) -> bool {
-        let balance = Self::get_balance(address);
-        let nonce = Self::get_nonce(address);
-        let code_len = Self::get_code_size(address);
-        balance.is_zero() && nonce.is_zero() && code_len == 0
+        Self::get_account(address).is_empty()
     }
 
     /// Increments storage generation for a given address.
     pub fn set_generation(address: &Address, generation: u32) {
-        sdk::write_storage(
-            &address_to_key(KeyPrefix::Generation, address),
-            &generation.to_be_bytes(),
-        );
+        let mut account = Self::get_account(address);
+        account.generation = generation;
+        Self::set_account(address, &account);
     }
 
     pub fn get_generation(address: &Address) -> u32 {
-        sdk::read_storage(&address_to_key(KeyPrefix::Generation, address))
-            .map(|value| {
-                let mut bytes = [0u8; 4];
-                bytes[0..4].copy_from_slice(&value[0..4]);
-                u32::from_be_bytes(bytes)
-            })
-            .unwrap_or(0)
+        Self::get_account(address).generation
     }
 
-    /// Removes all storage for the given address.
-    fn remove_all_storage(address: &Address, generation: u32) {
-        // FIXME: there is presently no way to prefix delete trie state.
-        // NOTE: There is not going to be a method on runtime for this.
-        //     You may need to store all keys in a list if you want to do this in a contract.
-        //     Maybe you can incentivize people to delete dead old keys. They can observe them from
-        //     external indexer node and then issue special cleaning transaction.
-        //     Either way you may have to store the nonce per storage address root. When the account
-        //     has to be deleted the storage nonce needs to be increased, and the old nonce keys
-        //     can be deleted over time. That's how TurboGeth does storage.
-        Self::set_generation(address, generation + 1);
-    }
-
-    /// Removes an account.
-    fn remove_account(address: &Address, generation: u32) {
-        Self::remove_nonce(address);
-        Self::remove_balance(address);
-        Self::remove_code(address);
-        Self::remove_all_storage(address, generation);
+    /// Removes all storage for the given account record, which is then to be written.
+    ///
+    /// There is no way to delete a prefix of the NEAR state, so the storage generation of the
+    /// account is increased instead, and the slots of the previous generation are removed
+    /// later on by `cleanup_storage` (see `storage_cleanup`).
+    fn remove_all_storage(address: &Address, account: &mut Account) {
+        storage_cleanup::retire_generation(address, account.generation);
+        account.generation += 1;
+    }
+
+    /// Removes an account, given its current record. Only the storage generation of the
+    /// account is kept, so that the storage of the removed account is not used again.
+    fn remove_account(address: &Address, account: &Account) {
+        // Apply changes for eth-connector
+        EthConnectorContract::get_instance().internal_remove_eth(address, &account.balance().raw());
+        Self::release_code(address, &H256(account.code_hash));
+        let mut removed_account = Account {
+            generation: account.generation,
+            ..Account::default()
+        };
+        Self::remove_all_storage(address, &mut removed_account);
+        Self::set_account(address, &removed_account);
     }
 
     pub fn deploy_code_with_input(&mut self, input: Vec<u8>) -> EngineResult<SubmitResult> {
@@ -557,6 +1432,7 @@ impl Engine {
         gas_limit: u64,
         access_list: Vec<(Address, Vec<H256>)>, // See EIP-2930
     ) -> EngineResult<SubmitResult> {
+        self.record_block_hash();
         let mut executor = self.make_executor(gas_limit);
         let address = executor.create_address(CreateScheme::Legacy { caller: origin });
         let (exit_reason, result) = (
@@ -577,11 +1453,10 @@ impl Engine {
         self.apply(values, Vec::<Log>::new(), true);
         Self::schedule_promises(promises);
 
-        Ok(SubmitResult {
-            status,
-            gas_used: used_gas,
-            logs: logs.into_iter().map(Into::into).collect(),
-        })
+        let logs: Vec<Log> = logs.into_iter().collect();
+        self.record_receipt(&status, used_gas, &logs);
+        let logs = self.emit_logs(logs);
+        Ok(SubmitResult::new(status, used_gas, logs))
     }
 
     pub fn call_with_args(&mut self, args: FunctionCallArgs) -> EngineResult<SubmitResult> {
@@ -600,6 +1475,7 @@ impl Engine {
         gas_limit: u64,
         access_list: Vec<(Address, Vec<H256>)>, // See EIP-2930
     ) -> EngineResult<SubmitResult> {
+        self.record_block_hash();
         let mut executor = self.make_executor(gas_limit);
         let (exit_reason, result) =
             executor.transact_call(origin, contract, value.raw(), input, gas_limit, access_list);
@@ -615,29 +1491,59 @@ impl Engine {
 
         let (values, logs, promises) = executor.into_state().deconstruct();
 
-        // There is no way to return the logs to the NEAR log method as it only
-        // allows a return of UTF-8 strings.
+        // The logs are not given to `apply` since they are returned in the result
+        // (and optionally written as NEAR logs by `emit_logs`).
         self.apply(values, Vec::<Log>::new(), true);
         Self::schedule_promises(promises);
 
-        Ok(SubmitResult {
-            status,
-            gas_used: used_gas,
-            logs: logs.into_iter().map(Into::into).collect(),
-        })
+        let logs: Vec<Log> = logs.into_iter().collect();
+        self.record_receipt(&status, used_gas, &logs);
+        let logs = self.emit_logs(logs);
+        Ok(SubmitResult::new(status, used_gas, logs))
+    }
+
+    /// Writes the logs as NEAR logs if `evm_log_events` is set, and converts them for use
+    /// in a `SubmitResult`.
+    fn emit_logs(&self, logs: Vec<Log>) -> Vec<ResultLog> {
+        if self.state.evm_log_events {
+            evm_event::emit_evm_logs(&logs, self.transaction_hash);
+        }
+        logs.into_iter().map(Into::into).collect()
     }
 
     pub fn increment_nonce(address: &Address) {
         let account_nonce = Self::get_nonce(address);
         let new_nonce = account_nonce.saturating_add(U256::one());
         Self::set_nonce(address, &new_nonce);
+        state_commitment::touch_account(address);
     }
 
     pub fn view_with_args(&self, args: ViewCallArgs) -> Result<TransactionStatus, EngineErrorKind> {
+        self.view_with_args_v2(args.into())
+    }
+
+    pub fn view_with_args_v2(
+        &self,
+        args: ViewCallArgsV2,
+    ) -> Result<TransactionStatus, EngineErrorKind> {
+        self.view_result_with_args(args).map(|result| result.status)
+    }
+
+    pub fn view_result_with_args(
+        &self,
+        args: ViewCallArgsV2,
+    ) -> Result<SubmitResult, EngineErrorKind> {
         let origin = Address::from_slice(&args.sender);
         let contract = Address::from_slice(&args.address);
         let value = U256::from_big_endian(&args.amount);
-        self.view(origin, contract, Wei::new(value), args.input, u64::MAX)
+        self.view_with_overrides(
+            origin,
+            contract,
+            Wei::new(value),
+            args.input,
+            u64::MAX,
+            &args.state_override,
+        )
     }
 
     pub fn view(
@@ -648,16 +1554,226 @@ impl Engine {
         input: Vec<u8>,
         gas_limit: u64,
     ) -> Result<TransactionStatus, EngineErrorKind> {
+        self.view_with_overrides(origin, contract, value, input, gas_limit, &[])
+            .map(|result| result.status)
+    }
+
+    /// Same as `view`, with parts of the state replaced for the duration of the call,
+    /// returning the gas used and the logs along with the status.
+    pub fn view_with_overrides(
+        &self,
+        origin: Address,
+        contract: Address,
+        value: Wei,
+        input: Vec<u8>,
+        gas_limit: u64,
+        state_override: &[StateOverride],
+    ) -> Result<SubmitResult, EngineErrorKind> {
+        self.simulate(
+            origin,
+            contract,
+            value,
+            input,
+            gas_limit,
+            Vec::new(),
+            state_override,
+        )
+    }
+
+    pub fn estimate_gas_with_args(
+        &self,
+        args: EstimateGasArgs,
+    ) -> Result<EstimateGasResult, EngineErrorKind> {
+        let origin = Address::from_slice(&args.sender);
+        let contract = Address::from_slice(&args.address);
+        let value = U256::from_big_endian(&args.amount);
+        let gas_cap = args
+            .gas_cap
+            .or(self.state.block_gas_limit)
+            .unwrap_or(DEFAULT_ESTIMATE_GAS_CAP);
+        self.estimate_gas(origin, contract, Wei::new(value), args.input, gas_cap)
+    }
+
+    /// Finds the lowest gas limit, up to `gas_cap`, with which the call succeeds.
+    ///
+    /// The gas used by a successful execution is a lower bound, but it may not be enough
+    /// because of gas refunds, the 63/64 rule for nested calls, or the SSTORE gas stipend check.
+    /// In that case the limit is doubled until the call succeeds, and then found by a binary
+    /// search, assuming that the call keeps succeeding with higher gas limits.
+    pub fn estimate_gas(
+        &self,
+        origin: Address,
+        contract: Address,
+        value: Wei,
+        input: Vec<u8>,
+        gas_cap: u64,
+    ) -> Result<EstimateGasResult, EngineErrorKind> {
+        let result = self.simulate(
+            origin,
+            contract,
+            value,
+            input.clone(),
+            gas_cap,
+            Vec::new(),
+            &[],
+        )?;
+        if !result.status.is_ok() {
+            return Ok(EstimateGasResult::Failure(result.status));
+        }
+        let gas_used = result.gas_used;
+
+        let succeeds = |gas_limit: u64| {
+            matches!(
+                self.simulate(origin, contract, value, input.clone(), gas_limit, Vec::new(), &[]),
+                Ok(result) if result.status.is_ok()
+            )
+        };
+        if succeeds(gas_used) {
+            return Ok(EstimateGasResult::Success(gas_used));
+        }
+
+        // Invariant: the call fails with `low` gas and succeeds with `high` gas.
+        let (mut low, mut high) = (gas_used, gas_cap);
+        loop {
+            let limit = low.saturating_mul(2).max(low + 1);
+            if limit >= gas_cap {
+                break;
+            }
+            if succeeds(limit) {
+                high = limit;
+                break;
+            }
+            low = limit;
+        }
+        while high - low > 1 {
+            let middle = low + (high - low) / 2;
+            if succeeds(middle) {
+                high = middle;
+            } else {
+                low = middle;
+            }
+        }
+        Ok(EstimateGasResult::Success(high))
+    }
+
+    /// Executes a call without applying its changes.
+    fn simulate(
+        &self,
+        origin: Address,
+        contract: Address,
+        value: Wei,
+        input: Vec<u8>,
+        gas_limit: u64,
+        access_list: Vec<(Address, Vec<H256>)>, // See EIP-2930
+        state_override: &[StateOverride],
+    ) -> Result<SubmitResult, EngineErrorKind> {
+        let mut executor = self.make_executor(gas_limit);
+        executor.state_mut().apply_overrides(state_override);
+        let (exit_reason, result) =
+            executor.transact_call(origin, contract, value.raw(), input, gas_limit, access_list);
+        let gas_used = executor.used_gas();
+        let status = exit_reason.into_result(result)?;
+        let (_, logs, _) = executor.into_state().deconstruct();
+        let logs = logs.into_iter().map(Into::into).collect();
+        Ok(SubmitResult::new(status, gas_used, logs))
+    }
+
+    pub fn create_access_list_with_args(
+        &self,
+        args: ViewCallArgs,
+    ) -> Result<CreateAccessListResult, EngineErrorKind> {
+        let origin = Address::from_slice(&args.sender);
+        let contract = Address::from_slice(&args.address);
+        let value = U256::from_big_endian(&args.amount);
+        self.create_access_list(origin, contract, Wei::new(value), args.input, u64::MAX)
+    }
+
+    /// Executes a call and returns the EIP-2930 access list of the addresses and storage slots
+    /// it accessed, like `eth_createAccessList`. The sender and the precompiles are left out
+    /// since they are always warm, and so is the called contract unless its storage is accessed.
+    pub fn create_access_list(
+        &self,
+        origin: Address,
+        contract: Address,
+        value: Wei,
+        input: Vec<u8>,
+        gas_limit: u64,
+    ) -> Result<CreateAccessListResult, EngineErrorKind> {
         let mut executor = self.make_executor(gas_limit);
-        let (status, result) =
-            executor.transact_call(origin, contract, value.raw(), input, gas_limit, Vec::new());
-        status.into_result(result)
+        let (status, result) = executor.transact_call(
+            origin,
+            contract,
+            value.raw(),
+            input.clone(),
+            gas_limit,
+            Vec::new(),
+        );
+        let gas_used_without_access_list = executor.used_gas();
+        status.into_result(result)?;
+
+        let mut access_list: BTreeMap<Address, Vec<H256>> = BTreeMap::new();
+        if let Some(accessed) = executor.state().metadata().accessed() {
+            let precompiles = self.hard_fork().precompiles();
+            for address in accessed.accessed_addresses.iter() {
+                if *address != origin
+                    && *address != contract
+                    && !precompiles.addresses().contains(address)
+                {
+                    access_list.entry(*address).or_default();
+                }
+            }
+            for (address, key) in accessed.accessed_storage.iter() {
+                access_list.entry(*address).or_default().push(*key);
+            }
+        }
+        let access_list: Vec<(Address, Vec<H256>)> = access_list.into_iter().collect();
+
+        let result = self.simulate(
+            origin,
+            contract,
+            value,
+            input,
+            gas_limit,
+            access_list.clone(),
+            &[],
+        )?;
+        Ok(CreateAccessListResult {
+            access_list: access_list
+                .into_iter()
+                .map(|(address, storage_keys)| ResultAccessTuple {
+                    address: address.0,
+                    storage_keys: storage_keys.into_iter().map(|key| key.0).collect(),
+                })
+                .collect(),
+            gas_used: result.gas_used,
+            gas_used_without_access_list,
+            status: result.status,
+        })
+    }
+
+    /// Executes a call without applying its changes and returns its JSON encoded trace.
+    #[cfg(feature = "tracing")]
+    pub fn trace_call_with_args(&self, args: TraceCallArgs) -> Vec<u8> {
+        let call = args.call;
+        let origin = Address::from_slice(&call.sender);
+        let contract = Address::from_slice(&call.address);
+        let value = U256::from_big_endian(&call.amount);
+        let gas_limit = u64::MAX;
+        let mut executor = self.make_executor(gas_limit);
+        let (tracer, (reason, result)) = tracing::trace(AnyTracer::new(args.tracer), || {
+            executor.transact_call(origin, contract, value, call.input, gas_limit, Vec::new())
+        });
+        tracer
+            .into_json(&reason, &result, executor.used_gas())
+            .into_bytes()
     }
 
     fn make_executor(&self, gas_limit: u64) -> StackExecutor<AuroraStackState, Precompiles> {
-        let metadata = StackSubstateMetadata::new(gas_limit, CONFIG);
+        let hard_fork = self.hard_fork();
+        let config = hard_fork.config();
+        let metadata = StackSubstateMetadata::new(gas_limit, config);
         let state = AuroraStackState::new(metadata, self);
-        StackExecutor::new_with_precompile(state, CONFIG, Precompiles::new_istanbul())
+        StackExecutor::new_with_precompile(state, config, hard_fork.precompiles())
     }
 
     pub fn register_relayer(&mut self, account_id: &[u8], evm_address: Address) {
@@ -666,7 +1782,6 @@ impl Engine {
             .insert_raw(account_id, evm_address.as_bytes());
     }
 
-    #[allow(dead_code)]
     pub fn get_relayer(&self, account_id: &[u8]) -> Option<Address> {
         self.state
             .relayers_evm_addresses
@@ -852,15 +1967,8 @@ impl evm::backend::Backend for Engine {
 
     /// Returns a block hash from a given index.
     ///
-    /// Currently, this returns
-    /// 0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff if
-    /// only for the 256 most recent blocks, excluding of the current one.
-    /// Otherwise, it returns 0x0.
-    ///
-    /// In other words, if the requested block index is less than the current
-    /// block index, return
-    /// 0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff.
-    /// Otherwise, return 0.
+    /// Only the 256 most recent blocks, excluding the current one, have a hash.
+    /// Otherwise, it returns 0x0. See `Engine::get_block_hash` for how the hash is obtained.
     ///
     /// This functionality may change in the future. Follow
     /// [nearcore#3456](https://github.com/near/nearcore/issues/3456) for more
@@ -868,59 +1976,95 @@ impl evm::backend::Backend for Engine {
     ///
     /// See: https://doc.aurora.dev/develop/compat/evm#blockhash
     fn block_hash(&self, number: U256) -> H256 {
-        let idx = U256::from(sdk::block_index());
-        if idx.saturating_sub(U256::from(256)) <= number && number < idx {
-            // since `idx` comes from `u64` it is always safe to downcast `number` from `U256`
-            #[cfg(feature = "contract")]
-            {
-                let account_id = sdk::current_account_id();
-                Self::compute_block_hash(self.state.chain_id, number.low_u64(), &account_id)
-            }
-
-            #[cfg(not(feature = "contract"))]
-            Self::compute_block_hash(self.state.chain_id, number.low_u64(), b"aurora")
-        } else {
-            H256::zero()
+        if number > U256::from(u64::MAX) {
+            return H256::zero();
+        }
+        #[cfg(feature = "evm_bully")]
+        if let Some(block) = self.begin_block() {
+            let current_number = U256::from_big_endian(&block.number).low_u64();
+            return Self::get_begin_block_hash(current_number, number.low_u64());
         }
+        self.get_block_hash(number.low_u64())
     }
 
     /// Returns the current block index number.
     fn block_number(&self) -> U256 {
+        #[cfg(feature = "evm_bully")]
+        if let Some(block) = self.begin_block() {
+            return U256::from_big_endian(&block.number);
+        }
         U256::from(sdk::block_index())
     }
 
-    /// Returns a mocked coinbase which is the EVM address for the Aurora
-    /// account, being 0x4444588443C3a91288c5002483449Aba1054192b.
+    /// Returns the coinbase according to the configured `CoinbasePolicy`, by default the EVM
+    /// address for the Aurora account (see `DEFAULT_COINBASE`).
     ///
     /// See: https://doc.aurora.dev/develop/compat/evm#coinbase
     fn block_coinbase(&self) -> Address {
-        Address([
-            0x44, 0x44, 0x58, 0x84, 0x43, 0xC3, 0xa9, 0x12, 0x88, 0xc5, 0x00, 0x24, 0x83, 0x44,
-            0x9A, 0xba, 0x10, 0x54, 0x19, 0x2b,
-        ])
+        #[cfg(feature = "evm_bully")]
+        if let Some(block) = self.begin_block() {
+            return Address(block.coinbase);
+        }
+        match self.state.coinbase_policy {
+            CoinbasePolicy::Fixed(address) => Address(address),
+            CoinbasePolicy::Relayer => self
+                .get_relayer(&sdk::predecessor_account_id())
+                .unwrap_or(Address(DEFAULT_COINBASE)),
+        }
     }
 
-    /// Returns the current block timestamp.
+    /// Returns the current block timestamp, in seconds unless the engine is configured
+    /// to return microseconds for compatibility (see `EngineState::block_timestamp_in_seconds`).
     fn block_timestamp(&self) -> U256 {
-        U256::from(sdk::block_timestamp())
+        #[cfg(feature = "evm_bully")]
+        if let Some(block) = self.begin_block() {
+            return U256::from_big_endian(&block.timestamp);
+        }
+        let timestamp_ns = sdk::block_timestamp();
+        if self.state.block_timestamp_in_seconds {
+            U256::from(timestamp_ns / 1_000_000_000)
+        } else {
+            U256::from(timestamp_ns / 1_000)
+        }
     }
 
-    /// Returns the current block difficulty.
+    /// Returns the current block difficulty, which is the random seed of the NEAR block
+    /// (the `PREVRANDAO` value after the Ethereum merge, see EIP-4399).
+    ///
+    /// Note that the seed is known to the NEAR block producer in advance, so it must not
+    /// be relied upon as a source of randomness for anything valuable.
     ///
     /// See: https://doc.aurora.dev/develop/compat/evm#difficulty
     fn block_difficulty(&self) -> U256 {
-        U256::zero()
+        #[cfg(feature = "evm_bully")]
+        if let Some(block) = self.begin_block() {
+            return U256::from_big_endian(&block.difficulty);
+        }
+        U256::from_big_endian(&sdk::random_seed())
     }
 
-    /// Returns the current block gas limit.
+    /// Returns the current base fee for the current block.
     ///
-    /// Currently, this returns 0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff
-    /// as there isn't a gas limit alternative right now but this may change in
-    /// the future.
+    /// Currently, this returns 0 as there is no base fee on Aurora.
+    fn block_base_fee_per_gas(&self) -> U256 {
+        BLOCK_BASE_FEE_PER_GAS
+    }
+
+    /// Returns the configured block gas limit.
+    ///
+    /// Unless configured, this returns
+    /// 0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff
+    /// as there isn't a gas limit alternative.
     ///
     /// See: https://doc.aurora.dev/develop/compat/evm#gaslimit
     fn block_gas_limit(&self) -> U256 {
-        U256::max_value()
+        #[cfg(feature = "evm_bully")]
+        if let Some(block) = self.begin_block() {
+            return U256::from_big_endian(&block.gaslimit);
+        }
+        self.state
+            .block_gas_limit
+            .map_or_else(U256::max_value, U256::from)
     }
 
     /// Returns the states chain ID.
@@ -935,9 +2079,10 @@ impl evm::backend::Backend for Engine {
 
     /// Returns basic account information.
     fn basic(&self, address: Address) -> Basic {
+        let account = Engine::get_account(&address);
         Basic {
-            nonce: Engine::get_nonce(&address),
-            balance: Engine::get_balance(&address).raw(),
+            nonce: account.nonce(),
+            balance: account.balance().raw(),
         }
     }
 
@@ -954,9 +2099,12 @@ impl evm::backend::Backend for Engine {
 
     /// Get original storage value of address at index, if available.
     ///
-    /// Currently, this returns `None` for now.
-    fn original_storage(&self, _address: Address, _index: H256) -> Option<H256> {
-        None
+    /// The engine state is only modified in `ApplyBackend::apply` once the transaction has
+    /// finished executing, so the stored value is the value the slot had before the
+    /// transaction. Slots written or reset during the transaction are tracked by the
+    /// `MemoryStackState` layered on top of the engine.
+    fn original_storage(&self, address: Address, index: H256) -> Option<H256> {
+        Some(self.storage(address, index))
     }
 }
 
@@ -967,6 +2115,7 @@ impl ApplyBackend for Engine {
         I: IntoIterator<Item = (H256, H256)>,
         L: IntoIterator<Item = Log>,
     {
+        let storage_usage = sdk::storage_usage();
         for apply in values {
             match apply {
                 Apply::Modify {
@@ -976,30 +2125,30 @@ impl ApplyBackend for Engine {
                     storage,
                     reset_storage,
                 } => {
-                    let generation = Self::get_generation(&address);
-                    Engine::set_nonce(&address, &basic.nonce);
-
                     // Apply changes for eth-connector
                     EthConnectorContract::get_instance()
                         .internal_set_eth_balance(&address, &basic.balance);
-                    Engine::set_balance(&address, &Wei::new(basic.balance));
 
-                    if let Some(code) = code {
-                        Engine::set_code(&address, &code)
+                    let mut account = Engine::get_account(&address);
+                    let generation = account.generation;
+                    account.nonce = u256_to_arr(&basic.nonce);
+                    account.balance = u256_to_arr(&basic.balance);
+
+                    if let Some(code) = code.as_ref() {
+                        Engine::replace_code(&address, &mut account, code);
                     }
 
-                    let next_generation = if reset_storage {
-                        Engine::remove_all_storage(&address, generation);
-                        generation + 1
-                    } else {
-                        generation
-                    };
+                    if reset_storage {
+                        Engine::remove_all_storage(&address, &mut account);
+                    }
+                    let next_generation = account.generation;
 
-                    for (index, value) in storage {
-                        if value == H256::default() {
-                            Engine::remove_storage(&address, &index, next_generation)
+                    let storage: Vec<(H256, H256)> = storage.into_iter().collect();
+                    for (index, value) in storage.iter() {
+                        if *value == H256::default() {
+                            Engine::remove_storage(&address, index, next_generation)
                         } else {
-                            Engine::set_storage(&address, &index, &value, next_generation)
+                            Engine::set_storage(&address, index, value, next_generation)
                         }
                     }
 
@@ -1008,21 +2157,143 @@ impl ApplyBackend for Engine {
                     // 2. the account is empty
                     // 3. we didn't already clear out the storage (because if we did then there is
                     //    nothing to do)
-                    if delete_empty
-                        && Engine::is_account_empty(&address)
-                        && generation == next_generation
-                    {
-                        Engine::remove_account(&address, generation);
+                    if delete_empty && account.is_empty() && generation == next_generation {
+                        Engine::remove_account(&address, &account);
+                        state_commitment::remove_account(&address);
+                    } else {
+                        Engine::set_account(&address, &account);
+                        state_commitment::update_account(
+                            &address,
+                            code.as_ref().map(|_| H256(account.code_hash)),
+                            reset_storage,
+                            &storage,
+                        );
                     }
                 }
                 Apply::Delete { address } => {
-                    let generation = Self::get_generation(&address);
-                    Engine::remove_account(&address, generation);
+                    let account = Engine::get_account(&address);
+                    Engine::remove_account(&address, &account);
+                    state_commitment::remove_account(&address);
                 }
             }
         }
+        self.applied_storage_usage = Some((storage_usage, sdk::storage_usage()));
     }
 }
 
 #[cfg(test)]
-mod tests {}
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hard_fork_at() {
+        let state = EngineState {
+            hard_fork: HardForkId::Istanbul,
+            scheduled_hard_fork: Some(ScheduledHardFork {
+                hard_fork: HardForkId::London,
+                block_height: 10,
+            }),
+            ..Default::default()
+        };
+
+        assert_eq!(state.hard_fork_at(9), HardForkId::Istanbul);
+        assert_eq!(state.hard_fork_at(10), HardForkId::London);
+        assert_eq!(state.hard_fork_at(11), HardForkId::London);
+    }
+
+    #[test]
+    fn test_schedule_hard_fork() {
+        let mut state = EngineState {
+            hard_fork: HardForkId::Istanbul,
+            ..Default::default()
+        };
+
+        state.schedule_hard_fork(HardForkId::Berlin, 10, 5).unwrap();
+        // A pending switch can be replaced
+        state.schedule_hard_fork(HardForkId::London, 20, 5).unwrap();
+        assert_eq!(state.hard_fork_at(19), HardForkId::Istanbul);
+        assert_eq!(state.hard_fork_at(20), HardForkId::London);
+
+        // Berlin is older than London, which is active from block 20
+        let result = state.schedule_hard_fork(HardForkId::Berlin, 30, 25);
+        assert!(matches!(result, Err(ScheduleHardForkError::Downgrade)));
+    }
+
+    #[test]
+    fn test_schedule_hard_fork_folds_activated_switch() {
+        let mut state = EngineState {
+            hard_fork: HardForkId::Istanbul,
+            scheduled_hard_fork: Some(ScheduledHardFork {
+                hard_fork: HardForkId::Berlin,
+                block_height: 10,
+            }),
+            ..Default::default()
+        };
+
+        state
+            .schedule_hard_fork(HardForkId::London, 20, 15)
+            .unwrap();
+
+        assert_eq!(state.hard_fork, HardForkId::Berlin);
+        assert_eq!(state.hard_fork_at(15), HardForkId::Berlin);
+        assert_eq!(state.hard_fork_at(20), HardForkId::London);
+    }
+
+    #[test]
+    fn test_decode_unversioned_state() {
+        let relayers_evm_addresses =
+            LookupMap::<{ KeyPrefix::RelayerEvmAddressMap as KeyPrefixU8 }>::new();
+        let legacy = (
+            [1u8; 32],
+            "owner.near".to_string(),
+            "prover.near".to_string(),
+            5u64,
+            relayers_evm_addresses,
+        );
+
+        // Layout prior to per-deployment hard fork selection
+        let state = decode_unversioned_state(&legacy.try_to_vec().unwrap()).unwrap();
+        assert_eq!(state.chain_id, [1u8; 32]);
+        assert_eq!(state.owner_id, "owner.near");
+        assert_eq!(state.upgrade_delay_blocks, 5);
+        assert_eq!(state.hard_fork, HardForkId::Istanbul);
+        assert!(!state.block_timestamp_in_seconds);
+
+        // Layout extended up to the timestamp unit
+        let mut bytes = legacy.try_to_vec().unwrap();
+        bytes.extend(
+            (HardForkId::Berlin, None::<ScheduledHardFork>)
+                .try_to_vec()
+                .unwrap(),
+        );
+        bytes.extend((BlockHashMode::Recorded, true).try_to_vec().unwrap());
+        let state = decode_unversioned_state(&bytes).unwrap();
+        assert_eq!(state.hard_fork, HardForkId::Berlin);
+        assert_eq!(state.block_hash_mode, BlockHashMode::Recorded);
+        assert!(state.block_timestamp_in_seconds);
+        assert_eq!(state.block_gas_limit, None);
+        assert!(!state.evm_log_events);
+        assert_eq!(state.storage_byte_price, None);
+
+        // Layout with every field, which cannot be followed by anything
+        let mut bytes = EngineState {
+            hard_fork: HardForkId::London,
+            evm_log_events: true,
+            storage_byte_price: Some([2u8; 32]),
+            ..Default::default()
+        }
+        .try_to_vec()
+        .unwrap();
+        let state = decode_unversioned_state(&bytes).unwrap();
+        assert_eq!(state.hard_fork, HardForkId::London);
+        assert!(state.evm_log_events);
+        assert_eq!(state.storage_byte_price, Some([2u8; 32]));
+        bytes.push(0);
+        assert!(decode_unversioned_state(&bytes).is_err());
+
+        // A layout cut in the middle of a step
+        let mut bytes = legacy.try_to_vec().unwrap();
+        bytes.extend(HardForkId::Berlin.try_to_vec().unwrap());
+        assert!(decode_unversioned_state(&bytes).is_err());
+    }
+}
@@ -0,0 +1,174 @@
+//! EVM logs written as NEAR logs, in the NEP-297 event format.
+//!
+//! See: https://nomicon.io/Standards/EventsFormat
+use evm::backend::Log;
+
+use crate::prelude::{format, String, ToString, Vec, H256};
+use crate::sdk;
+use crate::types::keccak;
+
+const EVENT_JSON_PREFIX: &str = "EVENT_JSON:";
+const STANDARD: &str = "aurora";
+const VERSION: &str = "1.0.0";
+
+/// Largest log data written as is, the data of larger logs is replaced by its hash.
+const MAX_DATA_LEN: usize = 1024;
+/// Most events written for a transaction. NEAR allows 100 logs per receipt,
+/// this leaves room for the other logs.
+const MAX_EVENTS: usize = 50;
+/// Most bytes written for a transaction. NEAR allows 16 KiB of logs per receipt,
+/// this leaves room for the other logs.
+const MAX_TOTAL_LEN: usize = 8 * 1024;
+/// Upper bound on the length of an `evm_logs_truncated` event.
+const MAX_TRUNCATED_EVENT_LEN: usize = 256;
+
+/// Writes one `evm_log` event for each of the logs of a transaction.
+pub fn emit_evm_logs(logs: &[Log], transaction_hash: Option<H256>) {
+    for event in evm_log_events(logs, transaction_hash) {
+        sdk::log_utf8(event.as_bytes());
+    }
+}
+
+/// Returns an `evm_log` event for each of the logs.
+///
+/// If the events would exceed `MAX_EVENTS` or `MAX_TOTAL_LEN`, the remaining logs are left
+/// out and replaced by a single `evm_logs_truncated` event, with the number of logs left out.
+pub fn evm_log_events(logs: &[Log], transaction_hash: Option<H256>) -> Vec<String> {
+    let mut events = Vec::new();
+    let mut total_len = 0;
+    for (i, log) in logs.iter().enumerate() {
+        let event = evm_log_event(log, transaction_hash);
+        if events.len() + 1 >= MAX_EVENTS
+            || total_len + event.len() + MAX_TRUNCATED_EVENT_LEN > MAX_TOTAL_LEN
+        {
+            events.push(evm_logs_truncated_event(logs.len() - i, transaction_hash));
+            break;
+        }
+        total_len += event.len();
+        events.push(event);
+    }
+    events
+}
+
+fn evm_log_event(log: &Log, transaction_hash: Option<H256>) -> String {
+    let topics: Vec<String> = log
+        .topics
+        .iter()
+        .map(|topic| format!(r#""{}""#, bytes_to_hex(topic.as_bytes())))
+        .collect();
+    let data = if log.data.len() <= MAX_DATA_LEN {
+        format!(r#""data":"{}""#, bytes_to_hex(&log.data))
+    } else {
+        format!(
+            r#""data_hash":"{}","data_len":{}"#,
+            bytes_to_hex(keccak(&log.data).as_bytes()),
+            log.data.len()
+        )
+    };
+    event_json(
+        "evm_log",
+        &format!(
+            r#""address":"{}","topics":[{}],{},"tx_hash":{}"#,
+            bytes_to_hex(log.address.as_bytes()),
+            topics.join(","),
+            data,
+            transaction_hash_to_json(transaction_hash),
+        ),
+    )
+}
+
+fn evm_logs_truncated_event(omitted: usize, transaction_hash: Option<H256>) -> String {
+    event_json(
+        "evm_logs_truncated",
+        &format!(
+            r#""omitted":{},"tx_hash":{}"#,
+            omitted,
+            transaction_hash_to_json(transaction_hash),
+        ),
+    )
+}
+
+fn event_json(event: &str, data: &str) -> String {
+    format!(
+        r#"{}{{"standard":"{}","version":"{}","event":"{}","data":[{{{}}}]}}"#,
+        EVENT_JSON_PREFIX, STANDARD, VERSION, event, data
+    )
+}
+
+fn transaction_hash_to_json(transaction_hash: Option<H256>) -> String {
+    match transaction_hash {
+        Some(hash) => format!(r#""{}""#, bytes_to_hex(hash.as_bytes())),
+        None => "null".to_string(),
+    }
+}
+
+fn bytes_to_hex(bytes: &[u8]) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::{vec, Address};
+
+    #[test]
+    fn test_evm_log_event() {
+        let log = Log {
+            address: Address([1; 20]),
+            topics: vec![H256([2; 32])],
+            data: vec![3, 4],
+        };
+
+        assert_eq!(
+            evm_log_events(&[log], Some(H256([5; 32]))),
+            vec![concat!(
+                r#"EVENT_JSON:{"standard":"aurora","version":"1.0.0","event":"evm_log","data":[{"#,
+                r#""address":"0x0101010101010101010101010101010101010101","#,
+                r#""topics":["0x0202020202020202020202020202020202020202020202020202020202020202"],"#,
+                r#""data":"0x0304","#,
+                r#""tx_hash":"0x0505050505050505050505050505050505050505050505050505050505050505"}]}"#,
+            )]
+        );
+    }
+
+    #[test]
+    fn test_large_evm_log_event() {
+        let data = vec![0; MAX_DATA_LEN + 1];
+        let log = Log {
+            address: Address([1; 20]),
+            topics: Vec::new(),
+            data: data.clone(),
+        };
+
+        let events = evm_log_events(&[log], None);
+        assert_eq!(events.len(), 1);
+        let expected_data = format!(
+            r#""data_hash":"{}","data_len":1025,"tx_hash":null"#,
+            bytes_to_hex(keccak(&data).as_bytes())
+        );
+        assert!(events[0].contains(&expected_data));
+    }
+
+    #[test]
+    fn test_truncated_evm_log_events() {
+        let log = Log {
+            address: Address([1; 20]),
+            topics: Vec::new(),
+            data: Vec::new(),
+        };
+        let logs = vec![log; 2 * MAX_EVENTS];
+
+        let events = evm_log_events(&logs, None);
+        assert!(events.len() <= MAX_EVENTS);
+        let omitted = logs.len() - (events.len() - 1);
+        assert_eq!(
+            events.last().unwrap(),
+            &format!(
+                r#"EVENT_JSON:{{"standard":"aurora","version":"1.0.0","event":"evm_logs_truncated","data":[{{"omitted":{},"tx_hash":null}}]}}"#,
+                omitted
+            )
+        );
+        let total_len: usize = events.iter().map(|event| event.len()).sum();
+        assert!(total_len <= MAX_TOTAL_LEN);
+    }
+}
@@ -11,6 +11,7 @@ use {
     crate::parameters::*,
     crate::prelude::{self, Ordering, String, ToString, TryInto, Vec, U256},
     crate::sdk,
+    crate::state_commitment,
     crate::storage,
 };
 
@@ -132,6 +133,7 @@ impl FungibleToken {
                 &prelude::Address(address),
                 &Wei::new(U256::from(new_balance)),
             );
+            state_commitment::touch_account(&prelude::Address(address));
             self.total_eth_supply_on_aurora = self
                 .total_eth_supply_on_aurora
                 .checked_add(amount)
@@ -182,6 +184,7 @@ impl FungibleToken {
                 &prelude::Address(address),
                 &Wei::new(U256::from(new_balance)),
             );
+            state_commitment::touch_account(&prelude::Address(address));
             self.total_eth_supply_on_aurora = self
                 .total_eth_supply_on_aurora
                 .checked_sub(amount)
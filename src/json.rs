@@ -28,6 +28,8 @@ pub enum JsonError {
     InvalidString,
     InvalidArray,
     ExpectedStringGotNumber,
+    InvalidHex,
+    InvalidU256,
     OutOfRange(JsonOutOfRangeError),
 }
 
@@ -114,6 +116,8 @@ impl AsRef<[u8]> for JsonError {
             Self::InvalidString => b"ERR_FAILED_PARSE_STRING",
             Self::InvalidArray => b"ERR_FAILED_PARSE_ARRAY",
             Self::ExpectedStringGotNumber => b"ERR_EXPECTED_STRING_GOT_NUMBER",
+            Self::InvalidHex => b"ERR_FAILED_PARSE_HEX",
+            Self::InvalidU256 => b"ERR_FAILED_PARSE_U256",
             Self::OutOfRange(err) => err.as_ref(),
         }
     }
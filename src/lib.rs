@@ -13,26 +13,38 @@ use crate::parameters::PromiseCreateArgs;
 mod map;
 #[cfg(feature = "meta-call")]
 pub mod meta_parsing;
+mod migration;
 pub mod parameters;
 pub mod prelude;
 pub mod storage;
 pub mod transaction;
 pub mod types;
 
+mod account_index;
+mod account_migration;
 mod admin_controlled;
 #[cfg_attr(not(feature = "contract"), allow(dead_code))]
 mod connector;
 mod deposit_event;
 pub mod engine;
+mod evm_event;
 mod fungible_token;
 mod json;
 mod log_entry;
 mod precompiles;
+mod receipt;
 pub mod sdk;
+#[cfg(feature = "tracing")]
+mod tracing;
+mod trie;
 
 #[cfg(test)]
 mod benches;
 mod state;
+mod state_commitment;
+#[cfg(any(feature = "contract", test))]
+mod state_dump;
+mod storage_cleanup;
 #[cfg(test)]
 mod test_utils;
 #[cfg(test)]
@@ -76,30 +88,45 @@ pub unsafe fn on_alloc_error(_: core::alloc::Layout) -> ! {
 mod contract {
     use borsh::{BorshDeserialize, BorshSerialize};
 
+    use crate::account_index;
     use crate::connector::EthConnectorContract;
-    use crate::engine::{Engine, EngineState, GasPaymentError};
+    use crate::engine::{Engine, EngineResult, EngineState, GasPaymentError, StoragePaymentError};
     use crate::fungible_token::FungibleTokenMetadata;
-    #[cfg(feature = "evm_bully")]
-    use crate::parameters::{BeginBlockArgs, BeginChainArgs};
+    #[cfg(feature = "tracing")]
+    use crate::parameters::TraceCallArgs;
     use crate::parameters::{
-        DeployErc20TokenArgs, ExpectUtf8, FunctionCallArgs, GetErc20FromNep141CallArgs,
-        GetStorageAtArgs, InitCallArgs, IsUsedProofCallArgs, NEP141FtOnTransferArgs, NewCallArgs,
-        PauseEthConnectorCallArgs, SetContractDataCallArgs, SubmitResult, TransactionStatus,
-        TransferCallCallArgs, ViewCallArgs,
+        BackfillStateCommitmentArgs, CleanupStorageArgs, DeployErc20TokenArgs, DumpStateArgs,
+        EstimateGasArgs, ExpectUtf8, FunctionCallArgs, GetErc20FromNep141CallArgs, GetProofArgs,
+        GetStorageAtArgs, IndexAccountsArgs, InitCallArgs, IsUsedProofCallArgs,
+        NEP141FtOnTransferArgs, NewCallArgs, PauseEthConnectorCallArgs, RunMigrationsArgs,
+        RunMigrationsResult, ScheduleHardForkArgs, SetContractDataCallArgs, SubmitResult,
+        SubmitResultV2, SubmittedTransaction, TransactionStatus, TransferCallCallArgs,
+        ViewCallArgs, ViewCallArgsV2,
     };
+    #[cfg(feature = "evm_bully")]
+    use crate::parameters::{BeginBlockArgs, BeginChainArgs, BeginChainArgsV2, GenesisAllocArgs};
 
     use crate::json::parse_json;
-    use crate::prelude::{Address, ToString, TryInto, H160, H256, U256};
+    use crate::migration;
+    use crate::prelude::{Address, ToString, TryFrom, TryInto, H160, H256, U256};
     use crate::sdk;
+    use crate::state_commitment;
     use crate::storage::{bytes_to_key, KeyPrefix};
+    use crate::storage_cleanup;
     use crate::types::{
-        near_account_to_evm_address, u256_to_arr, SdkExpect, SdkProcess, SdkUnwrap,
+        keccak, near_account_to_evm_address, u256_to_arr, SdkExpect, SdkProcess, SdkUnwrap, Wei,
         ERR_FAILED_PARSE,
     };
 
     const CODE_KEY: &[u8; 4] = b"CODE";
     const CODE_STAGE_KEY: &[u8; 10] = b"CODE_STAGE";
     const GAS_OVERFLOW: &str = "ERR_GAS_OVERFLOW";
+    /// Most storage slots removed by a call to `cleanup_storage`.
+    const MAX_CLEANUP_SLOTS: u32 = 1000;
+    /// Most migration steps run by a call to `run_migrations`.
+    const MAX_MIGRATION_STEPS: u32 = 100;
+    /// Most steps run by a call to `backfill_state_commitment`.
+    const MAX_BACKFILL_STEPS: u32 = 1000;
 
     ///
     /// ADMINISTRATIVE METHODS
@@ -111,6 +138,10 @@ mod contract {
     pub extern "C" fn new() {
         if let Ok(state) = Engine::get_state() {
             require_owner_only(&state);
+        } else {
+            // A new engine has no storage to migrate, nor accounts stored before the index.
+            migration::complete_all();
+            account_index::set_complete();
         }
 
         let args: NewCallArgs = sdk::read_input_borsh().sdk_unwrap();
@@ -147,6 +178,14 @@ mod contract {
         sdk::return_output(&Engine::get_state().sdk_unwrap().chain_id)
     }
 
+    /// Get the hard fork used to execute transactions in the current block.
+    #[no_mangle]
+    pub extern "C" fn get_hard_fork() {
+        let state = Engine::get_state().sdk_unwrap();
+        let hard_fork = state.hard_fork_at(sdk::block_index());
+        sdk::return_output(&hard_fork.try_to_vec().sdk_expect("ERR_SERIALIZE"));
+    }
+
     #[no_mangle]
     pub extern "C" fn get_upgrade_index() {
         let state = Engine::get_state().sdk_unwrap();
@@ -179,10 +218,153 @@ mod contract {
 
     /// Called as part of the upgrade process (see `sdk::self_deploy`). This function is meant
     /// to make any necessary changes to the state such that it aligns with the newly deployed
-    /// code.
+    /// code. Migrations of the rest of the storage run in batches, see `run_migrations`.
     #[no_mangle]
     pub extern "C" fn state_migration() {
-        // TODO: currently we don't have migrations
+        Engine::migrate_state().sdk_unwrap();
+    }
+
+    /// Schedule a switch to a newer hard fork at the given block height.
+    /// Replaces any previously scheduled switch which has not happened yet.
+    #[no_mangle]
+    pub extern "C" fn schedule_hard_fork() {
+        let mut state = Engine::get_state().sdk_unwrap();
+        require_owner_only(&state);
+        let args: ScheduleHardForkArgs = sdk::read_input_borsh().sdk_unwrap();
+        state
+            .schedule_hard_fork(args.hard_fork, args.block_height, sdk::block_index())
+            .sdk_unwrap();
+        Engine::set_state(state);
+    }
+
+    /// Set the source of the block hashes returned by the BLOCKHASH opcode.
+    #[no_mangle]
+    pub extern "C" fn set_block_hash_mode() {
+        let mut state = Engine::get_state().sdk_unwrap();
+        require_owner_only(&state);
+        state.block_hash_mode = sdk::read_input_borsh().sdk_unwrap();
+        Engine::set_state(state);
+    }
+
+    /// Set whether the TIMESTAMP opcode returns seconds rather than microseconds.
+    #[no_mangle]
+    pub extern "C" fn set_block_timestamp_in_seconds() {
+        let mut state = Engine::get_state().sdk_unwrap();
+        require_owner_only(&state);
+        state.block_timestamp_in_seconds = sdk::read_input_borsh().sdk_unwrap();
+        Engine::set_state(state);
+    }
+
+    /// Set the gas limit returned by the GASLIMIT opcode, or remove it to return `U256::MAX`.
+    #[no_mangle]
+    pub extern "C" fn set_block_gas_limit() {
+        let mut state = Engine::get_state().sdk_unwrap();
+        require_owner_only(&state);
+        state.block_gas_limit = sdk::read_input_borsh().sdk_unwrap();
+        Engine::set_state(state);
+    }
+
+    /// Set how the address returned by the COINBASE opcode is chosen.
+    #[no_mangle]
+    pub extern "C" fn set_coinbase_policy() {
+        let mut state = Engine::get_state().sdk_unwrap();
+        require_owner_only(&state);
+        state.coinbase_policy = sdk::read_input_borsh().sdk_unwrap();
+        Engine::set_state(state);
+    }
+
+    /// Set whether the EVM logs of transactions are also written as NEAR logs.
+    #[no_mangle]
+    pub extern "C" fn set_evm_log_events() {
+        let mut state = Engine::get_state().sdk_unwrap();
+        require_owner_only(&state);
+        state.evm_log_events = sdk::read_input_borsh().sdk_unwrap();
+        Engine::set_state(state);
+    }
+
+    /// Start maintaining a Merkle commitment to the EVM state, see `get_proof`.
+    /// The accounts already stored are then added with `backfill_state_commitment`.
+    #[no_mangle]
+    pub extern "C" fn enable_state_commitment() {
+        let state = Engine::get_state().sdk_unwrap();
+        require_owner_only(&state);
+        state_commitment::enable();
+    }
+
+    /// Add a batch of the accounts stored before the state commitment was enabled to it.
+    /// To be called until complete, before the state root and proofs are served.
+    #[no_mangle]
+    pub extern "C" fn backfill_state_commitment() {
+        let state = Engine::get_state().sdk_unwrap();
+        require_owner_only(&state);
+        let args: BackfillStateCommitmentArgs = sdk::read_input_borsh().sdk_unwrap();
+        let result = state_commitment::backfill(args.limit.min(MAX_BACKFILL_STEPS));
+        sdk::return_output(&result.try_to_vec().sdk_expect("ERR_SERIALIZE"));
+    }
+
+    /// Add accounts stored before the account index to it, along with the keys of their storage
+    /// slots written before the keys were recorded, see `dump_state` and `cleanup_storage`.
+    #[no_mangle]
+    pub extern "C" fn index_accounts() {
+        let state = Engine::get_state().sdk_unwrap();
+        require_owner_only(&state);
+        let args: IndexAccountsArgs = sdk::read_input_borsh().sdk_unwrap();
+        for account in args.accounts.iter() {
+            account_index::index_account(account);
+        }
+    }
+
+    /// Confirm that all the accounts stored before the account index were added to it with
+    /// `index_accounts`. Until then, the account migration, the state commitment backfill and
+    /// `dump_state` do not complete, since the index may be missing accounts.
+    #[no_mangle]
+    pub extern "C" fn finish_account_indexing() {
+        let state = Engine::get_state().sdk_unwrap();
+        require_owner_only(&state);
+        account_index::set_complete();
+    }
+
+    /// Run a batch of the storage migration in progress, see `get_migration_status`.
+    /// To be called until no migration is pending.
+    #[no_mangle]
+    pub extern "C" fn run_migrations() {
+        let state = Engine::get_state().sdk_unwrap();
+        require_owner_only(&state);
+        let args: RunMigrationsArgs = sdk::read_input_borsh().sdk_unwrap();
+        let steps = migration::run(args.limit.min(MAX_MIGRATION_STEPS));
+        let result = RunMigrationsResult {
+            steps,
+            status: migration::status(),
+        };
+        sdk::return_output(&result.try_to_vec().sdk_expect("ERR_SERIALIZE"));
+    }
+
+    /// Set the price in wei of a byte of storage used by Ethereum transactions, charged to
+    /// their senders out of a payment reserved from their gas limit (see `GAS_PER_STORAGE_BYTE`).
+    /// Direct calls are then charged the NEAR storage staking from their attached deposit, and
+    /// refunded the staking of the storage they free up to what their caller paid so far.
+    /// `None` stops charging for storage.
+    #[no_mangle]
+    pub extern "C" fn set_storage_byte_price() {
+        let mut state = Engine::get_state().sdk_unwrap();
+        require_owner_only(&state);
+        state.storage_byte_price = sdk::read_input_borsh().sdk_unwrap();
+        Engine::set_state(state);
+    }
+
+    /// Set the share, in percent, of the released storage staking paid to the callers
+    /// of `cleanup_storage`. The reward is paid out of the balance of the engine, including
+    /// for storage which was not charged to the users.
+    #[no_mangle]
+    pub extern "C" fn set_storage_cleanup_reward_percent() {
+        let mut state = Engine::get_state().sdk_unwrap();
+        require_owner_only(&state);
+        let percent: u8 = sdk::read_input_borsh().sdk_unwrap();
+        if percent > 100 {
+            sdk::panic_utf8(b"ERR_INVALID_PERCENT");
+        }
+        state.storage_cleanup_reward_percent = percent;
+        Engine::set_state(state);
     }
 
     ///
@@ -192,122 +374,50 @@ mod contract {
     /// Deploy code into the EVM.
     #[no_mangle]
     pub extern "C" fn deploy_code() {
+        let storage_usage = sdk::storage_usage();
         let input = sdk::read_input();
-        let mut engine = Engine::new(predecessor_address()).sdk_unwrap();
-        Engine::deploy_code_with_input(&mut engine, input)
+        let state = Engine::get_state().sdk_unwrap();
+        let charge_storage = state.storage_byte_price.is_some();
+        let mut engine = Engine::new_with_state(state, predecessor_address());
+        let result = Engine::deploy_code_with_input(&mut engine, input);
+        if charge_storage {
+            charge_storage_deposit(storage_usage);
+        }
+        result
             .map(|res| res.try_to_vec().sdk_expect("ERR_SERIALIZE"))
             .sdk_process();
-        // TODO: charge for storage
     }
 
     /// Call method on the EVM contract.
     #[no_mangle]
     pub extern "C" fn call() {
+        let storage_usage = sdk::storage_usage();
         let args: FunctionCallArgs = sdk::read_input_borsh().sdk_unwrap();
-        let mut engine = Engine::new(predecessor_address()).sdk_unwrap();
-        Engine::call_with_args(&mut engine, args)
+        let state = Engine::get_state().sdk_unwrap();
+        let charge_storage = state.storage_byte_price.is_some();
+        let mut engine = Engine::new_with_state(state, predecessor_address());
+        let result = Engine::call_with_args(&mut engine, args);
+        if charge_storage {
+            charge_storage_deposit(storage_usage);
+        }
+        result
             .map(|res| res.try_to_vec().sdk_expect("ERR_SERIALIZE"))
             .sdk_process();
-        // TODO: charge for storage
     }
 
     /// Process signed Ethereum transaction.
     /// Must match CHAIN_ID to make sure it's signed for given chain vs replayed from another chain.
     #[no_mangle]
     pub extern "C" fn submit() {
-        use crate::prelude::TryFrom;
-        use crate::transaction::EthTransaction;
-
-        let input = sdk::read_input();
-
-        let signed_transaction = EthTransaction::try_from(input.as_slice()).sdk_unwrap();
-
-        let state = Engine::get_state().sdk_unwrap();
-
-        // Validate the chain ID, if provided inside the signature:
-        if let Some(chain_id) = signed_transaction.chain_id() {
-            if U256::from(chain_id) != U256::from(state.chain_id) {
-                sdk::panic_utf8(b"ERR_INVALID_CHAIN_ID");
-            }
-        }
-
-        // Retrieve the signer of the transaction:
-        let sender = signed_transaction
-            .sender()
-            .sdk_expect("ERR_INVALID_ECDSA_SIGNATURE");
-
-        Engine::check_nonce(&sender, signed_transaction.nonce()).sdk_unwrap();
-
-        // Check intrinsic gas is covered by transaction gas limit
-        match signed_transaction.intrinsic_gas(crate::engine::CONFIG) {
-            None => sdk::panic_utf8(GAS_OVERFLOW.as_bytes()),
-            Some(intrinsic_gas) => {
-                if signed_transaction.gas_limit() < intrinsic_gas.into() {
-                    sdk::panic_utf8(b"ERR_INTRINSIC_GAS")
-                }
-            }
-        }
-
-        // Pay for gas
-        let gas_price = signed_transaction.gas_price();
-        let prepaid_amount =
-            match Engine::charge_gas_limit(&sender, signed_transaction.gas_limit(), gas_price) {
-                Ok(amount) => amount,
-                // If the account does not have enough funds to cover the gas cost then we still
-                // must increment the nonce to prevent the transaction from being replayed in the
-                // future when the state may have changed such that it could pass.
-                Err(GasPaymentError::OutOfFund) => {
-                    Engine::increment_nonce(&sender);
-                    let result = SubmitResult {
-                        status: TransactionStatus::OutOfFund,
-                        gas_used: 0,
-                        logs: crate::prelude::Vec::new(),
-                    };
-                    sdk::return_output(&result.try_to_vec().unwrap());
-                    return;
-                }
-                // If an overflow happens then the transaction is statically invalid
-                // (i.e. validity does not depend on state), so we do not need to increment the nonce.
-                Err(err) => sdk::panic_utf8(err.as_ref()),
-            };
-
-        // Figure out what kind of a transaction this is, and execute it:
-        let mut engine = Engine::new_with_state(state, sender);
-        let (value, gas_limit, data, maybe_receiver, access_list) =
-            signed_transaction.destructure();
-        let gas_limit = gas_limit.sdk_expect(GAS_OVERFLOW);
-        let access_list = access_list
-            .into_iter()
-            .map(|a| (a.address, a.storage_keys))
-            .collect();
-        let result = if let Some(receiver) = maybe_receiver {
-            Engine::call(
-                &mut engine,
-                sender,
-                receiver,
-                value,
-                data,
-                gas_limit,
-                access_list,
-            )
-            // TODO: charge for storage
-        } else {
-            // Execute a contract deployment:
-            Engine::deploy_code(&mut engine, sender, value, data, gas_limit, access_list)
-            // TODO: charge for storage
-        };
-
-        // Give refund
-        let relayer = predecessor_address();
-        let gas_used = match &result {
-            Ok(submit_result) => submit_result.gas_used,
-            Err(engine_err) => engine_err.gas_used,
-        };
-        Engine::refund_unused_gas(&sender, &relayer, prepaid_amount, gas_used, gas_price)
-            .sdk_unwrap();
+        submit_transaction(sdk::read_input())
+            .map(|res| res.result.try_to_vec().sdk_expect("ERR_SERIALIZE"))
+            .sdk_process();
+    }
 
-        // return result to user
-        result
+    /// Same as `submit`, but returns a `SubmitResultV2` which also identifies the transaction.
+    #[no_mangle]
+    pub extern "C" fn submit_v2() {
+        submit_transaction(sdk::read_input())
             .map(|res| res.try_to_vec().sdk_expect("ERR_SERIALIZE"))
             .sdk_process();
     }
@@ -377,11 +487,14 @@ mod contract {
     /// Deploy ERC20 token mapped to a NEP141
     #[no_mangle]
     pub extern "C" fn deploy_erc20_token() {
+        let storage_usage = sdk::storage_usage();
         // Id of the NEP141 token in Near
         let args: DeployErc20TokenArgs =
             DeployErc20TokenArgs::try_from_slice(&sdk::read_input()).sdk_expect("ERR_ARG_PARSE");
 
-        let mut engine = Engine::new(predecessor_address()).sdk_unwrap();
+        let state = Engine::get_state().sdk_unwrap();
+        let charge_storage = state.storage_byte_price.is_some();
+        let mut engine = Engine::new_with_state(state, predecessor_address());
 
         let erc20_contract = include_bytes!("../etc/eth-contracts/res/EvmErc20.bin");
         let deploy_args = ethabi::encode(&[
@@ -408,9 +521,31 @@ mod contract {
         engine
             .register_token(address.as_bytes(), args.nep141.as_bytes())
             .sdk_unwrap();
+        if charge_storage {
+            charge_storage_deposit(storage_usage);
+        }
         sdk::return_output(&address.as_bytes().try_to_vec().sdk_expect("ERR_SERIALIZE"));
+    }
 
-        // TODO: charge for storage
+    /// Remove a batch of storage slots left behind by old generations of accounts, and pay
+    /// the caller a share of the storage staking released. Anyone can call this function.
+    #[no_mangle]
+    pub extern "C" fn cleanup_storage() {
+        let state = Engine::get_state().sdk_unwrap();
+        let args: CleanupStorageArgs = sdk::read_input_borsh().sdk_unwrap();
+        let storage_usage = sdk::storage_usage();
+        let max_slots = args.max_slots.min(MAX_CLEANUP_SLOTS);
+        let mut result = storage_cleanup::cleanup(u64::from(max_slots));
+        let released_bytes = storage_usage.saturating_sub(sdk::storage_usage());
+        result.reward = u128::from(released_bytes)
+            * sdk::storage_byte_cost()
+            * u128::from(state.storage_cleanup_reward_percent)
+            / 100;
+        if result.reward > 0 {
+            let promise_id = sdk::promise_batch_create(&sdk::predecessor_account_id());
+            sdk::promise_batch_action_transfer(promise_id, result.reward);
+        }
+        sdk::return_output(&result.try_to_vec().sdk_expect("ERR_SERIALIZE"));
     }
 
     ///
@@ -419,12 +554,49 @@ mod contract {
 
     #[no_mangle]
     pub extern "C" fn view() {
+        let args = read_view_call_args();
+        let engine = Engine::new(Address::from_slice(&args.sender)).sdk_unwrap();
+        let result = Engine::view_with_args_v2(&engine, args).sdk_unwrap();
+        sdk::return_output(&result.try_to_vec().sdk_expect("ERR_SERIALIZE"));
+    }
+
+    /// Same as `view`, but returns a `SubmitResult` with the gas used and the logs.
+    #[no_mangle]
+    pub extern "C" fn view_result() {
+        let args = read_view_call_args();
+        let engine = Engine::new(Address::from_slice(&args.sender)).sdk_unwrap();
+        let result = Engine::view_result_with_args(&engine, args).sdk_unwrap();
+        sdk::return_output(&result.try_to_vec().sdk_expect("ERR_SERIALIZE"));
+    }
+
+    /// Create an EIP-2930 access list for a call.
+    #[no_mangle]
+    pub extern "C" fn create_access_list() {
         let args: ViewCallArgs = sdk::read_input_borsh().sdk_unwrap();
         let engine = Engine::new(Address::from_slice(&args.sender)).sdk_unwrap();
-        let result = Engine::view_with_args(&engine, args).sdk_unwrap();
+        let result = Engine::create_access_list_with_args(&engine, args).sdk_unwrap();
+        sdk::return_output(&result.try_to_vec().sdk_expect("ERR_SERIALIZE"));
+    }
+
+    /// Estimate the lowest gas limit with which a call succeeds.
+    #[no_mangle]
+    pub extern "C" fn estimate_gas() {
+        let args: EstimateGasArgs = sdk::read_input_borsh().sdk_unwrap();
+        let engine = Engine::new(Address::from_slice(&args.sender)).sdk_unwrap();
+        let result = engine.estimate_gas_with_args(args).sdk_unwrap();
         sdk::return_output(&result.try_to_vec().sdk_expect("ERR_SERIALIZE"));
     }
 
+    /// Trace a call, returning a geth `structLogger` or `callTracer` compatible JSON trace.
+    #[cfg(feature = "tracing")]
+    #[no_mangle]
+    pub extern "C" fn trace_call() {
+        let args: TraceCallArgs = sdk::read_input_borsh().sdk_unwrap();
+        let engine = Engine::new(Address::from_slice(&args.call.sender)).sdk_unwrap();
+        let trace = engine.trace_call_with_args(args);
+        sdk::return_output(&trace);
+    }
+
     #[no_mangle]
     pub extern "C" fn get_code() {
         let address = sdk::read_input_arr20().sdk_unwrap();
@@ -432,6 +604,16 @@ mod contract {
         sdk::return_output(&code)
     }
 
+    /// Get the keccak hash of the code of an account, read from its record. It is the same
+    /// hash as the EXTCODEHASH opcode returns for existing accounts, which the EVM still
+    /// computes from the code itself.
+    #[no_mangle]
+    pub extern "C" fn get_code_hash() {
+        let address = sdk::read_input_arr20().sdk_unwrap();
+        let code_hash = Engine::get_code_hash(&Address(address));
+        sdk::return_output(code_hash.as_bytes())
+    }
+
     #[no_mangle]
     pub extern "C" fn get_balance() {
         let address = sdk::read_input_arr20().sdk_unwrap();
@@ -446,6 +628,59 @@ mod contract {
         sdk::return_output(&u256_to_arr(&nonce))
     }
 
+    /// Get the hash of a block, as returned by the BLOCKHASH opcode in the current block.
+    #[no_mangle]
+    pub extern "C" fn get_block_hash() {
+        let block_height: u64 = sdk::read_input_borsh().sdk_unwrap();
+        let engine = Engine::new(Address::zero()).sdk_unwrap();
+        let block_hash = engine.get_block_hash(block_height);
+        sdk::return_output(block_hash.as_bytes())
+    }
+
+    /// Get the logs bloom and receipts root of the transactions executed in a block, among the
+    /// last `BLOCK_HASH_HISTORY` ones.
+    #[no_mangle]
+    pub extern "C" fn get_block_receipts_summary() {
+        let block_height: u64 = sdk::read_input_borsh().sdk_unwrap();
+        let summary = Engine::get_block_receipts_summary(block_height);
+        sdk::return_output(&summary.try_to_vec().sdk_expect("ERR_SERIALIZE"));
+    }
+
+    /// Return a page of the EVM accounts with their storage, as JSON in the format of geth's
+    /// `dump` command. Its `complete` field is false until `finish_account_indexing` is called,
+    /// since the accounts stored before the account index may be missing from it.
+    #[no_mangle]
+    pub extern "C" fn dump_state() {
+        let args: DumpStateArgs = sdk::read_input_borsh().sdk_unwrap();
+        sdk::return_output(crate::state_dump::dump_state(&args).as_bytes());
+    }
+
+    /// Return the storage migrations completed and still to run, see `run_migrations`.
+    #[no_mangle]
+    pub extern "C" fn get_migration_status() {
+        let status = migration::status();
+        sdk::return_output(&status.try_to_vec().sdk_expect("ERR_SERIALIZE"));
+    }
+
+    /// Return the root of the state trie. Panics if the state commitment is not enabled or
+    /// its backfill is not complete.
+    #[no_mangle]
+    pub extern "C" fn get_state_root() {
+        let state_root = state_commitment::get_state_root().sdk_unwrap();
+        sdk::return_output(state_root.as_bytes())
+    }
+
+    /// Return the Merkle proof of an account and of some of its storage slots against the state
+    /// root, as `eth_getProof` does. Panics if the state commitment is not enabled or its
+    /// backfill is not complete.
+    #[no_mangle]
+    pub extern "C" fn get_proof() {
+        let args: GetProofArgs = sdk::read_input_borsh().sdk_unwrap();
+        let proof =
+            state_commitment::get_proof(&Address(args.address), &args.storage_keys).sdk_unwrap();
+        sdk::return_output(&proof.try_to_vec().sdk_expect("ERR_SERIALIZE"));
+    }
+
     #[no_mangle]
     pub extern "C" fn get_storage_at() {
         let args: GetStorageAtArgs = sdk::read_input_borsh().sdk_unwrap();
@@ -459,32 +694,58 @@ mod contract {
     /// BENCHMARKING METHODS
     ///
 
+    /// Set the chain id and the balances of the genesis accounts, from a Borsh-encoded
+    /// `BeginChainArgs`. See `begin_chain_v2` for full genesis accounts.
     #[cfg(feature = "evm_bully")]
     #[no_mangle]
     pub extern "C" fn begin_chain() {
-        let mut state = Engine::get_state().sdk_unwrap();
-        require_owner_only(&state);
         let args: BeginChainArgs = sdk::read_input_borsh().sdk_unwrap();
-        state.chain_id = args.chain_id;
-        Engine::set_state(state);
-        // set genesis block balances
-        for account_balance in args.genesis_alloc {
-            Engine::set_balance(
-                &Address(account_balance.address),
-                &crate::types::Wei::new(U256::from(account_balance.balance)),
-            )
-        }
-        // return new chain ID
-        sdk::return_output(&Engine::get_state().sdk_unwrap().chain_id)
+        begin_chain_with_args(args.into());
+    }
+
+    /// Same as `begin_chain`, with full genesis accounts, from a Borsh-encoded
+    /// `BeginChainArgsV2`.
+    #[cfg(feature = "evm_bully")]
+    #[no_mangle]
+    pub extern "C" fn begin_chain_v2() {
+        let args: BeginChainArgsV2 = sdk::read_input_borsh().sdk_unwrap();
+        begin_chain_with_args(args);
+    }
+
+    /// Same as `begin_chain_v2`, from the JSON form of `BeginChainArgsV2`.
+    #[cfg(feature = "evm_bully")]
+    #[no_mangle]
+    pub extern "C" fn begin_chain_json() {
+        let json = parse_json(&sdk::read_input()).sdk_expect("ERR_ARG_PARSE");
+        begin_chain_with_args(BeginChainArgsV2::try_from(json).sdk_unwrap());
+    }
+
+    /// Import more genesis accounts after `begin_chain`, for genesis states too large to be
+    /// imported in one call. Takes a Borsh-encoded `GenesisAllocArgs`.
+    #[cfg(feature = "evm_bully")]
+    #[no_mangle]
+    pub extern "C" fn import_genesis_alloc() {
+        let args: GenesisAllocArgs = sdk::read_input_borsh().sdk_unwrap();
+        import_genesis_alloc_with_args(args);
+    }
+
+    /// Same as `import_genesis_alloc`, from the JSON form of `GenesisAllocArgs`.
+    #[cfg(feature = "evm_bully")]
+    #[no_mangle]
+    pub extern "C" fn import_genesis_alloc_json() {
+        let json = parse_json(&sdk::read_input()).sdk_expect("ERR_ARG_PARSE");
+        import_genesis_alloc_with_args(GenesisAllocArgs::try_from(json).sdk_unwrap());
     }
 
+    /// Set the properties of the Ethereum block being replayed, which the EVM sees instead
+    /// of the ones of the NEAR block from then on.
     #[cfg(feature = "evm_bully")]
     #[no_mangle]
     pub extern "C" fn begin_block() {
         let state = Engine::get_state().sdk_unwrap();
         require_owner_only(&state);
-        let _args: BeginBlockArgs = sdk::read_input_borsh().sdk_unwrap();
-        // TODO: https://github.com/aurora-is-near/aurora-engine/issues/2
+        let args: BeginBlockArgs = sdk::read_input_borsh().sdk_unwrap();
+        Engine::set_begin_block(&args);
     }
 
     #[no_mangle]
@@ -729,6 +990,247 @@ mod contract {
         }
     }
 
+    /// Executes the signed Ethereum transaction given to `submit` or `submit_v2`.
+    fn submit_transaction(input: crate::prelude::Vec<u8>) -> EngineResult<SubmitResultV2> {
+        use crate::transaction::EthTransaction;
+
+        let signed_transaction = EthTransaction::try_from(input.as_slice()).sdk_unwrap();
+
+        let state = Engine::get_state().sdk_unwrap();
+
+        // Validate the chain ID, if provided inside the signature:
+        if let Some(chain_id) = signed_transaction.chain_id() {
+            if U256::from(chain_id) != U256::from(state.chain_id) {
+                sdk::panic_utf8(b"ERR_INVALID_CHAIN_ID");
+            }
+        }
+
+        // Retrieve the signer of the transaction:
+        let sender = signed_transaction
+            .sender()
+            .sdk_expect("ERR_INVALID_ECDSA_SIGNATURE");
+
+        Engine::check_nonce(&sender, signed_transaction.nonce()).sdk_unwrap();
+
+        let submitted_transaction = SubmittedTransaction {
+            hash: keccak(&input).0,
+            sender: sender.0,
+            nonce: u256_to_arr(signed_transaction.nonce()),
+        };
+
+        // Check intrinsic gas is covered by transaction gas limit
+        let hard_fork = state.hard_fork_at(sdk::block_index());
+        match signed_transaction.intrinsic_gas(hard_fork.config()) {
+            None => sdk::panic_utf8(GAS_OVERFLOW.as_bytes()),
+            Some(intrinsic_gas) => {
+                if signed_transaction.gas_limit() < intrinsic_gas.into() {
+                    sdk::panic_utf8(b"ERR_INTRINSIC_GAS")
+                }
+            }
+        }
+
+        // Pay for gas
+        let gas_price = signed_transaction
+            .effective_gas_price(crate::engine::BLOCK_BASE_FEE_PER_GAS)
+            .sdk_expect("ERR_MAX_FEE_LESS_THAN_BASE_FEE");
+        let prepaid_amount =
+            match Engine::charge_gas_limit(&sender, signed_transaction.gas_limit(), gas_price) {
+                Ok(amount) => amount,
+                // If the account does not have enough funds to cover the gas cost then we still
+                // must increment the nonce to prevent the transaction from being replayed in the
+                // future when the state may have changed such that it could pass.
+                Err(GasPaymentError::OutOfFund) => {
+                    Engine::increment_nonce(&sender);
+                    return Ok(out_of_fund_result(submitted_transaction));
+                }
+                // If an overflow happens then the transaction is statically invalid
+                // (i.e. validity does not depend on state), so we do not need to increment the nonce.
+                Err(err) => sdk::panic_utf8(err.as_ref()),
+            };
+
+        // Reserve the payment for storage, in the same way
+        let storage_byte_price = state.storage_byte_price.map(U256::from);
+        let reserved_storage_amount = match storage_byte_price {
+            None => Wei::zero(),
+            Some(byte_price) => {
+                match Engine::reserve_storage(&sender, signed_transaction.gas_limit(), byte_price) {
+                    Ok(amount) => amount,
+                    // Nothing is executed, so the payment for gas is refunded in full.
+                    Err(StoragePaymentError::OutOfFund) => {
+                        Engine::refund_unused_gas(
+                            &sender,
+                            &predecessor_address(),
+                            prepaid_amount,
+                            0,
+                            gas_price,
+                        )
+                        .sdk_unwrap();
+                        Engine::increment_nonce(&sender);
+                        return Ok(out_of_fund_result(submitted_transaction));
+                    }
+                    Err(err) => sdk::panic_utf8(err.as_ref()),
+                }
+            }
+        };
+
+        // Figure out what kind of a transaction this is, and execute it:
+        let mut engine = Engine::new_with_state(state, sender);
+        engine.set_transaction_hash(H256(submitted_transaction.hash));
+        engine.set_transaction_type(signed_transaction.transaction_type());
+        let (value, gas_limit, data, maybe_receiver, access_list) =
+            signed_transaction.destructure();
+        let gas_limit = gas_limit.sdk_expect(GAS_OVERFLOW);
+        let access_list = access_list
+            .into_iter()
+            .map(|a| (a.address, a.storage_keys))
+            .collect();
+        let is_deployment = maybe_receiver.is_none();
+        let result = if let Some(receiver) = maybe_receiver {
+            Engine::call(
+                &mut engine,
+                sender,
+                receiver,
+                value,
+                data,
+                gas_limit,
+                access_list,
+            )
+        } else {
+            // Execute a contract deployment:
+            Engine::deploy_code(&mut engine, sender, value, data, gas_limit, access_list)
+        };
+
+        // Give refund
+        let relayer = predecessor_address();
+        let gas_used = match &result {
+            Ok(submit_result) => submit_result.gas_used,
+            Err(engine_err) => engine_err.gas_used,
+        };
+        Engine::refund_unused_gas(&sender, &relayer, prepaid_amount, gas_used, gas_price)
+            .sdk_unwrap();
+
+        // Pay for the storage used by the changes to the EVM state, out of the reserved payment
+        if let Some(byte_price) = storage_byte_price {
+            let (storage_usage_before, storage_usage_after) =
+                engine.applied_storage_usage().unwrap_or_default();
+            Engine::charge_storage(
+                &sender,
+                reserved_storage_amount,
+                storage_usage_before,
+                storage_usage_after,
+                byte_price,
+            )
+            .sdk_unwrap();
+        }
+
+        // return result to user
+        result.map(|result| {
+            // A successful deployment returns the address of the contract
+            let created_address = match &result.status {
+                TransactionStatus::Succeed(address) if is_deployment => {
+                    Some(H160::from_slice(address).0)
+                }
+                _ => None,
+            };
+            SubmitResultV2 {
+                result,
+                transaction: submitted_transaction,
+                created_address,
+            }
+        })
+    }
+
+    /// Result of a transaction whose sender cannot pay for its gas or storage.
+    fn out_of_fund_result(transaction: SubmittedTransaction) -> SubmitResultV2 {
+        SubmitResultV2 {
+            result: SubmitResult::new(TransactionStatus::OutOfFund, 0, crate::prelude::Vec::new()),
+            transaction,
+            created_address: None,
+        }
+    }
+
+    /// Reads either a `ViewCallArgs` or a `ViewCallArgsV2`.
+    fn read_view_call_args() -> ViewCallArgsV2 {
+        let input = sdk::read_input();
+        ViewCallArgs::try_from_slice(&input)
+            .map(Into::into)
+            .or_else(|_| ViewCallArgsV2::try_from_slice(&input))
+            .sdk_expect("ERR_ARG_PARSE")
+    }
+
+    #[cfg(feature = "evm_bully")]
+    fn begin_chain_with_args(args: BeginChainArgsV2) {
+        let mut state = Engine::get_state().sdk_unwrap();
+        require_owner_only(&state);
+        state.chain_id = args.chain_id;
+        Engine::set_state(state);
+        // set genesis block accounts
+        for account in args.genesis_alloc {
+            Engine::import_genesis_account(account);
+        }
+        // return new chain ID
+        sdk::return_output(&Engine::get_state().sdk_unwrap().chain_id)
+    }
+
+    #[cfg(feature = "evm_bully")]
+    fn import_genesis_alloc_with_args(args: GenesisAllocArgs) {
+        let state = Engine::get_state().sdk_unwrap();
+        require_owner_only(&state);
+        for account in args.genesis_alloc {
+            Engine::import_genesis_account(account);
+        }
+    }
+
+    /// Charges the deposit attached to a call for the storage used since `storage_usage`, at
+    /// the NEAR storage staking cost. The rest of the deposit is refunded to the caller, along
+    /// with the staking released by the storage freed, up to what the caller paid for storage
+    /// so far since the freed storage was not necessarily paid for by the caller.
+    fn charge_storage_deposit(storage_usage: u64) {
+        let caller = sdk::predecessor_account_id();
+        let deposit = sdk::attached_deposit();
+        let storage_usage_after = sdk::storage_usage();
+        let paid = get_storage_deposit(&caller);
+        let refund = if storage_usage_after >= storage_usage {
+            let cost = u128::from(storage_usage_after - storage_usage) * sdk::storage_byte_cost();
+            if deposit < cost {
+                sdk::panic_utf8(b"ERR_NOT_ENOUGH_STORAGE_DEPOSIT");
+            }
+            set_storage_deposit(&caller, paid.saturating_add(cost));
+            deposit - cost
+        } else {
+            let released = (u128::from(storage_usage - storage_usage_after)
+                * sdk::storage_byte_cost())
+            .min(paid);
+            set_storage_deposit(&caller, paid - released);
+            deposit.saturating_add(released)
+        };
+        if refund > 0 {
+            let promise_id = sdk::promise_batch_create(&caller);
+            sdk::promise_batch_action_transfer(promise_id, refund);
+        }
+    }
+
+    /// Returns the amount an account paid for storage with `charge_storage_deposit` and was
+    /// not refunded yet.
+    fn get_storage_deposit(account_id: &[u8]) -> u128 {
+        sdk::read_storage(&bytes_to_key(KeyPrefix::StorageDeposit, account_id))
+            .map(|value| {
+                let mut amount = [0u8; 16];
+                amount.copy_from_slice(&value);
+                u128::from_le_bytes(amount)
+            })
+            .unwrap_or(0)
+    }
+
+    fn set_storage_deposit(account_id: &[u8], amount: u128) {
+        let key = bytes_to_key(KeyPrefix::StorageDeposit, account_id);
+        if amount == 0 {
+            sdk::remove_storage(&key);
+        } else {
+            sdk::write_storage(&key, &amount.to_le_bytes());
+        }
+    }
+
     fn predecessor_address() -> Address {
         near_account_to_evm_address(&sdk::predecessor_account_id())
     }
@@ -1,8 +1,9 @@
-use crate::prelude::Vec;
+use crate::prelude::{TryFrom, Vec};
 use borsh::{BorshDeserialize, BorshSerialize};
 
 use crate::sdk;
-use crate::storage::{bytes_to_key, KeyPrefixU8};
+use crate::storage::{bytes_to_key, KeyPrefix, KeyPrefixU8};
+use crate::types::SdkUnwrap;
 
 /// An non-iterable implementation of a map that stores its content directly on the trie.
 /// Use `key_prefix` as a unique prefix for keys.
@@ -17,7 +18,7 @@ impl<const K: KeyPrefixU8> LookupMap<K> {
 
     /// Build key for this map scope
     fn raw_key_to_storage_key(&self, key_raw: &[u8]) -> Vec<u8> {
-        bytes_to_key(K.into(), key_raw)
+        bytes_to_key(KeyPrefix::try_from(K).sdk_unwrap(), key_raw)
     }
 
     /// Returns `true` if the serialized key is present in the map.
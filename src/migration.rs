@@ -0,0 +1,260 @@
+//! Migrations of the layout of the engine storage, e.g. to keys under a new `VersionPrefix`.
+//!
+//! Migrations run in the order of `MIGRATIONS`, in batches of bounded size so that large
+//! states can be migrated over several transactions. Each batch resumes the current migration
+//! from the cursor saved by the previous one. New migrations are added at the end of the list:
+//! an engine upgraded to a version with new migrations runs them after the ones it completed,
+//! while a new engine starts with all of them completed.
+use borsh::{BorshDeserialize, BorshSerialize};
+
+#[cfg(feature = "contract")]
+use crate::account_migration;
+#[cfg(feature = "contract")]
+use crate::parameters::MigrationStatus;
+use crate::prelude::Vec;
+#[cfg(feature = "contract")]
+use crate::prelude::{String, ToString};
+use crate::sdk;
+use crate::storage::{bytes_to_key, KeyPrefix};
+
+/// Key of the progress of the migrations.
+pub(crate) const MIGRATIONS_KEY: &[u8; 10] = b"MIGRATIONS";
+
+/// The migrations, in the order they run.
+const MIGRATIONS: [MigrationId; 1] = [MigrationId::AccountRecords];
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum MigrationId {
+    /// Moves the accounts to `Account` records (see `account_migration`).
+    AccountRecords,
+}
+
+#[cfg(feature = "contract")]
+impl MigrationId {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::AccountRecords => "ACCOUNT_RECORDS",
+        }
+    }
+}
+
+/// A migration run in batches.
+#[cfg(any(feature = "contract", test))]
+trait Migration {
+    /// Runs a batch of at most `limit` steps of the migration, from the given cursor or from
+    /// the start.
+    fn run_batch(&self, cursor: Option<&[u8]>, limit: u32) -> MigrationBatch;
+}
+
+#[cfg(feature = "contract")]
+impl Migration for MigrationId {
+    fn run_batch(&self, cursor: Option<&[u8]>, limit: u32) -> MigrationBatch {
+        match self {
+            Self::AccountRecords => account_migration::migrate(cursor, limit),
+        }
+    }
+}
+
+/// Outcome of a batch of a migration.
+#[cfg(any(feature = "contract", test))]
+pub struct MigrationBatch {
+    pub steps: u32,
+    /// Cursor from which the next batch resumes, or `None` if the migration is complete.
+    pub cursor: Option<Vec<u8>>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Default, Debug, Eq, PartialEq)]
+struct MigrationProgress {
+    /// Number of migrations completed, from the start of `MIGRATIONS`.
+    completed: u32,
+    /// Cursor of the current migration, if it has started.
+    cursor: Option<Vec<u8>>,
+}
+
+/// Whether a migration is completed.
+pub fn is_completed(migration: MigrationId) -> bool {
+    let completed = get_progress().completed as usize;
+    MIGRATIONS.iter().take(completed).any(|id| *id == migration)
+}
+
+/// Marks all the migrations as completed, for a new engine whose storage has the current
+/// layout from the start.
+#[cfg(feature = "contract")]
+pub fn complete_all() {
+    set_progress(&MigrationProgress {
+        completed: MIGRATIONS.len() as u32,
+        cursor: None,
+    });
+}
+
+/// Runs a batch of at most `limit` steps of the current migration, if any. Returns the number
+/// of steps run.
+#[cfg(feature = "contract")]
+pub fn run(limit: u32) -> u32 {
+    let mut progress = get_progress();
+    match run_next_batch(&MIGRATIONS, &mut progress, limit) {
+        Some(steps) => {
+            set_progress(&progress);
+            steps
+        }
+        None => 0,
+    }
+}
+
+/// Runs a batch of at most `limit` steps of the first of `migrations` not completed, and
+/// records it in `progress`. Returns the number of steps run, or `None` if no batch ran.
+#[cfg(any(feature = "contract", test))]
+fn run_next_batch<M: Migration>(
+    migrations: &[M],
+    progress: &mut MigrationProgress,
+    limit: u32,
+) -> Option<u32> {
+    if limit == 0 {
+        return None;
+    }
+    let migration = migrations.get(progress.completed as usize)?;
+    let batch = migration.run_batch(progress.cursor.as_deref(), limit);
+    progress.cursor = batch.cursor;
+    if progress.cursor.is_none() {
+        progress.completed += 1;
+    }
+    Some(batch.steps)
+}
+
+/// Returns the migrations completed and still to run, and the cursor of the current one.
+#[cfg(feature = "contract")]
+pub fn status() -> MigrationStatus {
+    let progress = get_progress();
+    let completed = (progress.completed as usize).min(MIGRATIONS.len());
+    let (completed, pending) = MIGRATIONS.split_at(completed);
+    let names = |migrations: &[MigrationId]| -> Vec<String> {
+        migrations
+            .iter()
+            .map(|migration| migration.name().to_string())
+            .collect()
+    };
+    MigrationStatus {
+        completed: names(completed),
+        pending: names(pending),
+        cursor: progress.cursor,
+    }
+}
+
+fn get_progress() -> MigrationProgress {
+    sdk::read_storage(&bytes_to_key(KeyPrefix::Config, MIGRATIONS_KEY))
+        .map(|value| MigrationProgress::try_from_slice(&value).expect("ERR_DESER"))
+        .unwrap_or_default()
+}
+
+#[cfg(feature = "contract")]
+fn set_progress(progress: &MigrationProgress) {
+    sdk::write_storage(
+        &bytes_to_key(KeyPrefix::Config, MIGRATIONS_KEY),
+        &progress.try_to_vec().expect("ERR_SER"),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{run_next_batch, Migration, MigrationBatch, MigrationProgress};
+    use crate::prelude::Vec;
+    use borsh::{BorshDeserialize, BorshSerialize};
+    use core::cell::RefCell;
+
+    /// Migration of `len` items, with the index of the next item as cursor, which logs its
+    /// batches as `(id, start, steps)`.
+    struct TestMigration<'a> {
+        id: u8,
+        len: u32,
+        log: &'a RefCell<Vec<(u8, u32, u32)>>,
+    }
+
+    impl Migration for TestMigration<'_> {
+        fn run_batch(&self, cursor: Option<&[u8]>, limit: u32) -> MigrationBatch {
+            let start = cursor.map_or(0, |cursor| {
+                let mut bytes = [0u8; 4];
+                bytes.copy_from_slice(cursor);
+                u32::from_le_bytes(bytes)
+            });
+            let end = self.len.min(start + limit);
+            self.log.borrow_mut().push((self.id, start, end - start));
+            MigrationBatch {
+                steps: end - start,
+                cursor: if end < self.len {
+                    Some(end.to_le_bytes().to_vec())
+                } else {
+                    None
+                },
+            }
+        }
+    }
+
+    /// Runs a batch with the progress read back from its serialization, as in a new
+    /// transaction.
+    fn run_in_new_transaction(
+        migrations: &[TestMigration],
+        progress: &mut MigrationProgress,
+        limit: u32,
+    ) -> Option<u32> {
+        *progress = MigrationProgress::try_from_slice(&progress.try_to_vec().unwrap()).unwrap();
+        run_next_batch(migrations, progress, limit)
+    }
+
+    #[test]
+    fn test_migrations_run_in_order_across_batches() {
+        let log = RefCell::new(Vec::new());
+        let migrations = [
+            TestMigration {
+                id: 0,
+                len: 3,
+                log: &log,
+            },
+            TestMigration {
+                id: 1,
+                len: 2,
+                log: &log,
+            },
+        ];
+        let mut progress = MigrationProgress::default();
+
+        assert_eq!(run_in_new_transaction(&migrations, &mut progress, 0), None);
+        assert_eq!(
+            run_in_new_transaction(&migrations, &mut progress, 2),
+            Some(2)
+        );
+        assert_eq!(
+            progress,
+            MigrationProgress {
+                completed: 0,
+                cursor: Some(2u32.to_le_bytes().to_vec()),
+            }
+        );
+
+        // The first migration resumes from its cursor and completes before the second one starts
+        assert_eq!(
+            run_in_new_transaction(&migrations, &mut progress, 2),
+            Some(1)
+        );
+        assert_eq!(
+            progress,
+            MigrationProgress {
+                completed: 1,
+                cursor: None,
+            }
+        );
+        assert_eq!(
+            run_in_new_transaction(&migrations, &mut progress, 2),
+            Some(2)
+        );
+        assert_eq!(
+            progress,
+            MigrationProgress {
+                completed: 2,
+                cursor: None,
+            }
+        );
+        assert_eq!(run_in_new_transaction(&migrations, &mut progress, 2), None);
+
+        assert_eq!(log.into_inner(), vec![(0, 0, 2), (0, 2, 1), (1, 0, 2)]);
+    }
+}
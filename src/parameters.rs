@@ -1,7 +1,9 @@
 use borsh::{BorshDeserialize, BorshSerialize};
 
+use crate::engine::HardForkId;
 use crate::fungible_token::FungibleTokenMetadata;
 use crate::prelude::{String, Vec};
+use crate::receipt::{self, BLOOM_SIZE};
 use crate::types::{AccountId, Balance, RawAddress, RawH256, RawU256};
 use crate::{
     admin_controlled::PausedMask,
@@ -10,6 +12,8 @@ use crate::{
     sdk,
     types::{EthAddress, Proof, SdkUnwrap},
 };
+#[cfg(feature = "evm_bully")]
+use crate::{prelude::U256, types::u256_to_arr};
 use evm::backend::Log;
 
 /// Borsh-encoded parameters for the `new` function.
@@ -27,6 +31,14 @@ pub struct NewCallArgs {
     pub upgrade_delay_blocks: u64,
 }
 
+/// Borsh-encoded parameters for the `schedule_hard_fork` function.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Eq, PartialEq)]
+pub struct ScheduleHardForkArgs {
+    pub hard_fork: HardForkId,
+    /// Block height from which the hard fork is used.
+    pub block_height: u64,
+}
+
 /// Borsh-encoded parameters for the `meta_call` function.
 #[derive(Debug, BorshSerialize, BorshDeserialize)]
 pub struct MetaCallArgs {
@@ -42,7 +54,7 @@ pub struct MetaCallArgs {
 }
 
 /// Borsh-encoded log for use in a `SubmitResult`.
-#[derive(Debug, BorshSerialize, BorshDeserialize)]
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq, Eq)]
 pub struct ResultLog {
     pub topics: Vec<RawU256>,
     pub data: Vec<u8>,
@@ -105,13 +117,184 @@ impl AsRef<[u8]> for TransactionStatus {
 
 /// Borsh-encoded parameters for the `call`, `call_with_args`, `deploy_code`,
 /// and `deploy_with_input` methods.
-#[derive(Debug, BorshSerialize, BorshDeserialize)]
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq, Eq)]
 pub struct SubmitResult {
     pub status: TransactionStatus,
     pub gas_used: u64,
     pub logs: Vec<ResultLog>,
 }
 
+impl SubmitResult {
+    pub fn new(status: TransactionStatus, gas_used: u64, logs: Vec<ResultLog>) -> Self {
+        Self {
+            status,
+            gas_used,
+            logs,
+        }
+    }
+}
+
+/// Borsh-encoded result of the `submit_v2` method: the `SubmitResult` returned by `submit`,
+/// along with the transaction which was executed.
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq, Eq)]
+pub struct SubmitResultV2 {
+    pub result: SubmitResult,
+    pub transaction: SubmittedTransaction,
+    /// Address of the deployed contract, for successful deployments.
+    pub created_address: Option<RawAddress>,
+}
+
+/// Ethereum transaction executed by the `submit_v2` method.
+#[derive(Debug, BorshSerialize, BorshDeserialize, PartialEq, Eq)]
+pub struct SubmittedTransaction {
+    /// Keccak hash of the raw (signed and RLP-encoded) transaction.
+    pub hash: RawH256,
+    pub sender: RawAddress,
+    pub nonce: RawU256,
+}
+
+/// Logs bloom and receipts root of the transactions executed in a NEAR block,
+/// as returned by the `get_block_receipts_summary` function.
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize, PartialEq, Eq)]
+pub struct BlockReceiptsSummary {
+    /// Bloom of the logs of all the transactions.
+    pub logs_bloom: [u8; BLOOM_SIZE],
+    /// Root of the trie of the receipts of the transactions, in execution order.
+    pub receipts_root: RawH256,
+    pub receipts_count: u64,
+    /// Gas used by all the transactions.
+    pub gas_used: u64,
+}
+
+impl Default for BlockReceiptsSummary {
+    fn default() -> Self {
+        Self {
+            logs_bloom: [0; BLOOM_SIZE],
+            receipts_root: receipt::ordered_trie_root(&[]).0,
+            receipts_count: 0,
+            gas_used: 0,
+        }
+    }
+}
+
+/// Borsh-encoded parameters for the `get_proof` function.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Eq, PartialEq)]
+pub struct GetProofArgs {
+    pub address: RawAddress,
+    pub storage_keys: Vec<RawH256>,
+}
+
+/// Merkle proof of an account and some of its storage slots against the state root,
+/// as returned by the `get_proof` function. Same as the result of `eth_getProof`.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Eq, PartialEq)]
+pub struct AccountProof {
+    pub state_root: RawH256,
+    /// Nodes of the account trie on the path to the account, starting with the root.
+    pub account_proof: Vec<Vec<u8>>,
+    pub balance: RawU256,
+    pub nonce: RawU256,
+    pub code_hash: RawH256,
+    pub storage_hash: RawH256,
+    pub storage_proof: Vec<StorageProof>,
+}
+
+/// Merkle proof of a storage slot against the storage root of its account.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Eq, PartialEq)]
+pub struct StorageProof {
+    pub key: RawH256,
+    pub value: RawU256,
+    /// Nodes of the storage trie on the path to the slot, starting with the root.
+    pub proof: Vec<Vec<u8>>,
+}
+
+/// Borsh-encoded parameters for the `dump_state` function.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Eq, PartialEq)]
+pub struct DumpStateArgs {
+    /// Position of the account from which to list the accounts: zero for the first page, then
+    /// the `next.start` position returned by the previous page.
+    pub start: u64,
+    /// Position of the slot from which to list the storage of the first account: zero for the
+    /// first page, then the `next.storageStart` position returned by the previous page.
+    pub storage_start: u64,
+    /// Most accounts to list, at most 100.
+    pub limit: u32,
+    /// Most storage slots to list, at most 1000.
+    pub storage_limit: u32,
+}
+
+/// Borsh-encoded parameters for the `index_accounts` function.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Eq, PartialEq)]
+pub struct IndexAccountsArgs {
+    pub accounts: Vec<IndexedAccount>,
+}
+
+/// Account stored before the account index, with the keys of the slots of its storage which
+/// were written before they were recorded.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Eq, PartialEq)]
+pub struct IndexedAccount {
+    pub address: RawAddress,
+    pub storage_keys: Vec<RawH256>,
+}
+
+/// Borsh-encoded parameters for the `cleanup_storage` function.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Eq, PartialEq)]
+pub struct CleanupStorageArgs {
+    /// Most storage slots to remove, at most 1000.
+    pub max_slots: u32,
+}
+
+/// Borsh-encoded result of the `cleanup_storage` function.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Eq, PartialEq)]
+pub struct CleanupStorageResult {
+    pub removed_slots: u64,
+    /// Slots of old generations still to be removed.
+    pub pending_slots: u64,
+    /// Old generations with slots still to be removed.
+    pub pending_generations: u64,
+    /// NEAR, in yoctoNEAR, paid to the caller.
+    pub reward: u128,
+}
+
+/// Borsh-encoded parameters for the `backfill_state_commitment` function.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Eq, PartialEq)]
+pub struct BackfillStateCommitmentArgs {
+    /// Most steps (storage slots, or accounts without slots) to run, at most 1000.
+    pub limit: u32,
+}
+
+/// Borsh-encoded result of the `backfill_state_commitment` function.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Eq, PartialEq)]
+pub struct BackfillStateCommitmentResult {
+    pub steps: u32,
+    /// Whether all the accounts are part of the state trie, so that it can be served.
+    pub complete: bool,
+}
+
+/// Borsh-encoded parameters for the `run_migrations` function.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Eq, PartialEq)]
+pub struct RunMigrationsArgs {
+    /// Most steps (e.g. accounts migrated) to run, at most 100.
+    pub limit: u32,
+}
+
+/// Borsh-encoded result of the `run_migrations` function.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Eq, PartialEq)]
+pub struct RunMigrationsResult {
+    pub steps: u32,
+    pub status: MigrationStatus,
+}
+
+/// Borsh-encoded result of the `get_migration_status` function.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Eq, PartialEq)]
+pub struct MigrationStatus {
+    /// Names of the storage migrations completed, in the order they ran.
+    pub completed: Vec<String>,
+    /// Names of the migrations still to run, starting with the current one.
+    pub pending: Vec<String>,
+    /// Position from which the current migration resumes, if it has started.
+    pub cursor: Option<Vec<u8>>,
+}
+
 /// Borsh-encoded parameters for the `call` function.
 #[derive(BorshSerialize, BorshDeserialize)]
 pub struct FunctionCallArgs {
@@ -128,6 +311,103 @@ pub struct ViewCallArgs {
     pub input: Vec<u8>,
 }
 
+/// Borsh-encoded parameters for the `view` function, with state overrides.
+///
+/// The `view` function accepts both this and the `ViewCallArgs` layout: the latter is a prefix
+/// of the former, so bytes of one layout always have the wrong length for the other.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Eq, PartialEq)]
+pub struct ViewCallArgsV2 {
+    pub sender: RawAddress,
+    pub address: RawAddress,
+    pub amount: RawU256,
+    pub input: Vec<u8>,
+    /// Changes to the state which only last for the duration of the call,
+    /// like the state override set of `eth_call`.
+    pub state_override: Vec<StateOverride>,
+}
+
+impl From<ViewCallArgs> for ViewCallArgsV2 {
+    fn from(args: ViewCallArgs) -> Self {
+        Self {
+            sender: args.sender,
+            address: args.address,
+            amount: args.amount,
+            input: args.input,
+            state_override: Vec::new(),
+        }
+    }
+}
+
+/// Borsh-encoded override of an account for use in a `ViewCallArgsV2`.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Eq, PartialEq)]
+pub struct StateOverride {
+    pub address: RawAddress,
+    pub balance: Option<RawU256>,
+    pub nonce: Option<RawU256>,
+    pub code: Option<Vec<u8>>,
+    /// Storage slots to replace, the other slots keep their value.
+    pub storage: Vec<(RawH256, RawH256)>,
+}
+
+/// Borsh-encoded parameters for the `estimate_gas` function.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Eq, PartialEq)]
+pub struct EstimateGasArgs {
+    pub sender: RawAddress,
+    pub address: RawAddress,
+    pub amount: RawU256,
+    pub input: Vec<u8>,
+    /// Highest gas limit to try, by default the block gas limit if any, or
+    /// `DEFAULT_ESTIMATE_GAS_CAP` otherwise.
+    pub gas_cap: Option<u64>,
+}
+
+/// Borsh-encoded result of the `estimate_gas` function.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Eq, PartialEq)]
+pub enum EstimateGasResult {
+    /// The lowest gas limit with which the call succeeds.
+    Success(u64),
+    /// The call does not succeed even with the gas cap, with the status of that execution
+    /// (which contains the revert reason, if any).
+    Failure(TransactionStatus),
+}
+
+/// Borsh-encoded result of the `create_access_list` function.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Eq, PartialEq)]
+pub struct CreateAccessListResult {
+    pub access_list: Vec<ResultAccessTuple>,
+    /// Gas used by the call with the access list.
+    pub gas_used: u64,
+    /// Gas used by the call without an access list.
+    pub gas_used_without_access_list: u64,
+    /// Status of the call with the access list.
+    pub status: TransactionStatus,
+}
+
+/// Borsh-encoded access list entry (see EIP-2930) for use in a `CreateAccessListResult`.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Eq, PartialEq)]
+pub struct ResultAccessTuple {
+    pub address: RawAddress,
+    pub storage_keys: Vec<RawH256>,
+}
+
+/// Borsh-encoded parameters for the `trace_call` function.
+#[cfg(feature = "tracing")]
+#[derive(BorshSerialize, BorshDeserialize, Debug, Eq, PartialEq)]
+pub struct TraceCallArgs {
+    pub call: ViewCallArgs,
+    pub tracer: TracerKind,
+}
+
+/// The tracer used by the `trace_call` function.
+#[cfg(feature = "tracing")]
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, Eq, PartialEq)]
+pub enum TracerKind {
+    /// Logs every step of the execution, like geth's `structLogger`.
+    StructLogger,
+    /// Returns the tree of calls, like geth's `callTracer`.
+    CallTracer,
+}
+
 /// Borsh-encoded parameters for `deploy_erc20_token` function.
 #[derive(BorshSerialize, BorshDeserialize, Debug, Eq, PartialEq)]
 pub struct DeployErc20TokenArgs {
@@ -160,9 +440,184 @@ pub struct BeginChainArgs {
     pub genesis_alloc: Vec<AccountBalance>,
 }
 
+/// Borsh-encoded genesis account used by the `begin_chain_v2` and `import_genesis_alloc`
+/// functions, like an account of the `alloc` of a geth genesis file.
+///
+/// Fields which are left out keep their value, so that an account with a large storage can be
+/// imported over several calls.
+#[cfg(feature = "evm_bully")]
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Eq, PartialEq)]
+pub struct GenesisAccount {
+    pub address: RawAddress,
+    pub balance: Option<RawU256>,
+    pub nonce: Option<RawU256>,
+    pub code: Option<Vec<u8>>,
+    /// Storage slots to set, a zero value removes the slot.
+    pub storage: Vec<(RawH256, RawH256)>,
+}
+
+#[cfg(feature = "evm_bully")]
+impl From<AccountBalance> for GenesisAccount {
+    fn from(account: AccountBalance) -> Self {
+        Self {
+            address: account.address,
+            balance: Some(account.balance),
+            nonce: None,
+            code: None,
+            storage: Vec::new(),
+        }
+    }
+}
+
+/// Borsh-encoded parameters for the `begin_chain_v2` function, with full genesis accounts.
+///
+/// The JSON form, taken by `begin_chain_json`, is `{"chain_id": 1, "alloc": {...}}`, with the
+/// `alloc` of a geth genesis file.
+#[cfg(feature = "evm_bully")]
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Eq, PartialEq)]
+pub struct BeginChainArgsV2 {
+    pub chain_id: RawU256,
+    pub genesis_alloc: Vec<GenesisAccount>,
+}
+
+#[cfg(feature = "evm_bully")]
+impl From<BeginChainArgs> for BeginChainArgsV2 {
+    fn from(args: BeginChainArgs) -> Self {
+        Self {
+            chain_id: args.chain_id,
+            genesis_alloc: args.genesis_alloc.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+#[cfg(feature = "evm_bully")]
+impl TryFrom<json::JsonValue> for BeginChainArgsV2 {
+    type Error = json::JsonError;
+
+    fn try_from(value: json::JsonValue) -> Result<Self, Self::Error> {
+        Ok(Self {
+            chain_id: u256_to_arr(&U256::from(value.u64("chain_id")?)),
+            genesis_alloc: genesis_alloc_from_json(&value)?,
+        })
+    }
+}
+
+/// Borsh-encoded parameters for the `import_genesis_alloc` function, which imports genesis
+/// accounts in chunks after `begin_chain`.
+///
+/// The JSON form, taken by `import_genesis_alloc_json`, is `{"alloc": {...}}`, with (part of)
+/// the `alloc` of a geth genesis file.
+#[cfg(feature = "evm_bully")]
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Eq, PartialEq)]
+pub struct GenesisAllocArgs {
+    pub genesis_alloc: Vec<GenesisAccount>,
+}
+
+#[cfg(feature = "evm_bully")]
+impl TryFrom<json::JsonValue> for GenesisAllocArgs {
+    type Error = json::JsonError;
+
+    fn try_from(value: json::JsonValue) -> Result<Self, Self::Error> {
+        Ok(Self {
+            genesis_alloc: genesis_alloc_from_json(&value)?,
+        })
+    }
+}
+
+/// Parses the `alloc` object of a geth genesis file, which maps addresses to accounts.
+#[cfg(feature = "evm_bully")]
+fn genesis_alloc_from_json(
+    value: &json::JsonValue,
+) -> Result<Vec<GenesisAccount>, json::JsonError> {
+    let alloc = match value {
+        json::JsonValue::Object(o) => o.get("alloc").ok_or(json::JsonError::MissingValue)?,
+        _ => return Err(json::JsonError::NotJsonType),
+    };
+    let alloc = match alloc {
+        json::JsonValue::Object(alloc) => alloc,
+        _ => return Err(json::JsonError::NotJsonType),
+    };
+    alloc
+        .iter()
+        .map(|(address, account)| {
+            let account = match account {
+                json::JsonValue::Object(account) => account,
+                _ => return Err(json::JsonError::NotJsonType),
+            };
+            let storage = match account.get("storage") {
+                None => Vec::new(),
+                Some(json::JsonValue::Object(storage)) => storage
+                    .iter()
+                    .map(|(key, value)| {
+                        Ok((
+                            fixed_bytes_from_hex(key)?,
+                            fixed_bytes_from_hex(&string_from_json(value)?)?,
+                        ))
+                    })
+                    .collect::<Result<_, _>>()?,
+                Some(_) => return Err(json::JsonError::NotJsonType),
+            };
+            Ok(GenesisAccount {
+                address: fixed_bytes_from_hex(address)?,
+                balance: account.get("balance").map(u256_from_json).transpose()?,
+                nonce: account.get("nonce").map(u256_from_json).transpose()?,
+                code: account
+                    .get("code")
+                    .map(|code| bytes_from_hex(&string_from_json(code)?))
+                    .transpose()?,
+                storage,
+            })
+        })
+        .collect()
+}
+
+#[cfg(feature = "evm_bully")]
+fn string_from_json(value: &json::JsonValue) -> Result<String, json::JsonError> {
+    match value {
+        json::JsonValue::String(s) => Ok(s.clone()),
+        _ => Err(json::JsonError::InvalidString),
+    }
+}
+
+/// Parses a quantity, given as a number or as a hexadecimal or decimal string.
+#[cfg(feature = "evm_bully")]
+fn u256_from_json(value: &json::JsonValue) -> Result<RawU256, json::JsonError> {
+    let value = match value {
+        json::JsonValue::U64(n) => U256::from(*n),
+        json::JsonValue::String(s) => match s.strip_prefix("0x") {
+            Some(_) => U256::from_big_endian(&fixed_bytes_from_hex::<32>(s)?),
+            None => U256::from_dec_str(s).map_err(|_| json::JsonError::InvalidU256)?,
+        },
+        _ => return Err(json::JsonError::InvalidU256),
+    };
+    Ok(u256_to_arr(&value))
+}
+
+/// Parses hexadecimal data, with or without a `0x` prefix.
+#[cfg(feature = "evm_bully")]
+fn bytes_from_hex(s: &str) -> Result<Vec<u8>, json::JsonError> {
+    let s = s.strip_prefix("0x").unwrap_or(s);
+    if s.len() % 2 == 1 {
+        return hex::decode(["0", s].concat()).map_err(|_| json::JsonError::InvalidHex);
+    }
+    hex::decode(s).map_err(|_| json::JsonError::InvalidHex)
+}
+
+/// Parses hexadecimal data of at most `N` bytes, padded with zeros on the left.
+#[cfg(feature = "evm_bully")]
+fn fixed_bytes_from_hex<const N: usize>(s: &str) -> Result<[u8; N], json::JsonError> {
+    let bytes = bytes_from_hex(s)?;
+    if bytes.len() > N {
+        return Err(json::JsonError::InvalidHex);
+    }
+    let mut result = [0u8; N];
+    result[N - bytes.len()..].copy_from_slice(&bytes);
+    Ok(result)
+}
+
 /// Borsh-encoded parameters for the `begin_block` function.
 #[cfg(feature = "evm_bully")]
-#[derive(BorshSerialize, BorshDeserialize)]
+#[derive(BorshSerialize, BorshDeserialize, Clone)]
 pub struct BeginBlockArgs {
     /// The current block's hash (for replayer use).
     pub hash: RawU256,
@@ -483,6 +938,55 @@ mod tests {
         let _ = ViewCallArgs::try_from_slice(&bytes).unwrap_err();
     }
 
+    #[test]
+    fn test_view_call_args_layouts() {
+        let args = ViewCallArgs {
+            sender: [1; 20],
+            address: [2; 20],
+            amount: [3; 32],
+            input: vec![1, 2, 3],
+        };
+        let bytes = args.try_to_vec().unwrap();
+        let _ = ViewCallArgsV2::try_from_slice(&bytes).unwrap_err();
+
+        let mut args_v2 = ViewCallArgsV2::from(args);
+        args_v2.state_override.push(StateOverride {
+            address: [2; 20],
+            balance: Some([4; 32]),
+            nonce: None,
+            code: Some(vec![5]),
+            storage: vec![([6; 32], [7; 32])],
+        });
+        let bytes = args_v2.try_to_vec().unwrap();
+        let _ = ViewCallArgs::try_from_slice(&bytes).unwrap_err();
+        assert_eq!(ViewCallArgsV2::try_from_slice(&bytes).unwrap(), args_v2);
+    }
+
+    #[test]
+    fn test_roundtrip_submit_result_v2() {
+        let result = SubmitResultV2 {
+            result: SubmitResult::new(
+                TransactionStatus::Succeed(vec![1, 2, 3]),
+                21_000,
+                vec![ResultLog {
+                    topics: vec![[4; 32]],
+                    data: vec![5, 6],
+                }],
+            ),
+            transaction: SubmittedTransaction {
+                hash: [7; 32],
+                sender: [8; 20],
+                nonce: [9; 32],
+            },
+            created_address: Some([10; 20]),
+        };
+
+        // The `SubmitResult` layout is a prefix of the `SubmitResultV2` layout
+        let bytes = result.try_to_vec().unwrap();
+        assert!(bytes.starts_with(&result.result.try_to_vec().unwrap()));
+        assert_eq!(SubmitResultV2::try_from_slice(&bytes).unwrap(), result);
+    }
+
     #[test]
     fn test_roundtrip_view_call() {
         let x = ViewCallArgs {
@@ -495,4 +999,49 @@ mod tests {
         let res = ViewCallArgs::try_from_slice(&bytes).unwrap();
         assert_eq!(x, res);
     }
+
+    #[cfg(feature = "evm_bully")]
+    #[test]
+    fn test_begin_chain_args_from_json() {
+        let json = json::parse_json(
+            br#"{
+                "chain_id": 1,
+                "alloc": {
+                    "0x0000000000000000000000000000000000000001": {"balance": "1000"},
+                    "0000000000000000000000000000000000000002": {
+                        "balance": "0x10",
+                        "nonce": "0x1",
+                        "code": "0x6000",
+                        "storage": {"0x01": "0x000000000000000000000000000000000000000000000000000000000000002a"}
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+        let args = BeginChainArgsV2::try_from(json).unwrap();
+
+        let mut storage_key = [0u8; 32];
+        storage_key[31] = 1;
+        let mut storage_value = [0u8; 32];
+        storage_value[31] = 42;
+        let mut second_address = [0u8; 20];
+        second_address[19] = 2;
+        assert_eq!(args.chain_id, u256_to_arr(&U256::one()));
+        // The accounts are ordered by their key in the JSON object
+        assert_eq!(
+            args.genesis_alloc[0],
+            GenesisAccount {
+                address: second_address,
+                balance: Some(u256_to_arr(&U256::from(16))),
+                nonce: Some(u256_to_arr(&U256::one())),
+                code: Some(vec![0x60, 0x00]),
+                storage: vec![(storage_key, storage_value)],
+            }
+        );
+        assert_eq!(
+            args.genesis_alloc[1].balance,
+            Some(u256_to_arr(&U256::from(1000)))
+        );
+        assert_eq!(args.genesis_alloc[1].code, None);
+    }
 }
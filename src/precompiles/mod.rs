@@ -87,13 +87,13 @@ pub trait HardFork {}
 /// Homestead hard fork marker.
 pub struct Homestead;
 
-/// Homestead hard fork marker.
+/// Byzantium hard fork marker.
 pub struct Byzantium;
 
-/// Homestead hard fork marker.
+/// Istanbul hard fork marker.
 pub struct Istanbul;
 
-/// Homestead hard fork marker.
+/// Berlin hard fork marker.
 pub struct Berlin;
 
 impl HardFork for Homestead {}
@@ -193,9 +193,45 @@ impl Precompiles {
         Precompiles { addresses, fun }
     }
 
-    #[allow(dead_code)]
-    fn new_berlin() -> Self {
-        Self::new_istanbul()
+    /// Same as the Istanbul set, except `ModExp` is priced according to EIP-2565.
+    pub fn new_berlin() -> Self {
+        let addresses = vec![
+            ECRecover::ADDRESS,
+            SHA256::ADDRESS,
+            RIPEMD160::ADDRESS,
+            Identity::ADDRESS,
+            ModExp::<Berlin>::ADDRESS,
+            Bn128Add::<Istanbul>::ADDRESS,
+            Bn128Mul::<Istanbul>::ADDRESS,
+            Bn128Pair::<Istanbul>::ADDRESS,
+            Blake2F::ADDRESS,
+            ExitToNear::ADDRESS,
+            ExitToEthereum::ADDRESS,
+        ];
+        let fun: Vec<PrecompileFn> = vec![
+            ECRecover::run,
+            SHA256::run,
+            RIPEMD160::run,
+            Identity::run,
+            ModExp::<Berlin>::run,
+            Bn128Add::<Istanbul>::run,
+            Bn128Mul::<Istanbul>::run,
+            Bn128Pair::<Istanbul>::run,
+            Blake2F::run,
+            ExitToNear::run,
+            ExitToEthereum::run,
+        ];
+
+        Precompiles { addresses, fun }
+    }
+
+    /// London did not introduce or reprice any precompiles, so this is the Berlin set.
+    pub fn new_london() -> Self {
+        Self::new_berlin()
+    }
+
+    pub fn addresses(&self) -> &[Address] {
+        &self.addresses
     }
 
     fn get_fun(&self, address: &Address) -> Option<PrecompileFn> {
@@ -5,7 +5,9 @@ pub use alloc::{
     boxed::Box,
     collections::BTreeMap as HashMap,
     collections::BTreeMap,
-    fmt, format, str,
+    fmt, format,
+    rc::Rc,
+    str,
     string::String,
     string::ToString,
     vec,
@@ -36,6 +38,7 @@ pub use std::{
     marker::PhantomData,
     mem,
     ops::{Add, Sub},
+    rc::Rc,
     str,
     string::String,
     string::ToString,
@@ -0,0 +1,109 @@
+//! Ethereum receipts, logs blooms and receipts roots.
+use evm::backend::Log;
+use rlp::RlpStream;
+
+use crate::log_entry::LogEntry;
+use crate::prelude::{Vec, H256};
+use crate::trie::trie_root;
+use crate::types::keccak;
+
+pub const BLOOM_SIZE: usize = 256;
+
+/// Adds `input` to a 2048-bit logs bloom, as specified in the yellow paper.
+pub fn accrue_bloom(bloom: &mut [u8; BLOOM_SIZE], input: &[u8]) {
+    let hash = keccak(input);
+    let hash = hash.as_bytes();
+    for i in [0, 2, 4].iter() {
+        let bit = (usize::from(hash[*i]) << 8 | usize::from(hash[i + 1])) & 0x7ff;
+        bloom[BLOOM_SIZE - 1 - bit / 8] |= 1 << (bit % 8);
+    }
+}
+
+/// Returns the bloom of the addresses and topics of the logs.
+pub fn logs_bloom(logs: &[Log]) -> [u8; BLOOM_SIZE] {
+    let mut bloom = [0u8; BLOOM_SIZE];
+    for log in logs {
+        accrue_bloom(&mut bloom, log.address.as_bytes());
+        for topic in log.topics.iter() {
+            accrue_bloom(&mut bloom, topic.as_bytes());
+        }
+    }
+    bloom
+}
+
+/// Returns the encoding of the receipt of a transaction (see EIP-658): its RLP encoding, which
+/// is prefixed with the type of the transaction for typed transactions (see EIP-2718).
+pub fn encode_receipt(
+    transaction_type: Option<u8>,
+    succeeded: bool,
+    cumulative_gas_used: u64,
+    logs_bloom: &[u8; BLOOM_SIZE],
+    logs: &[Log],
+) -> Vec<u8> {
+    let logs: Vec<LogEntry> = logs
+        .iter()
+        .map(|log| LogEntry {
+            address: log.address,
+            topics: log.topics.clone(),
+            data: log.data.clone(),
+        })
+        .collect();
+    let mut stream = RlpStream::new_list(4);
+    stream.append(&u8::from(succeeded));
+    stream.append(&cumulative_gas_used);
+    stream.append(&logs_bloom.to_vec());
+    stream.append_list::<LogEntry, _>(&logs);
+    let mut encoded = Vec::new();
+    encoded.extend(transaction_type);
+    encoded.extend_from_slice(&stream.out());
+    encoded
+}
+
+/// Returns the root of the Merkle Patricia trie which maps the RLP encoding of the index of
+/// each value to the value, as used for the receipts root of a block.
+pub fn ordered_trie_root(values: &[Vec<u8>]) -> H256 {
+    trie_root(
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| (rlp::encode(&(i as u64)).to_vec(), value.clone()))
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::{vec, Address};
+
+    #[test]
+    fn test_empty_receipts_root() {
+        assert_eq!(ordered_trie_root(&[]), crate::trie::EMPTY_TRIE_ROOT);
+    }
+
+    #[test]
+    fn test_typed_receipt() {
+        let bloom = [0u8; BLOOM_SIZE];
+        let legacy = encode_receipt(None, true, 21_000, &bloom, &[]);
+        let typed = encode_receipt(Some(0x02), true, 21_000, &bloom, &[]);
+        assert_eq!(typed[0], 0x02);
+        assert_eq!(typed[1..], legacy[..]);
+    }
+
+    #[test]
+    fn test_logs_bloom() {
+        let address = Address([1; 20]);
+        let log = Log {
+            address,
+            topics: Vec::new(),
+            data: vec![1, 2, 3],
+        };
+        let bloom = logs_bloom(&[log]);
+
+        let bits: u32 = bloom.iter().map(|b| b.count_ones()).sum();
+        assert!(bits > 0 && bits <= 3);
+        let mut address_bloom = [0u8; BLOOM_SIZE];
+        accrue_bloom(&mut address_bloom, address.as_bytes());
+        assert_eq!(bloom, address_bloom);
+    }
+}
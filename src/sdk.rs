@@ -45,7 +45,7 @@ mod exports {
         // ############
         // # Math API #
         // ############
-        fn random_seed(register_id: u64);
+        pub(crate) fn random_seed(register_id: u64);
         pub(crate) fn sha256(value_len: u64, value_ptr: u64, register_id: u64);
         pub(crate) fn keccak256(value_len: u64, value_ptr: u64, register_id: u64);
         pub(crate) fn ripemd160(value_len: u64, value_ptr: u64, register_id: u64);
@@ -280,6 +280,12 @@ pub fn remove_storage(key: &[u8]) {
         exports::storage_remove(key.len() as u64, key.as_ptr() as u64, EVICTED_REGISTER);
     }
 }
+
+/// Returns the number of bytes of storage used by the contract account.
+pub fn storage_usage() -> u64 {
+    unsafe { exports::storage_usage() }
+}
+
 /// Returns the size of the blob stored in the given register.
 /// * If register is used, then returns the size, which can potentially be zero;
 /// * If register is not used, returns `u64::MAX`
@@ -309,10 +315,9 @@ pub fn remove_storage_with_result(key: &[u8]) -> Option<Vec<u8>> {
 }
 
 #[allow(dead_code)]
+/// Returns the timestamp of the current block in nanoseconds.
 pub fn block_timestamp() -> u64 {
-    // NEAR timestamp is in nanoseconds
-    let timestamp_ns = unsafe { exports::block_timestamp() };
-    timestamp_ns / 1000 // convert to milliseconds for Ethereum compatibility
+    unsafe { exports::block_timestamp() }
 }
 
 pub fn block_index() -> u64 {
@@ -347,6 +352,16 @@ pub fn predecessor_account_id() -> Vec<u8> {
     }
 }
 
+/// Returns the random seed of the current block.
+pub fn random_seed() -> Vec<u8> {
+    unsafe {
+        exports::random_seed(1);
+        let bytes: Vec<u8> = vec![0u8; exports::register_len(1) as usize];
+        exports::read_register(1, bytes.as_ptr() as *const u64 as u64);
+        bytes
+    }
+}
+
 #[allow(dead_code)]
 pub fn signer_account_id() -> Vec<u8> {
     unsafe {
@@ -605,7 +620,6 @@ pub fn promise_batch_action_function_call(
     }
 }
 
-#[allow(dead_code)]
 pub fn storage_has_key(key: &[u8]) -> bool {
     unsafe { exports::storage_has_key(key.len() as _, key.as_ptr() as _) == 1 }
 }
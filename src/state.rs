@@ -3,14 +3,16 @@ use evm::executor::{MemoryStackState, StackState, StackSubstateMetadata};
 use evm::{ExitError, Transfer};
 
 use crate::engine::Engine;
-use crate::parameters::PromiseCreateArgs;
-use crate::prelude::{Vec, H160, H256, U256};
+use crate::parameters::{PromiseCreateArgs, StateOverride};
+use crate::prelude::{BTreeMap, Vec, H160, H256, U256};
 use crate::types::Stack;
 use crate::AuroraState;
 
 pub struct AuroraStackState<'backend, 'config> {
     memory_stack_state: MemoryStackState<'backend, 'config, Engine>,
     promises: Stack<PromiseCreateArgs>,
+    /// Overridden nonces, with the nonces they replace.
+    nonce_overrides: BTreeMap<H160, (U256, U256)>,
 }
 
 impl<'backend, 'config> AuroraStackState<'backend, 'config> {
@@ -18,6 +20,36 @@ impl<'backend, 'config> AuroraStackState<'backend, 'config> {
         Self {
             memory_stack_state: MemoryStackState::new(metadata, backend),
             promises: Stack::new(),
+            nonce_overrides: BTreeMap::new(),
+        }
+    }
+
+    /// Replaces parts of the state of some accounts, as seen by the execution.
+    ///
+    /// The changes are made like any other change of the execution, so they are only saved
+    /// if the state is applied. The original value of overridden storage slots (as used
+    /// to compute the gas cost of SSTORE) is still their value in the backend.
+    pub fn apply_overrides(&mut self, overrides: &[StateOverride]) {
+        for account in overrides {
+            let address = H160(account.address);
+            if let Some(balance) = account.balance {
+                self.memory_stack_state.reset_balance(address);
+                self.memory_stack_state
+                    .deposit(address, U256::from_big_endian(&balance));
+            }
+            if let Some(nonce) = account.nonce {
+                // Nonces can only be incremented, so the override is applied when reading them.
+                let original_nonce = self.memory_stack_state.basic(address).nonce;
+                self.nonce_overrides
+                    .insert(address, (U256::from_big_endian(&nonce), original_nonce));
+            }
+            if let Some(code) = &account.code {
+                self.memory_stack_state.set_code(address, code.clone());
+            }
+            for (key, value) in account.storage.iter() {
+                self.memory_stack_state
+                    .set_storage(address, H256(*key), H256(*value));
+            }
         }
     }
 
@@ -73,6 +105,10 @@ impl<'backend, 'config> Backend for AuroraStackState<'backend, 'config> {
         self.memory_stack_state.block_gas_limit()
     }
 
+    fn block_base_fee_per_gas(&self) -> U256 {
+        self.memory_stack_state.block_base_fee_per_gas()
+    }
+
     fn chain_id(&self) -> U256 {
         self.memory_stack_state.chain_id()
     }
@@ -82,7 +118,12 @@ impl<'backend, 'config> Backend for AuroraStackState<'backend, 'config> {
     }
 
     fn basic(&self, address: H160) -> Basic {
-        self.memory_stack_state.basic(address)
+        let mut basic = self.memory_stack_state.basic(address);
+        if let Some((nonce, original_nonce)) = self.nonce_overrides.get(&address) {
+            // The nonce may have been incremented since it was overridden
+            basic.nonce = nonce + basic.nonce.saturating_sub(*original_nonce);
+        }
+        basic
     }
 
     fn code(&self, address: H160) -> Vec<u8> {
@@ -0,0 +1,319 @@
+//! Optional commitment to the state of the EVM, in the form of Ethereum's state trie.
+//!
+//! Accounts are kept in a Merkle Patricia trie keyed by the hash of their address, and the
+//! storage of each account in a trie keyed by the hash of the slot, so that accounts and slots
+//! can be proven against the state root as with `eth_getProof`. The tries are updated
+//! incrementally once the commitment is enabled, and the accounts stored before then are added
+//! in batches by `backfill`, from the account index (see `account_index`) and the recorded keys
+//! of their storage (see `storage_cleanup`). The root and the proofs are only served once the
+//! backfill is complete, which it is not until the account index is known to list all the
+//! accounts stored before it existed. Nodes are reference counted and removed once they are no longer part
+//! of any trie.
+use borsh::{BorshDeserialize, BorshSerialize};
+use rlp::RlpStream;
+
+#[cfg(feature = "contract")]
+use crate::account_index;
+use crate::engine::Engine;
+#[cfg(feature = "contract")]
+use crate::parameters::{AccountProof, BackfillStateCommitmentResult, StorageProof};
+use crate::prelude::{Address, Vec, H256, U256};
+use crate::sdk;
+use crate::storage::{bytes_to_key, KeyPrefix};
+#[cfg(feature = "contract")]
+use crate::storage_cleanup;
+use crate::trie::{self, StorageNodeStore, EMPTY_TRIE_ROOT};
+use crate::types::{keccak, RawH256, EMPTY_CODE_HASH};
+
+const STATE_ROOT_ID: u8 = 0;
+const ACCOUNT_ID: u8 = 1;
+const NODE_ID: u8 = 2;
+const NODE_REFCOUNT_ID: u8 = 3;
+#[cfg(feature = "contract")]
+const BACKFILL_ID: u8 = 4;
+
+/// Errors serving the state root or proofs.
+#[cfg(feature = "contract")]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum StateCommitmentError {
+    /// The commitment is not enabled.
+    Disabled,
+    /// Accounts stored before the commitment was enabled are still to be added to it.
+    Incomplete,
+}
+
+#[cfg(feature = "contract")]
+impl AsRef<[u8]> for StateCommitmentError {
+    fn as_ref(&self) -> &[u8] {
+        match self {
+            Self::Disabled => b"ERR_STATE_COMMITMENT_DISABLED",
+            Self::Incomplete => b"ERR_STATE_COMMITMENT_INCOMPLETE",
+        }
+    }
+}
+
+/// Position in the account index from which the backfill continues.
+#[cfg(feature = "contract")]
+#[derive(BorshSerialize, BorshDeserialize, Default)]
+struct BackfillCursor {
+    start: u64,
+    /// Position in the recorded storage keys of the account at `start`.
+    storage_start: u64,
+}
+
+/// Parts of the trie leaf of an account which are not stored elsewhere.
+#[derive(BorshSerialize, BorshDeserialize)]
+struct AccountCommitment {
+    storage_root: RawH256,
+    code_hash: RawH256,
+}
+
+impl Default for AccountCommitment {
+    fn default() -> Self {
+        Self {
+            storage_root: EMPTY_TRIE_ROOT.0,
+            code_hash: EMPTY_CODE_HASH.0,
+        }
+    }
+}
+
+/// Starts maintaining the commitment, from the empty state. The accounts already stored are
+/// then to be added with `backfill`.
+#[cfg(feature = "contract")]
+pub fn enable() {
+    if state_root().is_none() {
+        sdk::write_storage(&state_root_key(), EMPTY_TRIE_ROOT.as_bytes());
+        set_backfill_cursor(&BackfillCursor::default());
+    }
+}
+
+/// Returns the root of the state trie, if the commitment is enabled. The trie may not include
+/// all the accounts yet, see `get_state_root`.
+pub fn state_root() -> Option<H256> {
+    sdk::read_storage(&state_root_key()).map(|value| H256::from_slice(&value))
+}
+
+/// Returns the root of the state trie, once it includes all the accounts.
+#[cfg(feature = "contract")]
+pub fn get_state_root() -> Result<H256, StateCommitmentError> {
+    let state_root = state_root().ok_or(StateCommitmentError::Disabled)?;
+    if get_backfill_cursor().is_some() {
+        return Err(StateCommitmentError::Incomplete);
+    }
+    Ok(state_root)
+}
+
+/// Adds the accounts stored before the commitment was enabled, along with their storage, in at
+/// most `limit` steps: one per storage slot, or one per account without slots. The accounts
+/// and slots modified since the commitment was enabled are added again, in their current state.
+/// The backfill only completes once the account index is complete (see `account_index`), and
+/// then goes on with the accounts added to it since.
+#[cfg(feature = "contract")]
+pub fn backfill(limit: u32) -> BackfillStateCommitmentResult {
+    let mut cursor = match get_backfill_cursor() {
+        Some(cursor) => cursor,
+        None => {
+            return BackfillStateCommitmentResult {
+                steps: 0,
+                complete: state_root().is_some(),
+            }
+        }
+    };
+    let limit = u64::from(limit);
+    let accounts_len = account_index::len();
+    let mut steps = 0;
+    while steps < limit && cursor.start < accounts_len {
+        let address = account_index::get(cursor.start).expect("ERR_CORRUPTED_STORAGE");
+        let code_hash = if cursor.storage_start == 0 {
+            Some(Engine::get_code_hash(&address))
+        } else {
+            None
+        };
+        let generation = Engine::get_generation(&address);
+        let slot_count = storage_cleanup::get_slot_count(&address, generation);
+        let storage_end = slot_count.min(cursor.storage_start + (limit - steps));
+        let storage: Vec<(H256, H256)> = (cursor.storage_start..storage_end)
+            .filter_map(|index| storage_cleanup::get_slot(&address, generation, index))
+            .map(|key| (key, Engine::get_storage(&address, &key, generation)))
+            .collect();
+        steps += storage_end.saturating_sub(cursor.storage_start).max(1);
+        update_account(&address, code_hash, false, &storage);
+        cursor = if storage_end < slot_count {
+            BackfillCursor {
+                start: cursor.start,
+                storage_start: storage_end,
+            }
+        } else {
+            BackfillCursor {
+                start: cursor.start + 1,
+                storage_start: 0,
+            }
+        };
+    }
+    let complete = cursor.start >= accounts_len && account_index::is_complete();
+    if complete {
+        sdk::remove_storage(&backfill_cursor_key());
+    } else {
+        set_backfill_cursor(&cursor);
+    }
+    BackfillStateCommitmentResult {
+        steps: steps as u32,
+        complete,
+    }
+}
+
+/// Updates the leaf of an account after a change of its balance or nonce.
+pub fn touch_account(address: &Address) {
+    update_account(address, None, false, &[]);
+}
+
+/// Removes an account from the commitment, after its removal from the state.
+pub fn remove_account(address: &Address) {
+    update_account(address, Some(EMPTY_CODE_HASH), true, &[]);
+}
+
+/// Updates the commitment to an account after a change to its state: its nonce and balance
+/// are read from the storage, and the hash of its new code and its storage changes are the ones
+/// given. Resetting the storage releases the nodes of the previous storage trie.
+pub fn update_account(
+    address: &Address,
+    code_hash: Option<H256>,
+    reset_storage: bool,
+    storage: &[(H256, H256)],
+) {
+    let state_root = match state_root() {
+        Some(state_root) => state_root,
+        None => return,
+    };
+    let mut store = node_store();
+    let mut account = get_account_commitment(address);
+    if let Some(code_hash) = code_hash {
+        account.code_hash = code_hash.0;
+    }
+    let mut storage_root = H256(account.storage_root);
+    if reset_storage {
+        trie::clear(&mut store, &storage_root);
+        storage_root = EMPTY_TRIE_ROOT;
+    }
+    for (key, value) in storage {
+        let key = keccak(key.as_bytes());
+        storage_root = if value.is_zero() {
+            trie::remove(&mut store, &storage_root, key.as_bytes())
+        } else {
+            let value = rlp::encode(&U256::from_big_endian(value.as_bytes())).to_vec();
+            trie::insert(&mut store, &storage_root, key.as_bytes(), value)
+        };
+    }
+    account.storage_root = storage_root.0;
+
+    let record = Engine::get_account(address);
+    let nonce = record.nonce();
+    let balance = record.balance().raw();
+    let key = keccak(address.as_bytes());
+    // Empty accounts are not part of the state, see EIP-161.
+    let state_root = if nonce.is_zero()
+        && balance.is_zero()
+        && account.code_hash == EMPTY_CODE_HASH.0
+        && account.storage_root == EMPTY_TRIE_ROOT.0
+    {
+        sdk::remove_storage(&account_key(address));
+        trie::remove(&mut store, &state_root, key.as_bytes())
+    } else {
+        sdk::write_storage(
+            &account_key(address),
+            &account.try_to_vec().expect("ERR_SER"),
+        );
+        let leaf = encode_account(&nonce, &balance, &account);
+        trie::insert(&mut store, &state_root, key.as_bytes(), leaf)
+    };
+    sdk::write_storage(&state_root_key(), state_root.as_bytes());
+}
+
+/// Returns the proof of an account and of the given storage slots, once the state trie includes
+/// all the accounts.
+#[cfg(feature = "contract")]
+pub fn get_proof(
+    address: &Address,
+    storage_keys: &[RawH256],
+) -> Result<AccountProof, StateCommitmentError> {
+    let state_root = get_state_root()?;
+    let store = node_store();
+    let account = get_account_commitment(address);
+    let record = Engine::get_account(address);
+    let storage_root = H256(account.storage_root);
+    let storage_proof = storage_keys
+        .iter()
+        .map(|key| {
+            let value = Engine::get_storage(address, &H256(*key), record.generation);
+            StorageProof {
+                key: *key,
+                value: value.0,
+                proof: trie::prove(&store, &storage_root, keccak(key).as_bytes()),
+            }
+        })
+        .collect();
+    Ok(AccountProof {
+        state_root: state_root.0,
+        account_proof: trie::prove(&store, &state_root, keccak(address.as_bytes()).as_bytes()),
+        balance: record.balance,
+        nonce: record.nonce,
+        code_hash: account.code_hash,
+        storage_hash: account.storage_root,
+        storage_proof,
+    })
+}
+
+/// Returns the RLP encoding of the leaf of an account in the state trie.
+fn encode_account(nonce: &U256, balance: &U256, account: &AccountCommitment) -> Vec<u8> {
+    let mut stream = RlpStream::new_list(4);
+    stream.append(nonce);
+    stream.append(balance);
+    stream.append(&H256(account.storage_root));
+    stream.append(&H256(account.code_hash));
+    stream.out().to_vec()
+}
+
+fn get_account_commitment(address: &Address) -> AccountCommitment {
+    sdk::read_storage(&account_key(address))
+        .map(|value| AccountCommitment::try_from_slice(&value).expect("ERR_DESER"))
+        .unwrap_or_default()
+}
+
+#[cfg(feature = "contract")]
+fn get_backfill_cursor() -> Option<BackfillCursor> {
+    sdk::read_storage(&backfill_cursor_key())
+        .map(|value| BackfillCursor::try_from_slice(&value).expect("ERR_DESER"))
+}
+
+#[cfg(feature = "contract")]
+fn set_backfill_cursor(cursor: &BackfillCursor) {
+    sdk::write_storage(
+        &backfill_cursor_key(),
+        &cursor.try_to_vec().expect("ERR_SER"),
+    );
+}
+
+#[cfg(feature = "contract")]
+fn backfill_cursor_key() -> Vec<u8> {
+    bytes_to_key(KeyPrefix::StateCommitment, &[BACKFILL_ID])
+}
+
+fn state_root_key() -> Vec<u8> {
+    bytes_to_key(KeyPrefix::StateCommitment, &[STATE_ROOT_ID])
+}
+
+fn account_key(address: &Address) -> Vec<u8> {
+    bytes_to_key(
+        KeyPrefix::StateCommitment,
+        &[&[ACCOUNT_ID], address.as_bytes()].concat(),
+    )
+}
+
+/// Trie nodes, kept in the contract storage.
+fn node_store() -> StorageNodeStore {
+    StorageNodeStore {
+        prefix: KeyPrefix::StateCommitment,
+        node_id: NODE_ID,
+        refcount_id: NODE_REFCOUNT_ID,
+    }
+}
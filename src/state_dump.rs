@@ -0,0 +1,240 @@
+//! Export of the EVM accounts, in the JSON format of geth's `dump` command.
+//!
+//! Accounts are listed in the order of the account index (see `account_index`), and their
+//! storage in the order its keys were recorded (see `storage_cleanup`). These lists replace an
+//! iteration over the keys of the contract with `storage_iter_prefix`, whose host functions are
+//! deprecated by NEAR. Pages are bounded both in accounts and in slots, so that the storage of
+//! an account may be split over several pages.
+//!
+//! Accounts stored before the account index existed, and the slots written before their keys
+//! were recorded, are only listed once added with `index_accounts`. Each page tells whether the
+//! owner confirmed that all of them were added: until then, the dump may be missing some.
+#[cfg(feature = "contract")]
+use crate::account_index;
+use crate::engine::Account;
+#[cfg(feature = "contract")]
+use crate::engine::Engine;
+#[cfg(feature = "contract")]
+use crate::parameters::DumpStateArgs;
+use crate::prelude::{format, Address, String, ToString, Vec, H256};
+#[cfg(feature = "contract")]
+use crate::sdk;
+#[cfg(feature = "contract")]
+use crate::state_commitment;
+#[cfg(feature = "contract")]
+use crate::storage::storage_to_key;
+#[cfg(feature = "contract")]
+use crate::storage_cleanup;
+
+/// Most accounts listed by a call.
+#[cfg(feature = "contract")]
+const MAX_LIMIT: u32 = 100;
+/// Most storage slots listed by a call.
+#[cfg(feature = "contract")]
+const MAX_STORAGE_LIMIT: u32 = 1000;
+
+/// Position from which the next page starts.
+struct Cursor {
+    start: u64,
+    storage_start: u64,
+}
+
+/// Returns the accounts from `args.start` on, at most `args.limit` of them along with at most
+/// `args.storage_limit` slots, and the cursor of the next page, if any.
+#[cfg(feature = "contract")]
+pub fn dump_state(args: &DumpStateArgs) -> String {
+    let limit = u64::from(args.limit.min(MAX_LIMIT));
+    let mut storage_left = u64::from(args.storage_limit.min(MAX_STORAGE_LIMIT));
+    let accounts_len = account_index::len();
+    let mut cursor = Cursor {
+        start: args.start,
+        storage_start: args.storage_start,
+    };
+    let mut page = Vec::new();
+    let mut next = None;
+    // Positions of removed accounts are skipped, but still count towards the limit.
+    let mut visited = 0;
+    while cursor.start < accounts_len {
+        if visited == limit || storage_left == 0 {
+            next = Some(cursor);
+            break;
+        }
+        visited += 1;
+        let address = account_index::get(cursor.start).expect("ERR_CORRUPTED_STORAGE");
+        let mut account = Engine::get_account(&address);
+        account.code_hash = Engine::account_code_hash(&address, &account).0;
+        // The records of removed accounts only keep their storage generation.
+        if account.is_empty() {
+            cursor = Cursor {
+                start: cursor.start + 1,
+                storage_start: 0,
+            };
+            continue;
+        }
+        let slot_count = storage_cleanup::get_slot_count(&address, account.generation);
+        let storage_end = slot_count.min(cursor.storage_start.saturating_add(storage_left));
+        let storage = account_storage(
+            &address,
+            account.generation,
+            cursor.storage_start,
+            storage_end,
+        );
+        storage_left -= storage_end.saturating_sub(cursor.storage_start);
+        page.push(account_json(
+            &address,
+            &account,
+            &Engine::get_code(&address),
+            &storage,
+        ));
+        if storage_end < slot_count {
+            next = Some(Cursor {
+                start: cursor.start,
+                storage_start: storage_end,
+            });
+            break;
+        }
+        cursor = Cursor {
+            start: cursor.start + 1,
+            storage_start: 0,
+        };
+    }
+    dump_json(
+        state_commitment::get_state_root().ok(),
+        &page,
+        next.as_ref(),
+        account_index::is_complete(),
+    )
+}
+
+/// Returns the slots recorded from position `start` to `end` for a generation of an account,
+/// except for the ones which were removed since.
+#[cfg(feature = "contract")]
+fn account_storage(
+    address: &Address,
+    generation: u32,
+    start: u64,
+    end: u64,
+) -> Vec<(H256, Vec<u8>)> {
+    (start..end)
+        .filter_map(|index| {
+            let key = storage_cleanup::get_slot(address, generation, index)?;
+            let value = sdk::read_storage(storage_to_key(address, &key, generation).as_ref())?;
+            Some((key, value))
+        })
+        .collect()
+}
+
+/// Returns a page of the dump, where `complete` tells whether all the accounts are indexed.
+fn dump_json(
+    root: Option<H256>,
+    accounts: &[String],
+    next: Option<&Cursor>,
+    complete: bool,
+) -> String {
+    let root = root
+        .map(|root| format!(r#""root":"{}","#, to_hex(root.as_bytes())))
+        .unwrap_or_default();
+    let next = next
+        .map(|next| {
+            format!(
+                r#"{{"start":{},"storageStart":{}}}"#,
+                next.start, next.storage_start
+            )
+        })
+        .unwrap_or_else(|| "null".to_string());
+    format!(
+        r#"{{{}"accounts":{{{}}},"next":{},"complete":{}}}"#,
+        root,
+        accounts.join(","),
+        next,
+        complete
+    )
+}
+
+/// Returns an entry of the `accounts` object, where empty code and storage are left out.
+fn account_json(
+    address: &Address,
+    account: &Account,
+    code: &[u8],
+    storage: &[(H256, Vec<u8>)],
+) -> String {
+    let mut fields = Vec::with_capacity(5);
+    fields.push(format!(r#""balance":"{}""#, account.balance().raw()));
+    fields.push(format!(r#""nonce":{}"#, account.nonce()));
+    fields.push(format!(r#""codeHash":"{}""#, to_hex(&account.code_hash)));
+    if !code.is_empty() {
+        fields.push(format!(r#""code":"{}""#, to_hex(code)));
+    }
+    if !storage.is_empty() {
+        let slots: Vec<String> = storage
+            .iter()
+            .map(|(key, value)| format!(r#""{}":"{}""#, to_hex(key.as_bytes()), to_hex(value)))
+            .collect();
+        fields.push(format!(r#""storage":{{{}}}"#, slots.join(",")));
+    }
+    format!(
+        r#""{}":{{{}}}"#,
+        to_hex(address.as_bytes()),
+        fields.join(",")
+    )
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::{vec, U256};
+    use crate::types::{keccak, u256_to_arr};
+
+    #[test]
+    fn test_dump_json() {
+        let account = Account {
+            nonce: u256_to_arr(&U256::one()),
+            balance: u256_to_arr(&U256::from(1000)),
+            generation: 0,
+            code_hash: keccak(&[0x60, 0x00]).0,
+        };
+        let other_account = Account {
+            balance: u256_to_arr(&U256::one()),
+            ..Account::default()
+        };
+        let accounts = [
+            account_json(
+                &Address([1; 20]),
+                &account,
+                &[0x60, 0x00],
+                &[(H256([2; 32]), vec![3; 32])],
+            ),
+            account_json(&Address([4; 20]), &other_account, &[], &[]),
+        ];
+
+        let expected = format!(
+            concat!(
+                r#"{{"accounts":{{"#,
+                r#""0x0101010101010101010101010101010101010101":{{"balance":"1000","nonce":1,"#,
+                r#""codeHash":"{}","code":"0x6000","storage":{{"#,
+                r#""0x0202020202020202020202020202020202020202020202020202020202020202":"#,
+                r#""0x0303030303030303030303030303030303030303030303030303030303030303"}}}},"#,
+                r#""0x0404040404040404040404040404040404040404":{{"balance":"1","nonce":0,"#,
+                r#""codeHash":"0xc5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"}}}},"#,
+                r#""next":{{"start":5,"storageStart":1}},"complete":false}}"#,
+            ),
+            to_hex(keccak(&[0x60, 0x00]).as_bytes())
+        );
+        assert_eq!(
+            dump_json(
+                None,
+                &accounts,
+                Some(&Cursor {
+                    start: 5,
+                    storage_start: 1
+                }),
+                false
+            ),
+            expected
+        );
+    }
+}
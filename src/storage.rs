@@ -1,4 +1,4 @@
-use crate::prelude::{Address, Vec, H256};
+use crate::prelude::{Address, TryFrom, Vec, H256};
 use borsh::{BorshDeserialize, BorshSerialize};
 
 // NOTE: We start at 0x7 as our initial value as our original storage was not
@@ -20,6 +20,14 @@ pub enum KeyPrefix {
     Generation = 0x7,
     Nep141Erc20Map = 0x8,
     Erc20Nep141Map = 0x9,
+    BlockHash = 0xa,
+    BlockReceipts = 0xb,
+    StateCommitment = 0xc,
+    StorageKeys = 0xd,
+    Account = 0xe,
+    CodeByHash = 0xf,
+    AccountIndex = 0x10,
+    StorageDeposit = 0x11,
 }
 
 /// Enum used to differentiate different storage keys used by eth-connector
@@ -36,10 +44,22 @@ pub enum EthConnectorStorageId {
 /// We can't use const generic over Enum, but we can do it over integral type
 pub type KeyPrefixU8 = u8;
 
-// TODO: Derive From<u8> using macro to avoid missing new arguments in the future
-impl From<KeyPrefixU8> for KeyPrefix {
-    fn from(value: KeyPrefixU8) -> Self {
-        match value {
+/// Error converting a byte which is not the value of any `KeyPrefix`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct UnknownKeyPrefix(pub KeyPrefixU8);
+
+impl AsRef<[u8]> for UnknownKeyPrefix {
+    fn as_ref(&self) -> &[u8] {
+        b"ERR_UNKNOWN_KEY_PREFIX"
+    }
+}
+
+// TODO: Derive TryFrom<u8> using macro to avoid missing new arguments in the future
+impl TryFrom<KeyPrefixU8> for KeyPrefix {
+    type Error = UnknownKeyPrefix;
+
+    fn try_from(value: KeyPrefixU8) -> Result<Self, Self::Error> {
+        let prefix = match value {
             0x0 => Self::Config,
             0x1 => Self::Nonce,
             0x2 => Self::Balance,
@@ -50,8 +70,17 @@ impl From<KeyPrefixU8> for KeyPrefix {
             0x7 => Self::Generation,
             0x8 => Self::Nep141Erc20Map,
             0x9 => Self::Erc20Nep141Map,
-            _ => unreachable!(),
-        }
+            0xa => Self::BlockHash,
+            0xb => Self::BlockReceipts,
+            0xc => Self::StateCommitment,
+            0xd => Self::StorageKeys,
+            0xe => Self::Account,
+            0xf => Self::CodeByHash,
+            0x10 => Self::AccountIndex,
+            0x11 => Self::StorageDeposit,
+            _ => return Err(UnknownKeyPrefix(value)),
+        };
+        Ok(prefix)
     }
 }
 
@@ -114,4 +143,19 @@ fn generation_storage_key(address: &Address, key: &H256, generation: u32) -> [u8
 }
 
 #[cfg(test)]
-mod tests {}
+mod tests {
+    use super::{KeyPrefix, UnknownKeyPrefix};
+    use crate::prelude::TryFrom;
+
+    #[test]
+    fn test_key_prefix_try_from() {
+        for prefix in 0x0..=0x11u8 {
+            let key_prefix = KeyPrefix::try_from(prefix).unwrap();
+            assert_eq!(key_prefix as u8, prefix);
+        }
+        assert_eq!(
+            KeyPrefix::try_from(0x12u8).err(),
+            Some(UnknownKeyPrefix(0x12))
+        );
+    }
+}
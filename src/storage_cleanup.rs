@@ -0,0 +1,183 @@
+//! Removal of the storage left behind by old generations of accounts.
+//!
+//! Removing all the storage of an account only bumps its generation (see
+//! `Engine::remove_all_storage`), so the slots of the previous generation stay in the NEAR
+//! state. The keys of the slots written in each generation are recorded, and when a generation
+//! is retired it is queued for its slots to be removed in batches by `cleanup_storage`.
+//! The recorded keys also list the storage of the accounts, for `dump_state`. The slots written
+//! before the keys were recorded are neither removed nor listed, unless they are recorded with
+//! `index_accounts`.
+use borsh::{BorshDeserialize, BorshSerialize};
+
+#[cfg(feature = "contract")]
+use crate::parameters::CleanupStorageResult;
+use crate::prelude::{Address, Vec, H256};
+use crate::sdk;
+#[cfg(feature = "contract")]
+use crate::storage::storage_to_key;
+use crate::storage::{bytes_to_key, KeyPrefix};
+
+const SLOT_ID: u8 = 0;
+const SLOT_COUNT_ID: u8 = 1;
+const QUEUE_ID: u8 = 2;
+const QUEUE_STATE_ID: u8 = 3;
+const SLOT_MARKER_ID: u8 = 4;
+
+/// Queue of the retired generations whose slots are still to be removed.
+#[derive(BorshSerialize, BorshDeserialize, Default)]
+struct QueueState {
+    /// Index of the first generation in the queue.
+    head: u64,
+    /// Index after the last generation in the queue.
+    tail: u64,
+    /// Number of slots left to remove in all the queued generations.
+    pending_slots: u64,
+}
+
+/// Records the key of a slot which did not exist in the given generation of an account, unless
+/// it was recorded already, i.e. if it existed earlier in the generation.
+pub fn record_slot(address: &Address, key: &H256, generation: u32) {
+    let marker_key = slot_marker_key(address, key, generation);
+    if sdk::storage_has_key(&marker_key) {
+        return;
+    }
+    sdk::write_storage(&marker_key, &[]);
+    let count = get_slot_count(address, generation);
+    sdk::write_storage(&slot_key(address, generation, count), key.as_bytes());
+    set_slot_count(address, generation, count + 1);
+}
+
+/// Queues the slots of a generation of an account which is no longer in use for removal.
+pub fn retire_generation(address: &Address, generation: u32) {
+    let count = get_slot_count(address, generation);
+    if count == 0 {
+        return;
+    }
+    let mut queue = get_queue_state();
+    let entry = [address.as_bytes(), &generation.to_le_bytes()].concat();
+    sdk::write_storage(&queue_key(queue.tail), &entry);
+    queue.tail += 1;
+    queue.pending_slots += count;
+    set_queue_state(&queue);
+}
+
+/// Removes at most `max_slots` slots of retired generations, in the order the generations
+/// were retired.
+#[cfg(feature = "contract")]
+pub fn cleanup(max_slots: u64) -> CleanupStorageResult {
+    let mut queue = get_queue_state();
+    let mut removed_slots = 0;
+    while removed_slots < max_slots && queue.head < queue.tail {
+        let entry = sdk::read_storage(&queue_key(queue.head)).expect("ERR_CORRUPTED_STORAGE");
+        let address = Address::from_slice(&entry[..20]);
+        let mut generation = [0u8; 4];
+        generation.copy_from_slice(&entry[20..24]);
+        let generation = u32::from_le_bytes(generation);
+
+        let mut count = get_slot_count(&address, generation);
+        while count > 0 && removed_slots < max_slots {
+            count -= 1;
+            let key = slot_key(&address, generation, count);
+            if let Some(slot) = sdk::read_storage(&key) {
+                let slot = H256::from_slice(&slot);
+                sdk::remove_storage(storage_to_key(&address, &slot, generation).as_ref());
+                sdk::remove_storage(&slot_marker_key(&address, &slot, generation));
+                sdk::remove_storage(&key);
+            }
+            removed_slots += 1;
+        }
+        set_slot_count(&address, generation, count);
+        if count == 0 {
+            sdk::remove_storage(&queue_key(queue.head));
+            queue.head += 1;
+        }
+    }
+    queue.pending_slots = queue.pending_slots.saturating_sub(removed_slots);
+    set_queue_state(&queue);
+
+    CleanupStorageResult {
+        removed_slots,
+        pending_slots: queue.pending_slots,
+        pending_generations: queue.tail - queue.head,
+        reward: 0,
+    }
+}
+
+/// Returns the key of the slot recorded at the given position for a generation of an account.
+#[cfg(feature = "contract")]
+pub fn get_slot(address: &Address, generation: u32, index: u64) -> Option<H256> {
+    sdk::read_storage(&slot_key(address, generation, index)).map(|value| H256::from_slice(&value))
+}
+
+/// Returns the number of slots recorded for a generation of an account, including the
+/// ones which were removed since.
+pub fn get_slot_count(address: &Address, generation: u32) -> u64 {
+    sdk::read_u64(&slot_count_key(address, generation)).unwrap_or(0)
+}
+
+fn set_slot_count(address: &Address, generation: u32, count: u64) {
+    let key = slot_count_key(address, generation);
+    if count == 0 {
+        sdk::remove_storage(&key);
+    } else {
+        sdk::write_storage(&key, &count.to_le_bytes());
+    }
+}
+
+fn get_queue_state() -> QueueState {
+    sdk::read_storage(&bytes_to_key(KeyPrefix::StorageKeys, &[QUEUE_STATE_ID]))
+        .map(|value| QueueState::try_from_slice(&value).expect("ERR_DESER"))
+        .unwrap_or_default()
+}
+
+fn set_queue_state(queue: &QueueState) {
+    sdk::write_storage(
+        &bytes_to_key(KeyPrefix::StorageKeys, &[QUEUE_STATE_ID]),
+        &queue.try_to_vec().expect("ERR_SER"),
+    );
+}
+
+fn slot_key(address: &Address, generation: u32, index: u64) -> Vec<u8> {
+    bytes_to_key(
+        KeyPrefix::StorageKeys,
+        &[
+            &[SLOT_ID][..],
+            address.as_bytes(),
+            &generation.to_le_bytes(),
+            &index.to_le_bytes(),
+        ]
+        .concat(),
+    )
+}
+
+fn slot_count_key(address: &Address, generation: u32) -> Vec<u8> {
+    bytes_to_key(
+        KeyPrefix::StorageKeys,
+        &[
+            &[SLOT_COUNT_ID][..],
+            address.as_bytes(),
+            &generation.to_le_bytes(),
+        ]
+        .concat(),
+    )
+}
+
+fn slot_marker_key(address: &Address, key: &H256, generation: u32) -> Vec<u8> {
+    bytes_to_key(
+        KeyPrefix::StorageKeys,
+        &[
+            &[SLOT_MARKER_ID][..],
+            address.as_bytes(),
+            &generation.to_le_bytes(),
+            key.as_bytes(),
+        ]
+        .concat(),
+    )
+}
+
+fn queue_key(index: u64) -> Vec<u8> {
+    bytes_to_key(
+        KeyPrefix::StorageKeys,
+        &[&[QUEUE_ID][..], &index.to_le_bytes()].concat(),
+    )
+}
@@ -12,13 +12,16 @@ use primitive_types::U256;
 use rlp::RlpStream;
 use secp256k1::{self, Message, PublicKey, SecretKey};
 
+use crate::account_index;
+use crate::engine::Account;
 use crate::fungible_token::{FungibleToken, FungibleTokenMetadata};
 use crate::parameters::{InitCallArgs, NewCallArgs, SubmitResult, TransactionStatus};
-use crate::prelude::Address;
+use crate::prelude::{Address, TryInto};
 use crate::storage;
 use crate::test_utils::solidity::{ContractConstructor, DeployedContract};
 use crate::transaction::{
     access_list::{self, AccessListEthSignedTransaction, AccessListEthTransaction},
+    dynamic_fee::{self, DynamicFeeEthSignedTransaction, DynamicFeeEthTransaction},
     LegacyEthSignedTransaction, LegacyEthTransaction,
 };
 use crate::types;
@@ -32,6 +35,9 @@ pub fn origin() -> AccountId {
 
 pub(crate) const SUBMIT: &str = "submit";
 
+/// Balance of the account created for the signer returned by `deploy_evm_with_signer`.
+pub(crate) const INITIAL_BALANCE: types::Wei = types::Wei::new_u64(1_000_000);
+
 pub(crate) mod erc20;
 pub(crate) mod exit_precompile;
 pub(crate) mod self_destruct;
@@ -186,30 +192,65 @@ impl AuroraRunner {
     }
 
     pub fn create_address(&mut self, address: Address, init_balance: types::Wei, init_nonce: U256) {
+        self.add_eth_supply(init_balance);
         let trie = &mut self.ext.fake_trie;
 
-        let balance_key = storage::address_to_key(storage::KeyPrefix::Balance, &address);
-        let balance_value = init_balance.to_bytes();
+        let account_key = storage::address_to_key(storage::KeyPrefix::Account, &address);
+        let account_value = Account {
+            nonce: types::u256_to_arr(&init_nonce),
+            balance: init_balance.to_bytes(),
+            ..Default::default()
+        };
+        trie.insert(account_key.to_vec(), account_value.try_to_vec().unwrap());
+
+        let position_key = account_index::position_key(&address);
+        if !trie.contains_key(&position_key) {
+            let length_key = account_index::length_key();
+            let position = trie
+                .get(&length_key)
+                .map(|value| u64::from_le_bytes(value[..].try_into().unwrap()))
+                .unwrap_or(0);
+            trie.insert(position_key, position.to_le_bytes().to_vec());
+            trie.insert(account_index::entry_key(position), address.0.to_vec());
+            trie.insert(length_key, (position + 1).to_le_bytes().to_vec());
+        }
+    }
 
+    /// Same as `create_address`, but the account is stored under the keys used before
+    /// `Account` records and is not part of the account index, as in an engine whose accounts
+    /// are not migrated yet.
+    pub fn create_legacy_address(
+        &mut self,
+        address: Address,
+        init_balance: types::Wei,
+        init_nonce: U256,
+    ) {
+        self.add_eth_supply(init_balance);
+        let trie = &mut self.ext.fake_trie;
+
+        let balance_key = storage::address_to_key(storage::KeyPrefix::Balance, &address);
         let nonce_key = storage::address_to_key(storage::KeyPrefix::Nonce, &address);
-        let nonce_value = types::u256_to_arr(&init_nonce);
+        trie.insert(balance_key.to_vec(), init_balance.to_bytes().to_vec());
+        trie.insert(nonce_key.to_vec(), types::u256_to_arr(&init_nonce).to_vec());
 
+        let migration_key =
+            storage::bytes_to_key(storage::KeyPrefix::Config, crate::migration::MIGRATIONS_KEY);
+        trie.remove(&migration_key);
+        trie.remove(&account_index::complete_key());
+    }
+
+    fn add_eth_supply(&mut self, amount: types::Wei) {
         let ft_key = storage::bytes_to_key(
             storage::KeyPrefix::EthConnector,
             &[storage::EthConnectorStorageId::FungibleToken as u8],
         );
-        let ft_value = {
-            let mut current_ft: FungibleToken = trie
-                .get(&ft_key)
-                .map(|bytes| FungibleToken::try_from_slice(&bytes).unwrap())
-                .unwrap_or_default();
-            current_ft.total_eth_supply_on_aurora += init_balance.raw().as_u128();
-            current_ft
-        };
-
-        trie.insert(balance_key.to_vec(), balance_value.to_vec());
-        trie.insert(nonce_key.to_vec(), nonce_value.to_vec());
-        trie.insert(ft_key, ft_value.try_to_vec().unwrap());
+        let trie = &mut self.ext.fake_trie;
+        let mut current_ft: FungibleToken = trie
+            .get(&ft_key)
+            .map(|bytes| FungibleToken::try_from_slice(&bytes).unwrap())
+            .unwrap_or_default();
+        current_ft.total_eth_supply_on_aurora += amount.raw().as_u128();
+        trie.insert(ft_key, current_ft.try_to_vec().unwrap());
     }
 
     pub fn submit_with_signer<F: FnOnce(U256) -> LegacyEthTransaction>(
@@ -227,11 +268,25 @@ impl AuroraRunner {
         account: &SecretKey,
         transaction: LegacyEthTransaction,
     ) -> Result<SubmitResult, VMError> {
-        let calling_account_id = "some-account.near".to_string();
         let signed_tx = sign_transaction(transaction, Some(self.chain_id), account);
+        self.submit_raw(rlp::encode(&signed_tx).to_vec())
+    }
 
-        let (output, maybe_err) =
-            self.call(SUBMIT, calling_account_id, rlp::encode(&signed_tx).to_vec());
+    pub fn submit_access_list_transaction(
+        &mut self,
+        account: &SecretKey,
+        transaction: AccessListEthTransaction,
+    ) -> Result<SubmitResult, VMError> {
+        let signed_tx = sign_access_list_transaction(transaction, account);
+        let input = std::iter::once(access_list::TYPE_BYTE)
+            .chain(rlp::encode(&signed_tx).into_iter())
+            .collect();
+        self.submit_raw(input)
+    }
+
+    fn submit_raw(&mut self, input: Vec<u8>) -> Result<SubmitResult, VMError> {
+        let calling_account_id = "some-account.near".to_string();
+        let (output, maybe_err) = self.call(SUBMIT, calling_account_id, input);
 
         if let Some(err) = maybe_err {
             Err(err)
@@ -265,6 +320,43 @@ impl AuroraRunner {
         }
     }
 
+    /// Deploys a contract with the given hex encoded init code and returns its address.
+    pub fn deploy(&mut self, signer: &mut Signer, init_code: &str) -> Address {
+        let result = self
+            .submit_with_signer(signer, |nonce| create_transaction(init_code, nonce))
+            .unwrap();
+        Address::from_slice(&unwrap_success(result))
+    }
+
+    /// Calls a contract with the given input, without attaching any value.
+    pub fn call_contract(
+        &mut self,
+        signer: &mut Signer,
+        contract: Address,
+        input: Vec<u8>,
+    ) -> SubmitResult {
+        self.submit_with_signer(signer, |nonce| call_transaction(contract, input, nonce))
+            .unwrap()
+    }
+
+    /// Calls a method of the engine as its owner, which must succeed.
+    pub fn call_as_owner(&mut self, method_name: &str, input: Vec<u8>) {
+        let (_, maybe_error) = self.call(method_name, self.aurora_account_id.clone(), input);
+        assert!(maybe_error.is_none());
+    }
+
+    /// Calls a method of the engine which must succeed, and returns its output.
+    pub fn view(&mut self, method_name: &str, input: Vec<u8>) -> Vec<u8> {
+        let (outcome, maybe_error) = self.call(method_name, "viewer".to_string(), input);
+        assert!(maybe_error.is_none());
+        outcome.unwrap().return_data.as_value().unwrap()
+    }
+
+    /// Same as `view`, for a method with a Borsh-encoded output.
+    pub fn view_borsh<T: BorshDeserialize>(&mut self, method_name: &str, input: Vec<u8>) -> T {
+        T::try_from_slice(&self.view(method_name, input)).unwrap()
+    }
+
     pub fn get_balance(&self, address: Address) -> types::Wei {
         types::Wei::new(self.getter_method_call("get_balance", address))
     }
@@ -346,7 +438,18 @@ impl Default for AuroraRunner {
 }
 
 pub(crate) fn deploy_evm() -> AuroraRunner {
-    let mut runner = AuroraRunner::default();
+    initialize_evm(AuroraRunner::default())
+}
+
+/// Same as `deploy_evm`, with the given engine code, e.g. that of an earlier version.
+pub(crate) fn deploy_evm_with_code(evm_wasm_bytes: Vec<u8>) -> AuroraRunner {
+    initialize_evm(AuroraRunner {
+        code: ContractCode::new(evm_wasm_bytes, None),
+        ..Default::default()
+    })
+}
+
+fn initialize_evm(mut runner: AuroraRunner) -> AuroraRunner {
     let args = NewCallArgs {
         chain_id: types::u256_to_arr(&U256::from(runner.chain_id)),
         owner_id: runner.aurora_account_id.clone(),
@@ -378,6 +481,43 @@ pub(crate) fn deploy_evm() -> AuroraRunner {
     runner
 }
 
+/// Same as `deploy_evm`, with an account holding `INITIAL_BALANCE` for the returned signer.
+pub(crate) fn deploy_evm_with_signer() -> (AuroraRunner, Signer) {
+    let mut runner = deploy_evm();
+    let signer = Signer::random();
+    let address = address_from_secret_key(&signer.secret_key);
+    runner.create_address(address, INITIAL_BALANCE, U256::zero());
+    (runner, signer)
+}
+
+/// Transaction deploying a contract with the given hex encoded init code.
+pub(crate) fn create_transaction(init_code: &str, nonce: U256) -> LegacyEthTransaction {
+    LegacyEthTransaction {
+        nonce,
+        gas_price: U256::zero(),
+        gas: u64::MAX.into(),
+        to: None,
+        value: types::Wei::zero(),
+        data: hex::decode(init_code).unwrap(),
+    }
+}
+
+/// Transaction calling a contract with the given input, without value.
+pub(crate) fn call_transaction(
+    contract: Address,
+    input: Vec<u8>,
+    nonce: U256,
+) -> LegacyEthTransaction {
+    LegacyEthTransaction {
+        nonce,
+        gas_price: U256::zero(),
+        gas: u64::MAX.into(),
+        to: Some(contract),
+        value: types::Wei::zero(),
+        data: input,
+    }
+}
+
 pub(crate) fn transfer(to: Address, amount: types::Wei, nonce: U256) -> LegacyEthTransaction {
     LegacyEthTransaction {
         nonce,
@@ -455,6 +595,28 @@ pub(crate) fn sign_access_list_transaction(
     }
 }
 
+pub(crate) fn sign_dynamic_fee_transaction(
+    tx: DynamicFeeEthTransaction,
+    secret_key: &SecretKey,
+) -> DynamicFeeEthSignedTransaction {
+    let mut rlp_stream = RlpStream::new();
+    rlp_stream.append(&dynamic_fee::TYPE_BYTE);
+    tx.rlp_append_unsigned(&mut rlp_stream);
+    let message_hash = types::keccak(rlp_stream.as_raw());
+    let message = Message::parse_slice(message_hash.as_bytes()).unwrap();
+
+    let (signature, recovery_id) = secp256k1::sign(&message, secret_key);
+    let r = U256::from_big_endian(&signature.r.b32());
+    let s = U256::from_big_endian(&signature.s.b32());
+
+    DynamicFeeEthSignedTransaction {
+        transaction_data: tx,
+        parity: recovery_id.serialize(),
+        r,
+        s,
+    }
+}
+
 pub(crate) fn address_from_secret_key(sk: &SecretKey) -> Address {
     let pk = PublicKey::from_secret_key(sk);
     let hash = types::keccak(&pk.serialize()[1..]);
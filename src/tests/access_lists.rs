@@ -1,11 +1,26 @@
-use crate::prelude::{H256, U256};
+use crate::parameters::{
+    CreateAccessListResult, ResultAccessTuple, TransactionStatus, ViewCallArgs,
+};
+use crate::prelude::{Address, H256, U256};
 use crate::test_utils;
 use crate::transaction::access_list::{self, AccessListEthTransaction, AccessTuple};
 use crate::transaction::EthTransaction;
 use crate::types::Wei;
+use borsh::BorshSerialize;
 use std::convert::TryFrom;
 use std::iter;
 
+const GAS_LIMIT: u64 = 100_000;
+
+/// Init code which deploys a contract with runtime code `PUSH1 0x00 SLOAD POP`,
+/// i.e. a contract that reads storage slot 0 and then stops.
+const SLOAD_CONTRACT_INIT_CODE: &str = "63600054506000526004601cf3";
+
+/// Init code which deploys a contract with runtime code
+/// `PUSH20 0x1111111111111111111111111111111111111111 BALANCE POP`.
+const BALANCE_CONTRACT_INIT_CODE: &str =
+    "6017600c60003960176000f37311111111111111111111111111111111111111113150";
+
 // Test taken from https://github.com/ethereum/tests/blob/develop/GeneralStateTests/stExample/accessListExample.json
 // TODO(#170): generally support Ethereum tests
 #[test]
@@ -46,7 +61,7 @@ fn test_access_list_tx_encoding_decoding() {
 
     let decoded_tx = match EthTransaction::try_from(expected_bytes.as_slice()) {
         Ok(EthTransaction::AccessList(tx)) => tx,
-        Ok(EthTransaction::Legacy(_)) => panic!("Unexpected transaction type"),
+        Ok(_) => panic!("Unexpected transaction type"),
         Err(_) => panic!("Transaction parsing failed"),
     };
 
@@ -58,6 +73,157 @@ fn test_access_list_tx_encoding_decoding() {
     )
 }
 
+// Expected gas values below are computed according to the Berlin rules (EIP-2929 and
+// EIP-2930) and match what geth reports for the same transactions.
+#[test]
+fn test_access_list_transfer_gas() {
+    let (mut runner, mut signer, _) = initialize();
+    let receiver = Address([0x11; 20]);
+
+    let tx = access_list_tx(
+        &runner,
+        signer.use_nonce().into(),
+        receiver,
+        vec![AccessTuple {
+            address: receiver,
+            storage_keys: vec![H256::zero()],
+        }],
+    );
+    let result = runner
+        .submit_access_list_transaction(&signer.secret_key, tx)
+        .unwrap();
+
+    test_utils::panic_on_fail(result.status);
+    // 21000 (base) + 2400 (address) + 1900 (storage key)
+    assert_eq!(result.gas_used, 25_300);
+}
+
+#[test]
+fn test_access_list_cold_and_warm_sload_gas() {
+    let (mut runner, mut signer, contract) = initialize();
+
+    // Without an access list the storage slot is cold when it is read.
+    let tx = access_list_tx(&runner, signer.use_nonce().into(), contract, Vec::new());
+    let result = runner
+        .submit_access_list_transaction(&signer.secret_key, tx)
+        .unwrap();
+    test_utils::panic_on_fail(result.status);
+    // 21000 (base) + 3 (PUSH1) + 2100 (cold SLOAD) + 2 (POP)
+    assert_eq!(result.gas_used, 23_105);
+
+    // Slot 0 is warm because it is listed.
+    let tx = access_list_tx(
+        &runner,
+        signer.use_nonce().into(),
+        contract,
+        vec![AccessTuple {
+            address: contract,
+            storage_keys: vec![H256::zero()],
+        }],
+    );
+    let result = runner
+        .submit_access_list_transaction(&signer.secret_key, tx)
+        .unwrap();
+    test_utils::panic_on_fail(result.status);
+    // 21000 (base) + 2400 (address) + 1900 (storage key) + 3 (PUSH1) + 100 (warm SLOAD) + 2 (POP)
+    assert_eq!(result.gas_used, 25_405);
+
+    // Listing a different slot does not make slot 0 warm.
+    let tx = access_list_tx(
+        &runner,
+        signer.use_nonce().into(),
+        contract,
+        vec![AccessTuple {
+            address: contract,
+            storage_keys: vec![one()],
+        }],
+    );
+    let result = runner
+        .submit_access_list_transaction(&signer.secret_key, tx)
+        .unwrap();
+    test_utils::panic_on_fail(result.status);
+    // 21000 (base) + 2400 (address) + 1900 (storage key) + 3 (PUSH1) + 2100 (cold SLOAD) + 2 (POP)
+    assert_eq!(result.gas_used, 27_405);
+}
+
+#[test]
+fn test_create_access_list_storage() {
+    let (mut runner, signer, contract) = initialize();
+
+    let result = create_access_list(&mut runner, &signer, contract);
+
+    assert_eq!(
+        result.access_list,
+        vec![ResultAccessTuple {
+            address: contract.0,
+            storage_keys: vec![[0; 32]],
+        }]
+    );
+    assert_eq!(result.status, TransactionStatus::Succeed(Vec::new()));
+    // See `test_access_list_cold_and_warm_sload_gas`
+    assert_eq!(result.gas_used_without_access_list, 23_105);
+    assert_eq!(result.gas_used, 25_405);
+}
+
+#[test]
+fn test_create_access_list_address() {
+    let (mut runner, mut signer, _) = initialize();
+    let contract = runner.deploy(&mut signer, BALANCE_CONTRACT_INIT_CODE);
+
+    let result = create_access_list(&mut runner, &signer, contract);
+
+    assert_eq!(
+        result.access_list,
+        vec![ResultAccessTuple {
+            address: [0x11; 20],
+            storage_keys: Vec::new(),
+        }]
+    );
+    // 21000 (base) + 3 (PUSH20) + 2600 (cold BALANCE) + 2 (POP)
+    assert_eq!(result.gas_used_without_access_list, 23_605);
+    // 21000 (base) + 2400 (address) + 3 (PUSH20) + 100 (warm BALANCE) + 2 (POP)
+    assert_eq!(result.gas_used, 23_505);
+}
+
+fn initialize() -> (test_utils::AuroraRunner, test_utils::Signer, Address) {
+    let (mut runner, mut signer) = test_utils::deploy_evm_with_signer();
+    let contract = runner.deploy(&mut signer, SLOAD_CONTRACT_INIT_CODE);
+
+    (runner, signer, contract)
+}
+
+fn create_access_list(
+    runner: &mut test_utils::AuroraRunner,
+    signer: &test_utils::Signer,
+    contract: Address,
+) -> CreateAccessListResult {
+    let args = ViewCallArgs {
+        sender: test_utils::address_from_secret_key(&signer.secret_key).0,
+        address: contract.0,
+        amount: [0; 32],
+        input: Vec::new(),
+    };
+    runner.view_borsh("create_access_list", args.try_to_vec().unwrap())
+}
+
+fn access_list_tx(
+    runner: &test_utils::AuroraRunner,
+    nonce: U256,
+    to: Address,
+    access_list: Vec<AccessTuple>,
+) -> AccessListEthTransaction {
+    AccessListEthTransaction {
+        chain_id: runner.chain_id,
+        nonce,
+        gas_price: U256::zero(),
+        gas_limit: U256::from(GAS_LIMIT),
+        to: Some(to),
+        value: Wei::zero(),
+        data: Vec::new(),
+        access_list,
+    }
+}
+
 fn one() -> H256 {
     let mut x = [0u8; 32];
     x[31] = 1;
@@ -0,0 +1,226 @@
+use crate::parameters::{
+    IndexAccountsArgs, IndexedAccount, MigrationStatus, RunMigrationsArgs, RunMigrationsResult,
+};
+use crate::prelude::{Address, H256};
+use crate::storage::{self, KeyPrefix};
+use crate::test_utils::{self, INITIAL_BALANCE};
+use crate::types::{keccak, Wei};
+use borsh::{BorshDeserialize, BorshSerialize};
+
+const TRANSFER_AMOUNT: Wei = Wei::new_u64(123);
+/// Runtime code `STOP`.
+const LEGACY_CODE: [u8; 1] = [0x00];
+
+#[test]
+fn test_migrate_accounts() {
+    let mut runner = test_utils::deploy_evm();
+    let mut signer = test_utils::Signer::random();
+    let source = test_utils::address_from_secret_key(&signer.secret_key);
+    let dest = Address::from_low_u64_be(1);
+    let other = Address::from_low_u64_be(2);
+    runner.create_legacy_address(source, INITIAL_BALANCE, 0.into());
+    runner.create_legacy_address(dest, INITIAL_BALANCE, 0.into());
+    runner.create_legacy_address(other, INITIAL_BALANCE, 7.into());
+
+    // Accounts which are not migrated are read from their former keys
+    test_utils::validate_address_balance_and_nonce(&runner, other, INITIAL_BALANCE, 7.into());
+    let result = runner
+        .submit_with_signer(&mut signer, |nonce| {
+            test_utils::transfer(dest, TRANSFER_AMOUNT, nonce)
+        })
+        .unwrap();
+    test_utils::panic_on_fail(result.status);
+
+    // Accounts stored before the account index are migrated once added to it
+    let args = IndexAccountsArgs {
+        accounts: vec![IndexedAccount {
+            address: other.0,
+            storage_keys: Vec::new(),
+        }],
+    };
+    runner.call_as_owner("index_accounts", args.try_to_vec().unwrap());
+    for prefix in [KeyPrefix::Nonce, KeyPrefix::Balance].iter() {
+        let key = storage::address_to_key(*prefix, &other);
+        assert!(!runner.ext.fake_trie.contains_key(&key[..]));
+    }
+
+    // The accounts written since the upgrade still have their former keys
+    let result = run_migrations(&mut runner, 2);
+    assert_eq!(result.steps, 2);
+    assert_eq!(result.status.pending, vec!["ACCOUNT_RECORDS".to_string()]);
+    assert!(result.status.cursor.is_some());
+    let result = run_migrations(&mut runner, 2);
+    assert_eq!(result.steps, 1);
+
+    // The migration only completes once all the accounts are known to be indexed
+    assert_eq!(result.status.pending, vec!["ACCOUNT_RECORDS".to_string()]);
+    runner.call_as_owner("finish_account_indexing", Vec::new());
+    let result = run_migrations(&mut runner, 2);
+    assert_eq!(result.steps, 0);
+    assert!(result.status.pending.is_empty());
+    assert_eq!(run_migrations(&mut runner, 2).steps, 0);
+    assert_eq!(get_migration_status(&mut runner), result.status);
+
+    for address in [source, dest, other].iter() {
+        for prefix in [KeyPrefix::Nonce, KeyPrefix::Balance].iter() {
+            let key = storage::address_to_key(*prefix, address);
+            assert!(!runner.ext.fake_trie.contains_key(&key[..]));
+        }
+    }
+    test_utils::validate_address_balance_and_nonce(
+        &runner,
+        source,
+        INITIAL_BALANCE - TRANSFER_AMOUNT,
+        1.into(),
+    );
+    test_utils::validate_address_balance_and_nonce(
+        &runner,
+        dest,
+        INITIAL_BALANCE + TRANSFER_AMOUNT,
+        0.into(),
+    );
+    test_utils::validate_address_balance_and_nonce(&runner, other, INITIAL_BALANCE, 7.into());
+}
+
+#[test]
+fn test_migrate_legacy_code() {
+    let mut runner = test_utils::deploy_evm();
+    let mut signer = test_utils::Signer::random();
+    let source = test_utils::address_from_secret_key(&signer.secret_key);
+    let first = Address::from_low_u64_be(1);
+    let second = Address::from_low_u64_be(2);
+    runner.create_legacy_address(source, INITIAL_BALANCE, 0.into());
+    for contract in [first, second].iter() {
+        runner.create_legacy_address(*contract, Wei::zero(), 1.into());
+        let key = storage::address_to_key(KeyPrefix::Code, contract);
+        runner
+            .ext
+            .fake_trie
+            .insert(key.to_vec(), LEGACY_CODE.to_vec());
+    }
+    let code_hash = keccak(&LEGACY_CODE);
+
+    // Codes which are not migrated are read from under the address of their account
+    for contract in [first, second].iter() {
+        assert_eq!(get_code(&mut runner, contract), LEGACY_CODE);
+        assert_eq!(get_code_hash(&mut runner, contract), code_hash);
+    }
+    let result = runner
+        .submit_with_signer(&mut signer, |nonce| {
+            test_utils::transfer(first, TRANSFER_AMOUNT, nonce)
+        })
+        .unwrap();
+    test_utils::panic_on_fail(result.status);
+    assert_eq!(code_refcount(&runner, &code_hash), None);
+
+    // The code is moved when the account is added to the account index, or by the migration
+    let args = IndexAccountsArgs {
+        accounts: vec![IndexedAccount {
+            address: second.0,
+            storage_keys: Vec::new(),
+        }],
+    };
+    runner.call_as_owner("index_accounts", args.try_to_vec().unwrap());
+    assert_eq!(code_refcount(&runner, &code_hash), Some(1));
+    runner.call_as_owner("finish_account_indexing", Vec::new());
+    assert!(run_migrations(&mut runner, 10).status.pending.is_empty());
+
+    // Both accounts share the code stored by hash
+    assert_eq!(code_refcount(&runner, &code_hash), Some(2));
+    let code_key = storage::bytes_to_key(
+        KeyPrefix::CodeByHash,
+        &[&[0][..], code_hash.as_bytes()].concat(),
+    );
+    assert_eq!(
+        runner.ext.fake_trie.get(&code_key),
+        Some(&LEGACY_CODE.to_vec())
+    );
+    for contract in [first, second].iter() {
+        let key = storage::address_to_key(KeyPrefix::Code, contract);
+        assert!(!runner.ext.fake_trie.contains_key(&key[..]));
+        assert_eq!(get_code(&mut runner, contract), LEGACY_CODE);
+        assert_eq!(get_code_hash(&mut runner, contract), code_hash);
+    }
+    assert_eq!(runner.get_balance(first), TRANSFER_AMOUNT);
+}
+
+#[test]
+fn test_migrate_unindexed_account() {
+    let mut runner = test_utils::deploy_evm();
+    let contract = Address::from_low_u64_be(1);
+    runner.create_legacy_address(contract, INITIAL_BALANCE, 3.into());
+    let key = storage::address_to_key(KeyPrefix::Code, &contract);
+    runner
+        .ext
+        .fake_trie
+        .insert(key.to_vec(), LEGACY_CODE.to_vec());
+
+    // The account is not in the index, so the migration does not complete and the account is
+    // still read from its former keys
+    for _ in 0..2 {
+        let result = run_migrations(&mut runner, 10);
+        assert_eq!(result.status.pending, vec!["ACCOUNT_RECORDS".to_string()]);
+    }
+    test_utils::validate_address_balance_and_nonce(&runner, contract, INITIAL_BALANCE, 3.into());
+    assert_eq!(get_code(&mut runner, &contract), LEGACY_CODE);
+
+    let args = IndexAccountsArgs {
+        accounts: vec![IndexedAccount {
+            address: contract.0,
+            storage_keys: Vec::new(),
+        }],
+    };
+    runner.call_as_owner("index_accounts", args.try_to_vec().unwrap());
+    runner.call_as_owner("finish_account_indexing", Vec::new());
+    assert!(run_migrations(&mut runner, 10).status.pending.is_empty());
+
+    test_utils::validate_address_balance_and_nonce(&runner, contract, INITIAL_BALANCE, 3.into());
+    assert_eq!(get_code(&mut runner, &contract), LEGACY_CODE);
+    assert_eq!(get_code_hash(&mut runner, &contract), keccak(&LEGACY_CODE));
+}
+
+#[test]
+fn test_new_engine_has_no_pending_migrations() {
+    let mut runner = test_utils::deploy_evm();
+    let status = get_migration_status(&mut runner);
+    assert_eq!(status.completed, vec!["ACCOUNT_RECORDS".to_string()]);
+    assert!(status.pending.is_empty());
+    assert_eq!(status.cursor, None);
+    assert_eq!(run_migrations(&mut runner, 2).steps, 0);
+}
+
+fn run_migrations(runner: &mut test_utils::AuroraRunner, limit: u32) -> RunMigrationsResult {
+    let args = RunMigrationsArgs { limit };
+    let (outcome, maybe_error) = runner.call(
+        "run_migrations",
+        runner.aurora_account_id.clone(),
+        args.try_to_vec().unwrap(),
+    );
+    assert!(maybe_error.is_none());
+    RunMigrationsResult::try_from_slice(&outcome.unwrap().return_data.as_value().unwrap()).unwrap()
+}
+
+fn get_migration_status(runner: &mut test_utils::AuroraRunner) -> MigrationStatus {
+    runner.view_borsh("get_migration_status", Vec::new())
+}
+
+fn get_code(runner: &mut test_utils::AuroraRunner, address: &Address) -> Vec<u8> {
+    runner.view("get_code", address.as_bytes().to_vec())
+}
+
+fn get_code_hash(runner: &mut test_utils::AuroraRunner, address: &Address) -> H256 {
+    H256::from_slice(&runner.view("get_code_hash", address.as_bytes().to_vec()))
+}
+
+/// Returns the number of accounts using a code stored by hash, if it is stored.
+fn code_refcount(runner: &test_utils::AuroraRunner, code_hash: &H256) -> Option<u64> {
+    let key = storage::bytes_to_key(
+        KeyPrefix::CodeByHash,
+        &[&[1][..], code_hash.as_bytes()].concat(),
+    );
+    runner.ext.fake_trie.get(&key).map(|value| {
+        let mut refcount = [0u8; 8];
+        refcount.copy_from_slice(value);
+        u64::from_le_bytes(refcount)
+    })
+}
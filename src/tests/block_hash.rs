@@ -0,0 +1,64 @@
+use crate::engine::{BlockHashMode, Engine};
+use crate::prelude::{H256, U256};
+use crate::test_utils;
+use crate::types::{self, Wei};
+use borsh::BorshSerialize;
+
+#[test]
+fn test_derived_block_hash() {
+    let mut runner = test_utils::deploy_evm();
+    let chain_id = types::u256_to_arr(&U256::from(runner.chain_id));
+
+    let block_height = runner.context.block_index;
+    let block_hash = get_block_hash(&mut runner, block_height);
+    assert_eq!(
+        block_hash,
+        Engine::compute_block_hash(chain_id, block_height, b"aurora")
+    );
+
+    // The current block has no hash yet
+    let current_height = runner.context.block_index + 1;
+    assert_eq!(get_block_hash(&mut runner, current_height), H256::zero());
+}
+
+#[test]
+fn test_recorded_block_hash() {
+    let (mut runner, mut signer) = test_utils::deploy_evm_with_signer();
+    let chain_id = types::u256_to_arr(&U256::from(runner.chain_id));
+    runner.call_as_owner(
+        "set_block_hash_mode",
+        BlockHashMode::Recorded.try_to_vec().unwrap(),
+    );
+    let empty_block_height = runner.context.block_index;
+
+    let address = test_utils::address_from_secret_key(&signer.secret_key);
+    runner
+        .submit_with_signer(&mut signer, |nonce| {
+            test_utils::transfer(address, Wei::zero(), nonce)
+        })
+        .unwrap();
+    let block_height = runner.context.block_index;
+    let block_timestamp = runner.context.block_timestamp;
+
+    let block_hash = get_block_hash(&mut runner, block_height);
+    assert_eq!(
+        block_hash,
+        Engine::compute_recorded_block_hash(
+            chain_id,
+            block_height,
+            b"aurora",
+            block_timestamp,
+            &runner.context.random_seed,
+        )
+    );
+
+    // No transaction was executed in this block, so no hash was recorded
+    assert_eq!(
+        get_block_hash(&mut runner, empty_block_height),
+        H256::zero()
+    );
+}
+
+fn get_block_hash(runner: &mut test_utils::AuroraRunner, block_height: u64) -> H256 {
+    H256::from_slice(&runner.view("get_block_hash", block_height.try_to_vec().unwrap()))
+}
@@ -0,0 +1,144 @@
+use crate::engine::{CoinbasePolicy, DEFAULT_COINBASE};
+use crate::prelude::{Address, U256};
+use crate::test_utils;
+use borsh::BorshSerialize;
+
+/// 2022-04-15T05:20:00.123456789Z, in nanoseconds.
+const BLOCK_TIMESTAMP_NS: u64 = 1_650_000_000_123_456_789;
+
+/// Init code which deploys a contract with runtime code
+/// `TIMESTAMP PUSH1 0x00 MSTORE PUSH1 0x20 PUSH1 0x00 RETURN`.
+const TIMESTAMP_CONTRACT_INIT_CODE: &str = "684260005260206000f360005260096017f3";
+
+/// Init code which deploys a contract with runtime code
+/// `DIFFICULTY PUSH1 0x00 MSTORE PUSH1 0x20 PUSH1 0x00 RETURN`.
+const DIFFICULTY_CONTRACT_INIT_CODE: &str = "684460005260206000f360005260096017f3";
+
+/// Init code which deploys a contract with runtime code
+/// `COINBASE PUSH1 0x00 MSTORE PUSH1 0x20 PUSH1 0x00 RETURN`.
+const COINBASE_CONTRACT_INIT_CODE: &str = "684160005260206000f360005260096017f3";
+
+/// Init code which deploys a contract with runtime code
+/// `GASLIMIT PUSH1 0x00 MSTORE PUSH1 0x20 PUSH1 0x00 RETURN`.
+const GAS_LIMIT_CONTRACT_INIT_CODE: &str = "684560005260206000f360005260096017f3";
+
+/// Account used by the runner to submit transactions.
+const RELAYER_ACCOUNT_ID: &str = "some-account.near";
+
+#[test]
+fn test_block_timestamp_in_seconds() {
+    let (mut runner, mut signer) = test_utils::deploy_evm_with_signer();
+    let contract = runner.deploy(&mut signer, TIMESTAMP_CONTRACT_INIT_CODE);
+
+    let timestamp = call(&mut runner, &mut signer, contract);
+
+    assert_eq!(timestamp, U256::from(1_650_000_000));
+}
+
+#[test]
+fn test_block_timestamp_in_microseconds() {
+    let (mut runner, mut signer) = test_utils::deploy_evm_with_signer();
+    runner.call_as_owner(
+        "set_block_timestamp_in_seconds",
+        false.try_to_vec().unwrap(),
+    );
+    let contract = runner.deploy(&mut signer, TIMESTAMP_CONTRACT_INIT_CODE);
+
+    let timestamp = call(&mut runner, &mut signer, contract);
+
+    assert_eq!(timestamp, U256::from(1_650_000_000_123_456u64));
+}
+
+#[test]
+fn test_block_difficulty_is_random_seed() {
+    let (mut runner, mut signer) = test_utils::deploy_evm_with_signer();
+    let contract = runner.deploy(&mut signer, DIFFICULTY_CONTRACT_INIT_CODE);
+    let random_seed: Vec<u8> = (0..32).collect();
+    runner.context.random_seed = random_seed.clone();
+
+    let difficulty = call(&mut runner, &mut signer, contract);
+
+    assert_eq!(difficulty, U256::from_big_endian(&random_seed));
+}
+
+#[test]
+fn test_block_gas_limit() {
+    let (mut runner, mut signer) = test_utils::deploy_evm_with_signer();
+    let contract = runner.deploy(&mut signer, GAS_LIMIT_CONTRACT_INIT_CODE);
+
+    assert_eq!(call(&mut runner, &mut signer, contract), U256::max_value());
+
+    runner.call_as_owner(
+        "set_block_gas_limit",
+        Some(30_000_000u64).try_to_vec().unwrap(),
+    );
+    assert_eq!(
+        call(&mut runner, &mut signer, contract),
+        U256::from(30_000_000)
+    );
+}
+
+#[test]
+fn test_fixed_coinbase() {
+    let (mut runner, mut signer) = test_utils::deploy_evm_with_signer();
+    let contract = runner.deploy(&mut signer, COINBASE_CONTRACT_INIT_CODE);
+
+    assert_eq!(
+        call(&mut runner, &mut signer, contract),
+        address_to_u256(Address(DEFAULT_COINBASE))
+    );
+
+    let coinbase = Address([0x11; 20]);
+    runner.call_as_owner(
+        "set_coinbase_policy",
+        CoinbasePolicy::Fixed(coinbase.0).try_to_vec().unwrap(),
+    );
+    assert_eq!(
+        call(&mut runner, &mut signer, contract),
+        address_to_u256(coinbase)
+    );
+}
+
+#[test]
+fn test_relayer_coinbase() {
+    let (mut runner, mut signer) = test_utils::deploy_evm_with_signer();
+    let contract = runner.deploy(&mut signer, COINBASE_CONTRACT_INIT_CODE);
+    runner.call_as_owner(
+        "set_coinbase_policy",
+        CoinbasePolicy::Relayer.try_to_vec().unwrap(),
+    );
+
+    // The relayer has not registered an address yet
+    assert_eq!(
+        call(&mut runner, &mut signer, contract),
+        address_to_u256(Address(DEFAULT_COINBASE))
+    );
+
+    let relayer_address = Address([0x22; 20]);
+    let (_, maybe_error) = runner.call(
+        "register_relayer",
+        RELAYER_ACCOUNT_ID.to_string(),
+        relayer_address.0.to_vec(),
+    );
+    assert!(maybe_error.is_none());
+    assert_eq!(
+        call(&mut runner, &mut signer, contract),
+        address_to_u256(relayer_address)
+    );
+}
+
+/// Calls the contract in a block with timestamp `BLOCK_TIMESTAMP_NS` and returns its output.
+fn call(
+    runner: &mut test_utils::AuroraRunner,
+    signer: &mut test_utils::Signer,
+    contract: Address,
+) -> U256 {
+    // The runner advances the timestamp by 100 ns on every call
+    runner.context.block_timestamp = BLOCK_TIMESTAMP_NS - 100;
+    let result = runner.call_contract(signer, contract, Vec::new());
+    U256::from_big_endian(&test_utils::unwrap_success(result))
+}
+
+fn address_to_u256(address: Address) -> U256 {
+    U256::from_big_endian(address.as_bytes())
+}
@@ -0,0 +1,165 @@
+use crate::engine::BLOCK_HASH_HISTORY;
+use crate::parameters::BlockReceiptsSummary;
+use crate::prelude::{Address, H256, U256};
+use crate::receipt;
+use crate::storage::{bytes_to_key, KeyPrefix};
+use crate::test_utils;
+use crate::transaction::access_list::{self, AccessListEthTransaction};
+use crate::types::{u256_to_arr, Wei};
+use borsh::BorshSerialize;
+use evm::backend::Log;
+
+/// Init code which deploys a contract with runtime code
+/// `PUSH1 0x2a PUSH1 0x00 MSTORE PUSH1 0x01 PUSH1 0x20 PUSH1 0x00 LOG1 STOP`,
+/// i.e. a contract which emits a log with topic 1 and data 42.
+const LOG_CONTRACT_INIT_CODE: &str = "6c602a600052600160206000a100600052600d6013f3";
+
+#[test]
+fn test_block_receipts_summary() {
+    let (mut runner, mut signer) = test_utils::deploy_evm_with_signer();
+    let contract = runner.deploy(&mut signer, LOG_CONTRACT_INIT_CODE);
+
+    // Two transactions in the same block
+    let first_gas_used = call(&mut runner, &mut signer, contract);
+    let block_height = runner.context.block_index;
+    runner.context.block_index -= 1;
+    let second_gas_used = call(&mut runner, &mut signer, contract);
+    assert_eq!(runner.context.block_index, block_height);
+
+    let log = Log {
+        address: contract,
+        topics: vec![H256(u256_to_arr(&U256::one()))],
+        data: u256_to_arr(&U256::from(42)).to_vec(),
+    };
+    let logs_bloom = receipt::logs_bloom(&[log.clone()]);
+    let receipts = vec![
+        receipt::encode_receipt(None, true, first_gas_used, &logs_bloom, &[log.clone()]),
+        receipt::encode_receipt(
+            None,
+            true,
+            first_gas_used + second_gas_used,
+            &logs_bloom,
+            &[log],
+        ),
+    ];
+    let expected = BlockReceiptsSummary {
+        logs_bloom,
+        receipts_root: receipt::ordered_trie_root(&receipts).0,
+        receipts_count: 2,
+        gas_used: first_gas_used + second_gas_used,
+    };
+    assert_eq!(
+        get_block_receipts_summary(&mut runner, block_height),
+        expected
+    );
+
+    // Blocks without transactions have an empty summary
+    assert_eq!(
+        get_block_receipts_summary(&mut runner, block_height + 1),
+        BlockReceiptsSummary::default()
+    );
+}
+
+#[test]
+fn test_typed_transaction_receipt() {
+    let (mut runner, mut signer) = test_utils::deploy_evm_with_signer();
+    let contract = runner.deploy(&mut signer, LOG_CONTRACT_INIT_CODE);
+
+    let tx = AccessListEthTransaction {
+        chain_id: runner.chain_id,
+        nonce: signer.use_nonce().into(),
+        gas_price: U256::zero(),
+        gas_limit: U256::from(u64::MAX),
+        to: Some(contract),
+        value: Wei::zero(),
+        data: Vec::new(),
+        access_list: Vec::new(),
+    };
+    let result = runner
+        .submit_access_list_transaction(&signer.secret_key, tx)
+        .unwrap();
+    test_utils::panic_on_fail(result.status);
+    let block_height = runner.context.block_index;
+
+    let log = Log {
+        address: contract,
+        topics: vec![H256(u256_to_arr(&U256::one()))],
+        data: u256_to_arr(&U256::from(42)).to_vec(),
+    };
+    let logs_bloom = receipt::logs_bloom(&[log.clone()]);
+    // The receipt is prefixed with the type of the transaction (see EIP-2718)
+    let encoded_receipt = receipt::encode_receipt(
+        Some(access_list::TYPE_BYTE),
+        true,
+        result.gas_used,
+        &logs_bloom,
+        &[log],
+    );
+    assert_eq!(encoded_receipt[0], access_list::TYPE_BYTE);
+    assert_eq!(
+        get_block_receipts_summary(&mut runner, block_height).receipts_root,
+        receipt::ordered_trie_root(&[encoded_receipt]).0
+    );
+}
+
+#[test]
+fn test_block_receipts_history() {
+    let (mut runner, mut signer) = test_utils::deploy_evm_with_signer();
+    let contract = runner.deploy(&mut signer, LOG_CONTRACT_INIT_CODE);
+
+    call(&mut runner, &mut signer, contract);
+    let first_height = runner.context.block_index;
+    assert_eq!(
+        get_block_receipts_summary(&mut runner, first_height).receipts_count,
+        1
+    );
+    assert_eq!(receipts_node_count(&runner), 1);
+
+    // The summary of a block is overwritten `BLOCK_HASH_HISTORY` blocks later
+    runner.context.block_index = first_height + BLOCK_HASH_HISTORY - 1;
+    call(&mut runner, &mut signer, contract);
+    let second_height = runner.context.block_index;
+    assert_eq!(second_height, first_height + BLOCK_HASH_HISTORY);
+    assert_eq!(
+        get_block_receipts_summary(&mut runner, first_height),
+        BlockReceiptsSummary::default()
+    );
+    assert_eq!(
+        get_block_receipts_summary(&mut runner, second_height).receipts_count,
+        1
+    );
+    // Only the receipts trie of the latest block is kept
+    assert_eq!(receipts_node_count(&runner), 1);
+}
+
+/// Returns the number of stored nodes of receipts tries.
+fn receipts_node_count(runner: &test_utils::AuroraRunner) -> usize {
+    let prefix = bytes_to_key(KeyPrefix::BlockReceipts, &[1]);
+    runner
+        .ext
+        .fake_trie
+        .keys()
+        .filter(|key| key.starts_with(&prefix))
+        .count()
+}
+
+/// Calls the contract and returns the gas used.
+fn call(
+    runner: &mut test_utils::AuroraRunner,
+    signer: &mut test_utils::Signer,
+    contract: Address,
+) -> u64 {
+    let result = runner.call_contract(signer, contract, Vec::new());
+    test_utils::panic_on_fail(result.status);
+    result.gas_used
+}
+
+fn get_block_receipts_summary(
+    runner: &mut test_utils::AuroraRunner,
+    block_height: u64,
+) -> BlockReceiptsSummary {
+    runner.view_borsh(
+        "get_block_receipts_summary",
+        block_height.try_to_vec().unwrap(),
+    )
+}
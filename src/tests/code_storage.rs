@@ -0,0 +1,70 @@
+use crate::prelude::{Address, H256};
+use crate::storage::{KeyPrefix, VersionPrefix};
+use crate::test_utils;
+use crate::types::{keccak, Wei};
+
+/// Init code returning the runtime code `CALLER SELFDESTRUCT`.
+const SELFDESTRUCT_INIT_CODE: &str = "6133ff6000526002601ef3";
+const SELFDESTRUCT_CODE: [u8; 2] = [0x33, 0xff];
+
+#[test]
+fn test_shared_code() {
+    let (mut runner, mut signer) = test_utils::deploy_evm_with_signer();
+
+    let first = runner.deploy(&mut signer, SELFDESTRUCT_INIT_CODE);
+    let second = runner.deploy(&mut signer, SELFDESTRUCT_INIT_CODE);
+    assert_ne!(first, second);
+    for contract in [first, second].iter() {
+        assert_eq!(get_code(&mut runner, contract), SELFDESTRUCT_CODE);
+        assert_eq!(
+            get_code_hash(&mut runner, contract),
+            keccak(&SELFDESTRUCT_CODE)
+        );
+    }
+    assert_eq!(stored_codes(&runner), 1);
+
+    // The code is kept as long as an account uses it
+    selfdestruct(&mut runner, &mut signer, first);
+    assert!(get_code(&mut runner, &first).is_empty());
+    assert_eq!(get_code(&mut runner, &second), SELFDESTRUCT_CODE);
+    assert_eq!(stored_codes(&runner), 1);
+
+    selfdestruct(&mut runner, &mut signer, second);
+    assert!(get_code(&mut runner, &second).is_empty());
+    assert_eq!(get_code_hash(&mut runner, &second), keccak(&[]));
+    assert_eq!(stored_codes(&runner), 0);
+}
+
+fn selfdestruct(
+    runner: &mut test_utils::AuroraRunner,
+    signer: &mut test_utils::Signer,
+    contract: Address,
+) {
+    let result = runner
+        .submit_with_signer(signer, |nonce| {
+            test_utils::transfer(contract, Wei::zero(), nonce)
+        })
+        .unwrap();
+    test_utils::panic_on_fail(result.status);
+}
+
+fn get_code(runner: &mut test_utils::AuroraRunner, address: &Address) -> Vec<u8> {
+    runner.view("get_code", address.as_bytes().to_vec())
+}
+
+fn get_code_hash(runner: &mut test_utils::AuroraRunner, address: &Address) -> H256 {
+    H256::from_slice(&runner.view("get_code_hash", address.as_bytes().to_vec()))
+}
+
+/// Returns the number of codes in the storage.
+fn stored_codes(runner: &test_utils::AuroraRunner) -> usize {
+    let prefix = [VersionPrefix::V1 as u8, KeyPrefix::CodeByHash as u8];
+    let keys = runner
+        .ext
+        .fake_trie
+        .keys()
+        .filter(|key| key.starts_with(&prefix))
+        .count();
+    // Each code has a reference count
+    keys / 2
+}
@@ -0,0 +1,125 @@
+use crate::parameters::{SubmitResult, TransactionStatus};
+use crate::prelude::{Address, H256, U256};
+use crate::test_utils;
+use crate::transaction::access_list::AccessTuple;
+use crate::transaction::dynamic_fee::{self, DynamicFeeEthTransaction};
+use crate::transaction::EthTransaction;
+use crate::types::{self, Wei};
+use borsh::BorshDeserialize;
+use std::convert::TryFrom;
+use std::iter;
+
+const INITIAL_BALANCE: Wei = Wei::new_u64(1_000_000);
+const TRANSFER_AMOUNT: Wei = Wei::new_u64(123);
+const MAX_PRIORITY_FEE_PER_GAS: u64 = 5;
+const MAX_FEE_PER_GAS: u64 = 10;
+const GAS_LIMIT: u64 = 30_000;
+
+#[test]
+fn test_dynamic_fee_tx_encoding_decoding() {
+    let signer = test_utils::Signer::random();
+    let transaction = example_transaction(Address([1; 20]));
+
+    let signed_tx = test_utils::sign_dynamic_fee_transaction(transaction, &signer.secret_key);
+    let bytes = encode_tx(&signed_tx);
+
+    let decoded_tx = match EthTransaction::try_from(bytes.as_slice()) {
+        Ok(EthTransaction::DynamicFee(tx)) => tx,
+        Ok(_) => panic!("Unexpected transaction type"),
+        Err(_) => panic!("Transaction parsing failed"),
+    };
+
+    assert_eq!(signed_tx, decoded_tx);
+
+    assert_eq!(
+        signed_tx.sender().unwrap(),
+        test_utils::address_from_secret_key(&signer.secret_key)
+    )
+}
+
+#[test]
+fn test_dynamic_fee_tx_priority_fee_greater_than_max_fee() {
+    let signer = test_utils::Signer::random();
+    let mut transaction = example_transaction(Address([1; 20]));
+    transaction.max_priority_fee_per_gas = U256::from(MAX_FEE_PER_GAS + 1);
+
+    let signed_tx = test_utils::sign_dynamic_fee_transaction(transaction, &signer.secret_key);
+    let bytes = encode_tx(&signed_tx);
+
+    match EthTransaction::try_from(bytes.as_slice()) {
+        Err(e) => assert_eq!(e.as_ref(), b"ERR_MAX_PRIORITY_FEE_GREATER"),
+        Ok(_) => panic!("Transaction should not parse"),
+    }
+}
+
+#[test]
+fn test_dynamic_fee_tx_charging_gas() {
+    let mut runner = test_utils::deploy_evm();
+    let signer = test_utils::Signer::random();
+    let source_address = test_utils::address_from_secret_key(&signer.secret_key);
+    let dest_address =
+        test_utils::address_from_secret_key(&test_utils::Signer::random().secret_key);
+    runner.create_address(source_address, INITIAL_BALANCE, U256::zero());
+
+    let mut transaction = example_transaction(dest_address);
+    transaction.chain_id = runner.chain_id;
+    let signed_tx = test_utils::sign_dynamic_fee_transaction(transaction, &signer.secret_key);
+
+    let calling_account_id = "some-account.near".to_string();
+    let (maybe_outcome, maybe_error) = runner.call(
+        test_utils::SUBMIT,
+        calling_account_id.clone(),
+        encode_tx(&signed_tx),
+    );
+    assert!(maybe_error.is_none());
+    let result =
+        SubmitResult::try_from_slice(&maybe_outcome.unwrap().return_data.as_value().unwrap())
+            .unwrap();
+    assert_eq!(result.status, TransactionStatus::Succeed(Vec::new()));
+
+    // There is no base fee on Aurora, so the sender pays exactly the priority fee
+    let spent_amount = Wei::new_u64(MAX_PRIORITY_FEE_PER_GAS * result.gas_used);
+    let relayer_address = types::near_account_to_evm_address(calling_account_id.as_bytes());
+
+    test_utils::validate_address_balance_and_nonce(
+        &runner,
+        source_address,
+        INITIAL_BALANCE - TRANSFER_AMOUNT - spent_amount,
+        U256::one(),
+    );
+    test_utils::validate_address_balance_and_nonce(
+        &runner,
+        dest_address,
+        TRANSFER_AMOUNT,
+        U256::zero(),
+    );
+    test_utils::validate_address_balance_and_nonce(
+        &runner,
+        relayer_address,
+        spent_amount,
+        U256::zero(),
+    );
+}
+
+fn example_transaction(to: Address) -> DynamicFeeEthTransaction {
+    DynamicFeeEthTransaction {
+        chain_id: 1,
+        nonce: U256::zero(),
+        max_priority_fee_per_gas: U256::from(MAX_PRIORITY_FEE_PER_GAS),
+        max_fee_per_gas: U256::from(MAX_FEE_PER_GAS),
+        gas_limit: U256::from(GAS_LIMIT),
+        to: Some(to),
+        value: TRANSFER_AMOUNT,
+        data: Vec::new(),
+        access_list: vec![AccessTuple {
+            address: to,
+            storage_keys: vec![H256::zero()],
+        }],
+    }
+}
+
+fn encode_tx(signed_tx: &dynamic_fee::DynamicFeeEthSignedTransaction) -> Vec<u8> {
+    iter::once(dynamic_fee::TYPE_BYTE)
+        .chain(rlp::encode(signed_tx).into_iter())
+        .collect()
+}
@@ -0,0 +1,57 @@
+use crate::parameters::{EstimateGasArgs, EstimateGasResult, TransactionStatus};
+use crate::prelude::Address;
+use crate::test_utils;
+use borsh::BorshSerialize;
+
+/// Init code which sets storage slot 0 to 1 and deploys a contract with runtime code
+/// `PUSH1 0x02 PUSH1 0x00 SSTORE PUSH1 0x01 PUSH1 0x00 SSTORE`.
+const RESTORE_SLOT_CONTRACT_INIT_CODE: &str =
+    "6001600055600a6011600039600a6000f360026000556001600055";
+
+/// Init code which deploys a contract with runtime code `PUSH1 0x00 PUSH1 0x00 REVERT`.
+const REVERT_CONTRACT_INIT_CODE: &str = "6460006000fd6000526005601bf3";
+
+#[test]
+fn test_estimate_gas() {
+    let (mut runner, mut signer) = test_utils::deploy_evm_with_signer();
+    let contract = runner.deploy(&mut signer, RESTORE_SLOT_CONTRACT_INIT_CODE);
+
+    // The call uses 23312 gas after a refund of 2800 (see `net_gas_metering`), but the second
+    // SSTORE requires more than 2300 gas to be left after the 26012 gas consumed before it.
+    let result = estimate_gas(&mut runner, &signer, contract, None);
+    assert_eq!(result, EstimateGasResult::Success(28_313));
+
+    let result = estimate_gas(&mut runner, &signer, contract, Some(28_312));
+    assert_eq!(
+        result,
+        EstimateGasResult::Failure(TransactionStatus::OutOfGas)
+    );
+}
+
+#[test]
+fn test_estimate_gas_revert() {
+    let (mut runner, mut signer) = test_utils::deploy_evm_with_signer();
+    let contract = runner.deploy(&mut signer, REVERT_CONTRACT_INIT_CODE);
+
+    let result = estimate_gas(&mut runner, &signer, contract, None);
+    assert_eq!(
+        result,
+        EstimateGasResult::Failure(TransactionStatus::Revert(Vec::new()))
+    );
+}
+
+fn estimate_gas(
+    runner: &mut test_utils::AuroraRunner,
+    signer: &test_utils::Signer,
+    contract: Address,
+    gas_cap: Option<u64>,
+) -> EstimateGasResult {
+    let args = EstimateGasArgs {
+        sender: test_utils::address_from_secret_key(&signer.secret_key).0,
+        address: contract.0,
+        amount: [0; 32],
+        input: Vec::new(),
+        gas_cap,
+    };
+    runner.view_borsh("estimate_gas", args.try_to_vec().unwrap())
+}
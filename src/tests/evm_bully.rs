@@ -0,0 +1,182 @@
+//! These tests need the engine to be built with the `evm_bully` feature.
+
+use crate::parameters::{
+    AccountBalance, BeginBlockArgs, BeginChainArgs, BeginChainArgsV2, GenesisAccount,
+    GenesisAllocArgs, GetStorageAtArgs,
+};
+use crate::prelude::{Address, Vec, H256, U256};
+use crate::test_utils;
+use crate::types::{u256_to_arr, Wei};
+use borsh::{BorshDeserialize, BorshSerialize};
+
+/// Init code which deploys a contract with runtime code
+/// `NUMBER PUSH1 0x00 MSTORE PUSH1 0x20 PUSH1 0x00 RETURN`.
+const NUMBER_CONTRACT_INIT_CODE: &str = "684360005260206000f360005260096017f3";
+
+/// Init code which deploys a contract with runtime code
+/// `TIMESTAMP PUSH1 0x00 MSTORE PUSH1 0x20 PUSH1 0x00 RETURN`.
+const TIMESTAMP_CONTRACT_INIT_CODE: &str = "684260005260206000f360005260096017f3";
+
+/// Init code which deploys a contract with runtime code `PUSH1 0x00 CALLDATALOAD BLOCKHASH
+/// PUSH1 0x00 MSTORE PUSH1 0x20 PUSH1 0x00 RETURN`, i.e. a contract returning the hash of the
+/// block whose number is given as input.
+const BLOCK_HASH_CONTRACT_INIT_CODE: &str = "6b6000354060005260206000f3600052600c6014f3";
+
+#[test]
+fn test_begin_block() {
+    let (mut runner, mut signer) = test_utils::deploy_evm_with_signer();
+    let number_contract = runner.deploy(&mut signer, NUMBER_CONTRACT_INIT_CODE);
+    let timestamp_contract = runner.deploy(&mut signer, TIMESTAMP_CONTRACT_INIT_CODE);
+    let block_hash_contract = runner.deploy(&mut signer, BLOCK_HASH_CONTRACT_INIT_CODE);
+
+    let parent_hash = H256::repeat_byte(1);
+    begin_block(&mut runner, 4_999_999, parent_hash, 1_438_269_970);
+    let hash = H256::repeat_byte(2);
+    begin_block(&mut runner, 5_000_000, hash, 1_438_269_988);
+
+    let number = call(&mut runner, &mut signer, number_contract, Vec::new());
+    assert_eq!(number, U256::from(5_000_000));
+    let timestamp = call(&mut runner, &mut signer, timestamp_contract, Vec::new());
+    assert_eq!(timestamp, U256::from(1_438_269_988));
+
+    // Only the hashes of the blocks before the replayed one are available
+    let input = u256_to_arr(&U256::from(4_999_999)).to_vec();
+    let block_hash = call(&mut runner, &mut signer, block_hash_contract, input);
+    assert_eq!(block_hash, U256::from_big_endian(parent_hash.as_bytes()));
+    let input = u256_to_arr(&U256::from(5_000_000)).to_vec();
+    let block_hash = call(&mut runner, &mut signer, block_hash_contract, input);
+    assert_eq!(block_hash, U256::zero());
+}
+
+#[test]
+fn test_import_genesis_alloc_in_chunks() {
+    let mut runner = test_utils::deploy_evm();
+    let funded = Address::from_low_u64_be(1);
+    let contract = Address::from_low_u64_be(2);
+
+    let args = BeginChainArgsV2 {
+        chain_id: u256_to_arr(&U256::from(runner.chain_id)),
+        genesis_alloc: vec![GenesisAccount {
+            address: funded.0,
+            balance: Some(u256_to_arr(&U256::from(1000))),
+            nonce: None,
+            code: None,
+            storage: Vec::new(),
+        }],
+    };
+    runner.call_as_owner("begin_chain_v2", args.try_to_vec().unwrap());
+
+    // Borsh-encoded chunk, with the code, nonce and first slot of the contract
+    let args = GenesisAllocArgs {
+        genesis_alloc: vec![GenesisAccount {
+            address: contract.0,
+            balance: None,
+            nonce: Some(u256_to_arr(&U256::one())),
+            code: Some(vec![0x60, 0x00]),
+            storage: vec![(slot(1), slot(42))],
+        }],
+    };
+    runner.call_as_owner("import_genesis_alloc", args.try_to_vec().unwrap());
+
+    // JSON chunk, with the second slot of the contract: the fields left out keep their value
+    let json = r#"{"alloc": {
+        "0x0000000000000000000000000000000000000001": {"nonce": "0x5"},
+        "0x0000000000000000000000000000000000000002": {"storage": {"0x02": "0x2b"}}
+    }}"#;
+    runner.call_as_owner("import_genesis_alloc_json", json.as_bytes().to_vec());
+
+    assert_eq!(runner.get_balance(funded), Wei::new_u64(1000));
+    assert_eq!(runner.get_nonce(funded), U256::from(5));
+    assert_eq!(runner.get_nonce(contract), U256::one());
+    assert_eq!(
+        runner.view("get_code", contract.as_bytes().to_vec()),
+        vec![0x60, 0x00]
+    );
+    assert_eq!(get_storage_at(&mut runner, contract, 1), slot(42));
+    assert_eq!(get_storage_at(&mut runner, contract, 2), slot(43));
+}
+
+#[test]
+fn test_begin_chain() {
+    let mut runner = test_utils::deploy_evm();
+    let chain_id = u256_to_arr(&U256::from(runner.chain_id));
+    let funded = Address::from_low_u64_be(1);
+    let contract = Address::from_low_u64_be(2);
+    let other = Address::from_low_u64_be(3);
+
+    let args = BeginChainArgs {
+        chain_id,
+        genesis_alloc: vec![AccountBalance {
+            address: funded.0,
+            balance: u256_to_arr(&U256::from(1000)),
+        }],
+    };
+    runner.call_as_owner("begin_chain", args.try_to_vec().unwrap());
+    assert_eq!(runner.get_balance(funded), Wei::new_u64(1000));
+
+    // This account has the size of an `AccountBalance`, yet its code is imported
+    let args = BeginChainArgsV2 {
+        chain_id,
+        genesis_alloc: vec![GenesisAccount {
+            address: contract.0,
+            balance: None,
+            nonce: None,
+            code: Some(vec![0x00; 21]),
+            storage: Vec::new(),
+        }],
+    };
+    let input = args.try_to_vec().unwrap();
+    assert!(BeginChainArgs::try_from_slice(&input).is_ok());
+    runner.call_as_owner("begin_chain_v2", input);
+    assert_eq!(runner.get_balance(contract), Wei::zero());
+    assert_eq!(
+        runner.view("get_code", contract.as_bytes().to_vec()),
+        vec![0x00; 21]
+    );
+
+    let json = format!(
+        r#"{{"chain_id": {}, "alloc": {{
+            "0x0000000000000000000000000000000000000003": {{"balance": "0x2a", "nonce": "0x1"}}
+        }}}}"#,
+        runner.chain_id
+    );
+    runner.call_as_owner("begin_chain_json", json.into_bytes());
+    assert_eq!(runner.get_balance(other), Wei::new_u64(42));
+    assert_eq!(runner.get_nonce(other), U256::one());
+}
+
+fn begin_block(runner: &mut test_utils::AuroraRunner, number: u64, hash: H256, timestamp: u64) {
+    let args = BeginBlockArgs {
+        hash: hash.0,
+        coinbase: [0; 20],
+        timestamp: u256_to_arr(&U256::from(timestamp)),
+        number: u256_to_arr(&U256::from(number)),
+        difficulty: [0; 32],
+        gaslimit: u256_to_arr(&U256::from(u64::MAX)),
+    };
+    runner.call_as_owner("begin_block", args.try_to_vec().unwrap());
+}
+
+fn call(
+    runner: &mut test_utils::AuroraRunner,
+    signer: &mut test_utils::Signer,
+    contract: Address,
+    input: Vec<u8>,
+) -> U256 {
+    let result = runner.call_contract(signer, contract, input);
+    U256::from_big_endian(&test_utils::unwrap_success(result))
+}
+
+fn get_storage_at(runner: &mut test_utils::AuroraRunner, address: Address, key: u64) -> [u8; 32] {
+    let args = GetStorageAtArgs {
+        address: address.0,
+        key: slot(key),
+    };
+    let mut value = [0; 32];
+    value.copy_from_slice(&runner.view("get_storage_at", args.try_to_vec().unwrap()));
+    value
+}
+
+fn slot(value: u64) -> [u8; 32] {
+    H256::from_low_u64_be(value).0
+}
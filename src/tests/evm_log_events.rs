@@ -0,0 +1,60 @@
+use crate::prelude::Address;
+use crate::test_utils;
+use crate::types::keccak;
+use borsh::BorshSerialize;
+
+/// Init code which deploys a contract with runtime code
+/// `PUSH1 0x2a PUSH1 0x00 MSTORE PUSH1 0x01 PUSH1 0x20 PUSH1 0x00 LOG1 STOP`,
+/// i.e. a contract which emits a log with topic 1 and data 42.
+const LOG_CONTRACT_INIT_CODE: &str = "6c602a600052600160206000a100600052600d6013f3";
+
+#[test]
+fn test_evm_log_events() {
+    let (mut runner, mut signer) = test_utils::deploy_evm_with_signer();
+    let contract = runner.deploy(&mut signer, LOG_CONTRACT_INIT_CODE);
+
+    // Logs are not written as NEAR logs by default
+    call(&mut runner, &mut signer, contract);
+    assert!(event_logs(&runner).is_empty());
+
+    runner.call_as_owner("set_evm_log_events", true.try_to_vec().unwrap());
+
+    let transaction_hash = call(&mut runner, &mut signer, contract);
+    let expected = format!(
+        concat!(
+            r#"EVENT_JSON:{{"standard":"aurora","version":"1.0.0","event":"evm_log","data":[{{"#,
+            r#""address":"0x{}","#,
+            r#""topics":["0x0000000000000000000000000000000000000000000000000000000000000001"],"#,
+            r#""data":"0x000000000000000000000000000000000000000000000000000000000000002a","#,
+            r#""tx_hash":"0x{}"}}]}}"#,
+        ),
+        hex::encode(contract),
+        hex::encode(transaction_hash),
+    );
+    assert_eq!(event_logs(&runner), vec![expected]);
+}
+
+/// Calls the contract and returns the hash of the transaction.
+fn call(
+    runner: &mut test_utils::AuroraRunner,
+    signer: &mut test_utils::Signer,
+    contract: Address,
+) -> [u8; 32] {
+    // Signatures are deterministic, so this is the transaction submitted below
+    let tx = test_utils::call_transaction(contract, Vec::new(), signer.nonce.into());
+    let signed_tx = test_utils::sign_transaction(tx, Some(runner.chain_id), &signer.secret_key);
+    let transaction_hash = keccak(&rlp::encode(&signed_tx)).0;
+
+    let result = runner.call_contract(signer, contract, Vec::new());
+    test_utils::panic_on_fail(result.status);
+    transaction_hash
+}
+
+fn event_logs(runner: &test_utils::AuroraRunner) -> Vec<String> {
+    runner
+        .previous_logs
+        .iter()
+        .filter(|log| log.starts_with("EVENT_JSON:"))
+        .cloned()
+        .collect()
+}
@@ -0,0 +1,100 @@
+use crate::engine::HardForkId;
+use crate::parameters::ScheduleHardForkArgs;
+use crate::test_utils;
+use borsh::BorshSerialize;
+
+/// Init code which deploys a contract with runtime code
+/// `BASEFEE PUSH1 0x00 MSTORE PUSH1 0x20 PUSH1 0x00 RETURN`.
+const BASEFEE_CONTRACT_INIT_CODE: &str = "684860005260206000f360005260096017f3";
+
+/// Init code which deploys a contract with runtime code
+/// `PUSH1 0x01 PUSH1 0x00 SSTORE PUSH1 0x00 PUSH1 0x00 SSTORE`,
+/// i.e. a contract which sets and then clears storage slot 0.
+const SET_AND_CLEAR_CONTRACT_INIT_CODE: &str = "6960016000556000600055600052600a6016f3";
+
+/// Init code which deploys a contract with runtime code `CALLER SELFDESTRUCT`.
+const SELF_DESTRUCT_CONTRACT_INIT_CODE: &str = "6133ff6000526002601ef3";
+
+/// Init code which deploys a contract consisting of the single byte 0xEF.
+const EF_CONTRACT_INIT_CODE: &str = "60ef60005360016000f3";
+
+/// Init code which deploys a contract consisting of the single byte 0xFE.
+const FE_CONTRACT_INIT_CODE: &str = "60fe60005360016000f3";
+
+// EIP-3198
+#[test]
+fn test_basefee_opcode() {
+    let (mut runner, mut signer) = test_utils::deploy_evm_with_signer();
+    let contract = runner.deploy(&mut signer, BASEFEE_CONTRACT_INIT_CODE);
+
+    let result = runner.call_contract(&mut signer, contract, Vec::new());
+
+    // There is no base fee on Aurora
+    assert_eq!(test_utils::unwrap_success(result), vec![0u8; 32]);
+}
+
+// EIP-3529
+#[test]
+fn test_reduced_sstore_clear_refund() {
+    let (mut runner, mut signer) = test_utils::deploy_evm_with_signer();
+    let contract = runner.deploy(&mut signer, SET_AND_CLEAR_CONTRACT_INIT_CODE);
+
+    let result = runner.call_contract(&mut signer, contract, Vec::new());
+    test_utils::panic_on_fail(result.status);
+
+    // Execution costs 21000 (base) + 4 * 3 (PUSH1) + 22100 (cold SSTORE, set)
+    // + 100 (warm SSTORE, dirty) = 43212 with a refund of 19900. The refund is capped at
+    // a fifth of the gas used (it would have been capped at half of it before London).
+    assert_eq!(result.gas_used, 43_212 - 43_212 / 5);
+}
+
+// EIP-3529
+#[test]
+fn test_no_self_destruct_refund() {
+    let (mut runner, mut signer) = test_utils::deploy_evm_with_signer();
+    let contract = runner.deploy(&mut signer, SELF_DESTRUCT_CONTRACT_INIT_CODE);
+
+    let result = runner.call_contract(&mut signer, contract, Vec::new());
+    test_utils::panic_on_fail(result.status);
+
+    // 21000 (base) + 2 (CALLER) + 5000 (SELFDESTRUCT), with no refund.
+    assert_eq!(result.gas_used, 26_002);
+}
+
+// EIP-3541
+#[test]
+fn test_reject_code_starting_with_ef() {
+    let (mut runner, mut signer) = test_utils::deploy_evm_with_signer();
+
+    // Code starting with other bytes can still be deployed
+    runner.deploy(&mut signer, FE_CONTRACT_INIT_CODE);
+
+    let err = runner
+        .submit_with_signer(&mut signer, |nonce| {
+            test_utils::create_transaction(EF_CONTRACT_INIT_CODE, nonce)
+        })
+        .unwrap_err();
+    let error_message = format!("{:?}", err);
+    assert!(error_message.contains("ERR_INVALID_CODE"));
+}
+
+#[test]
+fn test_new_deployment_uses_london() {
+    let mut runner = test_utils::deploy_evm();
+
+    let hard_fork: HardForkId = runner.view_borsh("get_hard_fork", Vec::new());
+    assert_eq!(hard_fork, HardForkId::London);
+
+    // There is no newer hard fork to switch to
+    let args = ScheduleHardForkArgs {
+        hard_fork: HardForkId::London,
+        block_height: 1_000,
+    };
+    let (_, error) = runner.call(
+        "schedule_hard_fork",
+        runner.aurora_account_id.clone(),
+        args.try_to_vec().unwrap(),
+    );
+    let error_message = format!("{:?}", error.unwrap());
+    assert!(error_message.contains("ERR_HARD_FORK_DOWNGRADE"));
+}
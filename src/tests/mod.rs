@@ -1,11 +1,31 @@
 mod access_lists;
+mod account_migration;
+mod block_hash;
+mod block_properties;
+mod block_receipts;
+mod code_storage;
 mod contract_call;
+mod eip1559;
 mod erc20;
 mod erc20_connector;
+mod estimate_gas;
 mod eth_connector;
+#[cfg(feature = "evm_bully")]
+mod evm_bully;
+mod evm_log_events;
+mod london_hard_fork;
 #[cfg(feature = "meta-call")]
 mod meta_parsing;
+mod net_gas_metering;
 mod sanity;
 mod self_destruct_state;
 mod standard_precompiles;
+mod state_commitment;
+mod state_dump;
 mod state_migration;
+mod state_override;
+mod storage_charge;
+mod storage_cleanup;
+#[cfg(feature = "tracing")]
+mod trace_call;
+mod view_result;
@@ -0,0 +1,60 @@
+use crate::test_utils;
+
+/// Init code which sets storage slot 0 to 1 and deploys a contract with runtime code
+/// `PUSH1 0x02 PUSH1 0x00 SSTORE`.
+const MODIFY_SLOT_CONTRACT_INIT_CODE: &str = "60016000556005601160003960056000f36002600055";
+
+/// Init code which sets storage slot 0 to 1 and deploys a contract with runtime code
+/// `PUSH1 0x00 PUSH1 0x00 SSTORE`.
+const CLEAR_SLOT_CONTRACT_INIT_CODE: &str = "60016000556005601160003960056000f36000600055";
+
+/// Init code which sets storage slot 0 to 1 and deploys a contract with runtime code
+/// `PUSH1 0x02 PUSH1 0x00 SSTORE PUSH1 0x01 PUSH1 0x00 SSTORE`, i.e. the pattern of
+/// a re-entrancy guard which is entered and then exited.
+const RESTORE_SLOT_CONTRACT_INIT_CODE: &str =
+    "6001600055600a6011600039600a6000f360026000556001600055";
+
+// The expected values below follow EIP-2200 with the changes of EIP-2929 and EIP-3529.
+
+#[test]
+fn test_modify_slot() {
+    let (mut runner, mut signer) = test_utils::deploy_evm_with_signer();
+    let contract = runner.deploy(&mut signer, MODIFY_SLOT_CONTRACT_INIT_CODE);
+
+    let result = runner.call_contract(&mut signer, contract, Vec::new());
+    test_utils::panic_on_fail(result.status);
+
+    // 21000 (base) + 2 * 3 (PUSH1) + 2100 (cold slot) + 2900 (SSTORE, reset)
+    assert_eq!(result.gas_used, 26_006);
+}
+
+#[test]
+fn test_clear_slot() {
+    let (mut runner, mut signer) = test_utils::deploy_evm_with_signer();
+    let contract = runner.deploy(&mut signer, CLEAR_SLOT_CONTRACT_INIT_CODE);
+
+    let result = runner.call_contract(&mut signer, contract, Vec::new());
+    test_utils::panic_on_fail(result.status);
+
+    // 26006, as above, with a refund of 4800 for clearing the slot.
+    assert_eq!(result.gas_used, 21_206);
+}
+
+#[test]
+fn test_restore_slot() {
+    let (mut runner, mut signer) = test_utils::deploy_evm_with_signer();
+    let contract = runner.deploy(&mut signer, RESTORE_SLOT_CONTRACT_INIT_CODE);
+
+    let result = runner.call_contract(&mut signer, contract, Vec::new());
+    test_utils::panic_on_fail(result.status);
+
+    // 21000 (base) + 4 * 3 (PUSH1) + 2100 (cold slot) + 2900 (SSTORE, reset)
+    // + 100 (SSTORE, dirty) = 26112 with a refund of 2900 - 100 = 2800 for restoring
+    // the original value.
+    assert_eq!(result.gas_used, 23_312);
+
+    // The slot is left untouched, so the transaction can be repeated at the same cost.
+    let result = runner.call_contract(&mut signer, contract, Vec::new());
+    test_utils::panic_on_fail(result.status);
+    assert_eq!(result.gas_used, 23_312);
+}
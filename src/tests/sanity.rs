@@ -1,10 +1,11 @@
 use crate::fungible_token::FungibleTokenMetadata;
-use crate::parameters::{SubmitResult, TransactionStatus};
+use crate::parameters::{SubmitResult, SubmitResultV2, TransactionStatus};
 use crate::prelude::{Address, U256};
 use crate::test_utils;
 use crate::tests::state_migration;
+use crate::transaction::LegacyEthTransaction;
 use crate::types::{self, Wei, ERC20_MINT_SELECTOR};
-use borsh::BorshSerialize;
+use borsh::{BorshDeserialize, BorshSerialize};
 use secp256k1::SecretKey;
 use std::path::{Path, PathBuf};
 
@@ -252,6 +253,40 @@ fn test_eth_transfer_charging_gas_not_enough_balance() {
     test_utils::validate_address_balance_and_nonce(&runner, relayer, Wei::zero(), 0.into());
 }
 
+/// Tests that the result of `submit_v2` identifies the executed transaction.
+#[test]
+fn test_submit_result_transaction() {
+    let (mut runner, mut source_account, _) = initialize_transfer();
+    let source_address = test_utils::address_from_secret_key(&source_account.secret_key);
+    let nonce = source_account.use_nonce();
+    let tx = LegacyEthTransaction {
+        nonce: nonce.into(),
+        gas_price: U256::zero(),
+        gas: u64::MAX.into(),
+        to: None,
+        value: Wei::zero(),
+        data: Vec::new(),
+    };
+    let signed_tx =
+        test_utils::sign_transaction(tx, Some(runner.chain_id), &source_account.secret_key);
+    let raw_tx = rlp::encode(&signed_tx).to_vec();
+
+    let (outcome, maybe_error) =
+        runner.call("submit_v2", "some-account.near".to_string(), raw_tx.clone());
+    assert!(maybe_error.is_none());
+    let result =
+        SubmitResultV2::try_from_slice(&outcome.unwrap().return_data.as_value().unwrap()).unwrap();
+
+    assert_eq!(result.transaction.hash, types::keccak(&raw_tx).0);
+    assert_eq!(result.transaction.sender, source_address.0);
+    assert_eq!(result.transaction.nonce, types::u256_to_arr(&nonce.into()));
+    let created_address = result.created_address.unwrap();
+    assert_eq!(
+        test_utils::unwrap_success(result.result),
+        created_address.to_vec()
+    );
+}
+
 fn initialize_transfer() -> (test_utils::AuroraRunner, test_utils::Signer, Address) {
     // set up Aurora runner and accounts
     let mut runner = test_utils::deploy_evm();
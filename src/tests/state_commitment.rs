@@ -0,0 +1,241 @@
+use crate::parameters::{
+    AccountProof, BackfillStateCommitmentArgs, BackfillStateCommitmentResult, GetProofArgs,
+    IndexAccountsArgs, IndexedAccount,
+};
+use crate::prelude::{Address, BTreeMap, Vec, H256, U256};
+use crate::storage::{bytes_to_key, KeyPrefix};
+use crate::test_utils;
+use crate::trie;
+use crate::types::{keccak, u256_to_arr};
+use borsh::{BorshDeserialize, BorshSerialize};
+use rlp::RlpStream;
+
+/// Init code `PUSH1 0x2a PUSH1 0x00 SSTORE STOP`, i.e. a contract without code which stores 42
+/// in its slot 0.
+const SSTORE_INIT_CODE: &str = "602a60005500";
+/// Init code of a contract which stores 42 in its slot 0, and whose code `CALLER SELFDESTRUCT`
+/// destroys it when called.
+const SELFDESTRUCT_INIT_CODE: &str = "602a6000556133ff6000526002601ef3";
+
+#[test]
+fn test_get_proof() {
+    let (mut runner, mut signer) = test_utils::deploy_evm_with_signer();
+    let address = test_utils::address_from_secret_key(&signer.secret_key);
+
+    runner.call_as_owner("enable_state_commitment", Vec::new());
+    assert!(backfill(&mut runner, 100).complete);
+
+    let contract = runner.deploy(&mut signer, SSTORE_INIT_CODE);
+
+    let proof = get_proof(&mut runner, contract, vec![[0; 32], [1; 32]]);
+    assert_eq!(proof.state_root, get_state_root(&mut runner).0);
+    assert_eq!(proof.nonce, u256_to_arr(&U256::one()));
+    assert_eq!(proof.code_hash, keccak(&[]).0);
+    assert_eq!(
+        verify(&proof.state_root, contract.as_bytes(), &proof.account_proof),
+        Some(encode_account(&proof))
+    );
+
+    let storage_proof = &proof.storage_proof[0];
+    assert_eq!(storage_proof.value, u256_to_arr(&U256::from(42)));
+    assert_eq!(
+        verify(&proof.storage_hash, &[0; 32], &storage_proof.proof),
+        Some(rlp::encode(&U256::from(42)).to_vec())
+    );
+    // The proof of a missing slot shows that it is absent
+    let storage_proof = &proof.storage_proof[1];
+    assert_eq!(storage_proof.value, [0; 32]);
+    assert_eq!(
+        verify(&proof.storage_hash, &[1; 32], &storage_proof.proof),
+        None
+    );
+
+    // The sender is part of the state since its nonce changed
+    let proof = get_proof(&mut runner, address, Vec::new());
+    assert_eq!(proof.balance, test_utils::INITIAL_BALANCE.to_bytes());
+    assert_eq!(
+        verify(&proof.state_root, address.as_bytes(), &proof.account_proof),
+        Some(encode_account(&proof))
+    );
+}
+
+#[test]
+fn test_get_proof_disabled() {
+    let mut runner = test_utils::deploy_evm();
+    let args = GetProofArgs {
+        address: [1; 20],
+        storage_keys: Vec::new(),
+    };
+    let (_, maybe_error) = runner.call(
+        "get_proof",
+        "viewer".to_string(),
+        args.try_to_vec().unwrap(),
+    );
+    assert!(maybe_error.is_some());
+}
+
+#[test]
+fn test_backfill() {
+    let signer = test_utils::Signer::random();
+    let address = test_utils::address_from_secret_key(&signer.secret_key);
+
+    // The commitment is maintained from the start
+    let mut incremental_runner = test_utils::deploy_evm();
+    let mut incremental_signer = test_utils::Signer::new(signer.secret_key.clone());
+    incremental_runner.create_address(address, test_utils::INITIAL_BALANCE, U256::zero());
+    incremental_runner.call_as_owner("enable_state_commitment", Vec::new());
+    assert!(backfill(&mut incremental_runner, 100).complete);
+    deploy_contracts(&mut incremental_runner, &mut incremental_signer);
+
+    // The commitment is enabled once the accounts are stored
+    let mut runner = test_utils::deploy_evm();
+    let mut signer = signer;
+    runner.create_address(address, test_utils::INITIAL_BALANCE, U256::zero());
+    let contract = deploy_contracts(&mut runner, &mut signer);
+    runner.call_as_owner("enable_state_commitment", Vec::new());
+
+    let (_, maybe_error) = runner.call("get_state_root", "viewer".to_string(), Vec::new());
+    assert!(maybe_error.is_some());
+    let args = GetProofArgs {
+        address: contract.0,
+        storage_keys: Vec::new(),
+    };
+    let (_, maybe_error) = runner.call(
+        "get_proof",
+        "viewer".to_string(),
+        args.try_to_vec().unwrap(),
+    );
+    assert!(maybe_error.is_some());
+
+    // One step for each of the sender and the destroyed contract, and for the stored slot
+    let mut steps = 0;
+    loop {
+        let result = backfill(&mut runner, 1);
+        steps += result.steps;
+        if result.complete {
+            break;
+        }
+    }
+    assert_eq!(steps, 3);
+    assert_eq!(backfill(&mut runner, 1).steps, 0);
+
+    assert_eq!(
+        get_state_root(&mut runner),
+        get_state_root(&mut incremental_runner)
+    );
+    let proof = get_proof(&mut runner, contract, vec![[0; 32]]);
+    assert_eq!(
+        verify(&proof.state_root, contract.as_bytes(), &proof.account_proof),
+        Some(encode_account(&proof))
+    );
+    assert_eq!(
+        verify(&proof.storage_hash, &[0; 32], &proof.storage_proof[0].proof),
+        Some(rlp::encode(&U256::from(42)).to_vec())
+    );
+
+    // The nodes replaced while maintaining the commitment, including those of the storage of
+    // the destroyed contract, were removed
+    assert_eq!(node_count(&runner), node_count(&incremental_runner));
+}
+
+#[test]
+fn test_backfill_unindexed_account() {
+    let mut runner = test_utils::deploy_evm();
+    let address = Address::from_low_u64_be(1);
+    runner.create_legacy_address(address, test_utils::INITIAL_BALANCE, 7.into());
+    runner.call_as_owner("enable_state_commitment", Vec::new());
+
+    // The account is not in the index, so the backfill does not complete
+    assert!(!backfill(&mut runner, 100).complete);
+    assert!(!backfill(&mut runner, 100).complete);
+    let (_, maybe_error) = runner.call("get_state_root", "viewer".to_string(), Vec::new());
+    assert!(maybe_error.is_some());
+
+    let args = IndexAccountsArgs {
+        accounts: vec![IndexedAccount {
+            address: address.0,
+            storage_keys: Vec::new(),
+        }],
+    };
+    runner.call_as_owner("index_accounts", args.try_to_vec().unwrap());
+    runner.call_as_owner("finish_account_indexing", Vec::new());
+    assert!(backfill(&mut runner, 100).complete);
+
+    let proof = get_proof(&mut runner, address, Vec::new());
+    assert_eq!(proof.balance, test_utils::INITIAL_BALANCE.to_bytes());
+    assert_eq!(proof.nonce, u256_to_arr(&U256::from(7)));
+    assert_eq!(
+        verify(&proof.state_root, address.as_bytes(), &proof.account_proof),
+        Some(encode_account(&proof))
+    );
+}
+
+/// Deploys a contract storing a value, and a contract which is destroyed along with its
+/// storage. Returns the address of the first one.
+fn deploy_contracts(
+    runner: &mut test_utils::AuroraRunner,
+    signer: &mut test_utils::Signer,
+) -> Address {
+    let contract = runner.deploy(signer, SSTORE_INIT_CODE);
+    let destroyed = runner.deploy(signer, SELFDESTRUCT_INIT_CODE);
+    runner.call_contract(signer, destroyed, Vec::new());
+    contract
+}
+
+/// Returns the number of trie nodes and reference counts stored.
+fn node_count(runner: &test_utils::AuroraRunner) -> usize {
+    let node_prefix = bytes_to_key(KeyPrefix::StateCommitment, &[2]);
+    let refcount_prefix = bytes_to_key(KeyPrefix::StateCommitment, &[3]);
+    runner
+        .ext
+        .fake_trie
+        .keys()
+        .filter(|key| key.starts_with(&node_prefix) || key.starts_with(&refcount_prefix))
+        .count()
+}
+
+/// Returns the value proven at the hash of `key`.
+fn verify(root: &[u8; 32], key: &[u8], proof: &[Vec<u8>]) -> Option<Vec<u8>> {
+    let store: BTreeMap<H256, Vec<u8>> = proof
+        .iter()
+        .map(|node| (keccak(node), node.clone()))
+        .collect();
+    trie::get(&store, &H256(*root), keccak(key).as_bytes())
+}
+
+fn encode_account(proof: &AccountProof) -> Vec<u8> {
+    let mut stream = RlpStream::new_list(4);
+    stream.append(&U256::from_big_endian(&proof.nonce));
+    stream.append(&U256::from_big_endian(&proof.balance));
+    stream.append(&H256(proof.storage_hash));
+    stream.append(&H256(proof.code_hash));
+    stream.out().to_vec()
+}
+
+fn get_proof(
+    runner: &mut test_utils::AuroraRunner,
+    address: Address,
+    storage_keys: Vec<[u8; 32]>,
+) -> AccountProof {
+    let args = GetProofArgs {
+        address: address.0,
+        storage_keys,
+    };
+    runner.view_borsh("get_proof", args.try_to_vec().unwrap())
+}
+
+fn backfill(runner: &mut test_utils::AuroraRunner, limit: u32) -> BackfillStateCommitmentResult {
+    let args = BackfillStateCommitmentArgs { limit };
+    let (outcome, maybe_error) = runner.call(
+        "backfill_state_commitment",
+        runner.aurora_account_id.clone(),
+        args.try_to_vec().unwrap(),
+    );
+    assert!(maybe_error.is_none());
+    BackfillStateCommitmentResult::try_from_slice(&outcome.unwrap().return_data.as_value().unwrap())
+        .unwrap()
+}
+
+fn get_state_root(runner: &mut test_utils::AuroraRunner) -> H256 {
+    H256::from_slice(&runner.view("get_state_root", Vec::new()))
+}
@@ -0,0 +1,153 @@
+use crate::parameters::{DumpStateArgs, IndexAccountsArgs, IndexedAccount};
+use crate::prelude::{Address, BTreeMap, H256};
+use crate::storage;
+use crate::test_utils::{self, INITIAL_BALANCE};
+use borsh::BorshSerialize;
+use serde_json::Value;
+
+/// Init code `PUSH1 0x2a PUSH1 0x00 SSTORE PUSH1 0x2b PUSH1 0x01 SSTORE STOP`, i.e. a contract
+/// without code which stores 42 and 43 in its slots 0 and 1.
+const SSTORE_INIT_CODE: &str = "602a600055602b60015500";
+
+#[test]
+fn test_dump_state_pages() {
+    let (mut runner, mut signer) = test_utils::deploy_evm_with_signer();
+    let sender = test_utils::address_from_secret_key(&signer.secret_key);
+    let first = runner.deploy(&mut signer, SSTORE_INIT_CODE);
+    let second = runner.deploy(&mut signer, SSTORE_INIT_CODE);
+
+    // The storage of the contracts is split over several pages
+    let pages = dump_all(&mut runner, 2, 1);
+    assert!(pages.len() >= 4);
+    for page in pages.iter() {
+        let slots: usize = page["accounts"]
+            .as_object()
+            .unwrap()
+            .values()
+            .filter_map(|account| account.get("storage"))
+            .map(|storage| storage.as_object().unwrap().len())
+            .sum();
+        assert!(slots <= 1);
+    }
+
+    let accounts = merge(&pages);
+    assert_eq!(accounts.len(), 3);
+    let sender_account = &accounts[&hex_address(&sender)];
+    assert_eq!(sender_account.0, INITIAL_BALANCE.raw().to_string());
+    assert!(sender_account.1.is_empty());
+    let expected_storage: BTreeMap<String, String> = vec![
+        (
+            hex_h256(&H256::zero()),
+            hex_h256(&H256::from_low_u64_be(42)),
+        ),
+        (
+            hex_h256(&H256::from_low_u64_be(1)),
+            hex_h256(&H256::from_low_u64_be(43)),
+        ),
+    ]
+    .into_iter()
+    .collect();
+    for contract in [first, second].iter() {
+        assert_eq!(accounts[&hex_address(contract)].1, expected_storage);
+    }
+}
+
+#[test]
+fn test_index_accounts() {
+    let mut runner = test_utils::deploy_evm();
+    let address = Address::from_low_u64_be(1);
+    runner.create_legacy_address(address, INITIAL_BALANCE, 0.into());
+    let key = H256::from_low_u64_be(7);
+    let value = H256::from_low_u64_be(8);
+    runner.ext.fake_trie.insert(
+        storage::storage_to_key(&address, &key, 0).as_ref().to_vec(),
+        value.as_bytes().to_vec(),
+    );
+
+    // Accounts stored before the account index are not listed until they are indexed, and the
+    // dump is incomplete until the owner confirms that all of them are
+    let pages = dump_all(&mut runner, 100, 1000);
+    assert!(merge(&pages).is_empty());
+    assert_eq!(pages[0]["complete"], Value::Bool(false));
+
+    let args = IndexAccountsArgs {
+        accounts: vec![
+            IndexedAccount {
+                address: address.0,
+                storage_keys: vec![key.0, [9; 32]],
+            },
+            // Addresses without an account are left out
+            IndexedAccount {
+                address: [2; 20],
+                storage_keys: Vec::new(),
+            },
+        ],
+    };
+    runner.call_as_owner("index_accounts", args.try_to_vec().unwrap());
+    assert_eq!(
+        dump_all(&mut runner, 100, 1000)[0]["complete"],
+        Value::Bool(false)
+    );
+    runner.call_as_owner("finish_account_indexing", Vec::new());
+
+    let pages = dump_all(&mut runner, 100, 1000);
+    assert!(pages
+        .iter()
+        .all(|page| page["complete"] == Value::Bool(true)));
+    let accounts = merge(&pages);
+    assert_eq!(accounts.len(), 1);
+    let storage: BTreeMap<String, String> = vec![(hex_h256(&key), hex_h256(&value))]
+        .into_iter()
+        .collect();
+    assert_eq!(accounts[&hex_address(&address)].1, storage);
+}
+
+/// Returns all the pages of the dump, listing at most `limit` accounts and `storage_limit`
+/// slots each.
+fn dump_all(runner: &mut test_utils::AuroraRunner, limit: u32, storage_limit: u32) -> Vec<Value> {
+    let mut pages = Vec::new();
+    let mut args = DumpStateArgs {
+        start: 0,
+        storage_start: 0,
+        limit,
+        storage_limit,
+    };
+    loop {
+        let page: Value =
+            serde_json::from_slice(&runner.view("dump_state", args.try_to_vec().unwrap())).unwrap();
+        let next = page["next"].clone();
+        pages.push(page);
+        if next.is_null() {
+            return pages;
+        }
+        args.start = next["start"].as_u64().unwrap();
+        args.storage_start = next["storageStart"].as_u64().unwrap();
+    }
+}
+
+/// Returns the balance and storage of the accounts listed in the pages.
+fn merge(pages: &[Value]) -> BTreeMap<String, (String, BTreeMap<String, String>)> {
+    let mut accounts: BTreeMap<String, (String, BTreeMap<String, String>)> = BTreeMap::new();
+    for page in pages {
+        for (address, account) in page["accounts"].as_object().unwrap() {
+            let entry = accounts.entry(address.clone()).or_default();
+            entry.0 = account["balance"].as_str().unwrap().to_string();
+            if let Some(storage) = account.get("storage") {
+                for (key, value) in storage.as_object().unwrap() {
+                    entry
+                        .1
+                        .insert(key.clone(), value.as_str().unwrap().to_string());
+                }
+            }
+        }
+    }
+    accounts
+}
+
+fn hex_address(address: &Address) -> String {
+    format!("0x{}", hex::encode(address))
+}
+
+fn hex_h256(value: &H256) -> String {
+    format!("0x{}", hex::encode(value))
+}
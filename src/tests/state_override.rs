@@ -0,0 +1,119 @@
+use crate::parameters::{StateOverride, TransactionStatus, ViewCallArgs, ViewCallArgsV2};
+use crate::prelude::{Address, U256};
+use crate::test_utils;
+use crate::types::{u256_to_arr, Wei};
+use borsh::BorshSerialize;
+use rlp::RlpStream;
+use sha3::{Digest, Keccak256};
+
+/// Runtime code `PUSH1 0x00 SLOAD PUSH1 0x00 MSTORE PUSH1 0x20 PUSH1 0x00 RETURN`,
+/// i.e. a contract which returns the value of its storage slot 0.
+const SLOAD_CODE: &str = "60005460005260206000f3";
+
+/// Runtime code `CALLER BALANCE PUSH1 0x00 MSTORE PUSH1 0x20 PUSH1 0x00 RETURN`,
+/// i.e. a contract which returns the balance of its caller.
+const CALLER_BALANCE_CODE: &str = "333160005260206000f3";
+
+/// Runtime code `PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 CREATE PUSH1 0x00 MSTORE PUSH1 0x20
+/// PUSH1 0x00 RETURN`, i.e. a contract which deploys an empty contract and returns its address.
+const CREATE_CODE: &str = "600060006000f060005260206000f3";
+
+#[test]
+fn test_override_code_and_storage() {
+    let mut runner = test_utils::deploy_evm();
+    let contract = Address([1; 20]);
+    let mut args = view_args(Address([2; 20]), contract);
+    args.state_override.push(StateOverride {
+        address: contract.0,
+        balance: None,
+        nonce: None,
+        code: Some(hex::decode(SLOAD_CODE).unwrap()),
+        storage: vec![([0; 32], u256_to_arr(&U256::from(42)))],
+    });
+
+    let status = view(&mut runner, args.try_to_vec().unwrap());
+    assert_eq!(
+        status,
+        TransactionStatus::Succeed(u256_to_arr(&U256::from(42)).to_vec())
+    );
+
+    // The overrides are not saved
+    let args = ViewCallArgs {
+        sender: [2; 20],
+        address: contract.0,
+        amount: [0; 32],
+        input: Vec::new(),
+    };
+    let status = view(&mut runner, args.try_to_vec().unwrap());
+    assert_eq!(status, TransactionStatus::Succeed(Vec::new()));
+}
+
+#[test]
+fn test_override_balance() {
+    let mut runner = test_utils::deploy_evm();
+    let sender = Address([2; 20]);
+    runner.create_address(sender, Wei::new_u64(1_000), U256::zero());
+    let contract = Address([1; 20]);
+    let mut args = view_args(sender, contract);
+    args.state_override.push(StateOverride {
+        address: contract.0,
+        balance: None,
+        nonce: None,
+        code: Some(hex::decode(CALLER_BALANCE_CODE).unwrap()),
+        storage: Vec::new(),
+    });
+    args.state_override.push(StateOverride {
+        address: sender.0,
+        balance: Some(u256_to_arr(&U256::from(5))),
+        nonce: None,
+        code: None,
+        storage: Vec::new(),
+    });
+
+    let status = view(&mut runner, args.try_to_vec().unwrap());
+    assert_eq!(
+        status,
+        TransactionStatus::Succeed(u256_to_arr(&U256::from(5)).to_vec())
+    );
+}
+
+#[test]
+fn test_override_nonce() {
+    let mut runner = test_utils::deploy_evm();
+    let contract = Address([1; 20]);
+    runner.create_address(contract, Wei::zero(), U256::from(3));
+    let mut args = view_args(Address([2; 20]), contract);
+    args.state_override.push(StateOverride {
+        address: contract.0,
+        balance: None,
+        nonce: Some(u256_to_arr(&U256::from(5))),
+        code: Some(hex::decode(CREATE_CODE).unwrap()),
+        storage: Vec::new(),
+    });
+
+    let status = view(&mut runner, args.try_to_vec().unwrap());
+    let mut expected = [0u8; 32];
+    expected[12..].copy_from_slice(create_address(contract, 5).as_bytes());
+    assert_eq!(status, TransactionStatus::Succeed(expected.to_vec()));
+}
+
+fn view_args(sender: Address, contract: Address) -> ViewCallArgsV2 {
+    ViewCallArgsV2 {
+        sender: sender.0,
+        address: contract.0,
+        amount: [0; 32],
+        input: Vec::new(),
+        state_override: Vec::new(),
+    }
+}
+
+fn view(runner: &mut test_utils::AuroraRunner, input: Vec<u8>) -> TransactionStatus {
+    runner.view_borsh("view", input)
+}
+
+fn create_address(creator: Address, nonce: u64) -> Address {
+    let mut stream = RlpStream::new_list(2);
+    stream.append(&creator);
+    stream.append(&nonce);
+    Address::from_slice(&Keccak256::digest(&stream.out())[12..])
+}
@@ -0,0 +1,135 @@
+use crate::parameters::{FunctionCallArgs, SubmitResult, TransactionStatus};
+use crate::prelude::{Address, U256};
+use crate::test_utils::{self, INITIAL_BALANCE};
+use crate::types::{near_account_to_evm_address, u256_to_arr, Wei, STORAGE_PRICE_PER_BYTE};
+use borsh::{BorshDeserialize, BorshSerialize};
+
+/// Init code `PUSH1 0x2a PUSH1 0x00 SSTORE PUSH5 0x6000600055 PUSH1 0x00 MSTORE PUSH1 0x05
+/// PUSH1 0x1b RETURN`, i.e. a contract which stores a value in its slot 0, with runtime code
+/// `PUSH1 0x00 PUSH1 0x00 SSTORE` clearing it.
+const CLEAR_SLOT_INIT_CODE: &str = "602a6000556460006000556000526005601bf3";
+
+/// Init code `PUSH2 0x33ff PUSH1 0x00 MSTORE PUSH2 0x03e8 PUSH1 0x1e RETURN`, i.e. a contract
+/// whose 1000 bytes of code start with `CALLER SELFDESTRUCT`, so that calling it frees more
+/// storage than the call uses (e.g. for its receipt).
+const LARGE_SELFDESTRUCT_INIT_CODE: &str = "6133ff6000526103e8601ef3";
+
+/// Enough for the storage payment reserved from the gas limit of the runner's transactions.
+const SENDER_BALANCE: Wei = Wei::new_u64(u64::MAX);
+
+#[test]
+fn test_charge_storage_to_sender() {
+    let mut runner = test_utils::deploy_evm();
+    let mut signer = test_utils::Signer::random();
+    let sender = test_utils::address_from_secret_key(&signer.secret_key);
+    runner.create_address(sender, SENDER_BALANCE, U256::zero());
+    let engine_address = near_account_to_evm_address(runner.aurora_account_id.as_bytes());
+    set_storage_byte_price(&mut runner, Some(U256::one()));
+
+    // The sender pays for the storage of the deployed contract, and gets back the rest of
+    // the payment reserved
+    let contract = runner.deploy(&mut signer, LARGE_SELFDESTRUCT_INIT_CODE);
+    let balance = runner.get_balance(sender);
+    assert!(balance < SENDER_BALANCE);
+    assert_eq!(runner.get_balance(engine_address), SENDER_BALANCE - balance);
+
+    // And is refunded for the storage freed when the contract is destroyed
+    let result = runner
+        .submit_with_signer(&mut signer, |nonce| {
+            test_utils::transfer(contract, Wei::zero(), nonce)
+        })
+        .unwrap();
+    test_utils::panic_on_fail(result.status);
+    let refunded_balance = runner.get_balance(sender);
+    assert!(refunded_balance > balance);
+    assert_eq!(
+        runner.get_balance(engine_address),
+        SENDER_BALANCE - refunded_balance
+    );
+}
+
+#[test]
+fn test_storage_out_of_fund() {
+    let (mut runner, mut signer) = test_utils::deploy_evm_with_signer();
+    let sender = test_utils::address_from_secret_key(&signer.secret_key);
+    set_storage_byte_price(&mut runner, Some(U256::one()));
+
+    // The sender cannot pay for the storage reserved, so the transaction fails without being
+    // executed, but its nonce is used
+    let result = runner
+        .submit_with_signer(&mut signer, |nonce| {
+            test_utils::create_transaction(CLEAR_SLOT_INIT_CODE, nonce)
+        })
+        .unwrap();
+    assert_eq!(result.status, TransactionStatus::OutOfFund);
+    assert_eq!(runner.get_nonce(sender), U256::one());
+    assert_eq!(runner.get_balance(sender), INITIAL_BALANCE);
+}
+
+#[test]
+fn test_charge_storage_to_deposit() {
+    let mut runner = test_utils::deploy_evm();
+    set_storage_byte_price(&mut runner, Some(U256::one()));
+    let input = hex::decode(CLEAR_SLOT_INIT_CODE).unwrap();
+
+    let (_, maybe_error) = runner.call("deploy_code", "caller".to_string(), input.clone());
+    let error = format!("{:?}", maybe_error.unwrap());
+    assert!(error.contains("ERR_NOT_ENOUGH_STORAGE_DEPOSIT"));
+
+    runner.context.attached_deposit = 10u128.pow(24);
+    let (_, maybe_error) = runner.call("deploy_code", "caller".to_string(), input.clone());
+    assert!(maybe_error.is_none());
+
+    // The storage freed is refunded to a caller who paid for storage before
+    let large_input = hex::decode(LARGE_SELFDESTRUCT_INIT_CODE).unwrap();
+    let (outcome, maybe_error) =
+        runner.call("deploy_code", "caller".to_string(), large_input.clone());
+    assert!(maybe_error.is_none());
+    let contract = created_address(&outcome.unwrap().return_data.as_value().unwrap());
+    let (outcome, maybe_error) = runner.call("deploy_code", "caller".to_string(), large_input);
+    assert!(maybe_error.is_none());
+    let other_contract = created_address(&outcome.unwrap().return_data.as_value().unwrap());
+    runner.context.attached_deposit = 0;
+    let storage_usage_before_call = runner.context.storage_usage;
+    let args = FunctionCallArgs {
+        contract: contract.0,
+        input: Vec::new(),
+    };
+    let (outcome, maybe_error) =
+        runner.call("call", "caller".to_string(), args.try_to_vec().unwrap());
+    assert!(maybe_error.is_none());
+    let outcome = outcome.unwrap();
+    assert!(outcome.storage_usage < storage_usage_before_call);
+    assert_eq!(
+        runner.context.account_balance - outcome.balance,
+        u128::from(storage_usage_before_call - outcome.storage_usage) * STORAGE_PRICE_PER_BYTE
+    );
+
+    // But not to a caller who never paid for storage
+    let storage_usage_before_call = runner.context.storage_usage;
+    let args = FunctionCallArgs {
+        contract: other_contract.0,
+        input: Vec::new(),
+    };
+    let (outcome, maybe_error) =
+        runner.call("call", "other".to_string(), args.try_to_vec().unwrap());
+    assert!(maybe_error.is_none());
+    let outcome = outcome.unwrap();
+    assert!(outcome.storage_usage < storage_usage_before_call);
+    assert_eq!(outcome.balance, runner.context.account_balance);
+
+    // Storage is not charged once the price is removed
+    set_storage_byte_price(&mut runner, None);
+    let (_, maybe_error) = runner.call("deploy_code", "caller".to_string(), input);
+    assert!(maybe_error.is_none());
+}
+
+fn created_address(output: &[u8]) -> Address {
+    let result = SubmitResult::try_from_slice(output).unwrap();
+    Address::from_slice(&test_utils::unwrap_success(result))
+}
+
+fn set_storage_byte_price(runner: &mut test_utils::AuroraRunner, byte_price: Option<U256>) {
+    let args = byte_price.map(|byte_price| u256_to_arr(&byte_price));
+    runner.call_as_owner("set_storage_byte_price", args.try_to_vec().unwrap());
+}
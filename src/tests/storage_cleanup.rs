@@ -0,0 +1,107 @@
+use crate::engine::Account;
+use crate::parameters::{CleanupStorageArgs, CleanupStorageResult};
+use crate::prelude::{Address, H256};
+use crate::storage::{self, bytes_to_key, KeyPrefix};
+use crate::test_utils;
+use borsh::{BorshDeserialize, BorshSerialize};
+
+/// Init code `PUSH1 0x2a PUSH1 0x00 SSTORE PUSH1 0x2b PUSH1 0x01 SSTORE PUSH2 0x33ff PUSH1 0x00
+/// MSTORE PUSH1 0x02 PUSH1 0x1e RETURN`, i.e. a contract which stores values in its slots 0
+/// and 1, with runtime code `CALLER SELFDESTRUCT`.
+const SELFDESTRUCT_INIT_CODE: &str = "602a600055602b6001556133ff6000526002601ef3";
+
+/// Init code which deploys a contract with runtime code
+/// `PUSH1 0x00 SLOAD ISZERO PUSH1 0x00 SSTORE STOP`, i.e. a contract which toggles its slot 0
+/// between 0 and 1.
+const TOGGLE_INIT_CODE: &str = "67600054156000550060005260086018f3";
+
+#[test]
+fn test_cleanup_storage() {
+    let (mut runner, mut signer) = test_utils::deploy_evm_with_signer();
+    let contract = runner.deploy(&mut signer, SELFDESTRUCT_INIT_CODE);
+    let generation = generation(&runner, &contract);
+    let slot_keys: Vec<Vec<u8>> = [H256::zero(), H256::from_low_u64_be(1)]
+        .iter()
+        .map(|key| {
+            storage::storage_to_key(&contract, key, generation)
+                .as_ref()
+                .to_vec()
+        })
+        .collect();
+    assert!(slot_keys
+        .iter()
+        .all(|key| runner.ext.fake_trie.contains_key(key)));
+
+    // Nothing to remove while the contract exists
+    assert_eq!(cleanup_storage(&mut runner, 10).removed_slots, 0);
+
+    let result = runner.call_contract(&mut signer, contract, Vec::new());
+    test_utils::panic_on_fail(result.status);
+
+    // There is no reward by default
+    let result = cleanup_storage(&mut runner, 1);
+    assert_eq!(result.removed_slots, 1);
+    assert_eq!(result.pending_slots, 1);
+    assert_eq!(result.pending_generations, 1);
+    assert_eq!(result.reward, 0);
+
+    runner.call_as_owner(
+        "set_storage_cleanup_reward_percent",
+        50u8.try_to_vec().unwrap(),
+    );
+
+    let result = cleanup_storage(&mut runner, 10);
+    assert_eq!(result.removed_slots, 1);
+    assert_eq!(result.pending_slots, 0);
+    assert_eq!(result.pending_generations, 0);
+    assert!(result.reward > 0);
+    assert!(slot_keys
+        .iter()
+        .all(|key| !runner.ext.fake_trie.contains_key(key)));
+}
+
+#[test]
+fn test_slot_recorded_once() {
+    let (mut runner, mut signer) = test_utils::deploy_evm_with_signer();
+    let contract = runner.deploy(&mut signer, TOGGLE_INIT_CODE);
+
+    // The slot is set, removed and set again
+    for _ in 0..3 {
+        let result = runner.call_contract(&mut signer, contract, Vec::new());
+        test_utils::panic_on_fail(result.status);
+    }
+    assert_eq!(recorded_slots(&runner, &contract), 1);
+}
+
+/// Returns the storage generation of an account.
+fn generation(runner: &test_utils::AuroraRunner, address: &Address) -> u32 {
+    let key = storage::address_to_key(KeyPrefix::Account, address);
+    let value = runner.ext.fake_trie.get(&key[..]).unwrap();
+    Account::try_from_slice(value).unwrap().generation
+}
+
+/// Returns the number of slot keys recorded for the current generation of an account.
+fn recorded_slots(runner: &test_utils::AuroraRunner, address: &Address) -> usize {
+    let generation = generation(runner, address);
+    let prefix = bytes_to_key(
+        KeyPrefix::StorageKeys,
+        &[&[0][..], address.as_bytes(), &generation.to_le_bytes()].concat(),
+    );
+    runner
+        .ext
+        .fake_trie
+        .keys()
+        .filter(|key| key.starts_with(&prefix))
+        .count()
+}
+
+fn cleanup_storage(runner: &mut test_utils::AuroraRunner, max_slots: u32) -> CleanupStorageResult {
+    let args = CleanupStorageArgs { max_slots };
+    let (outcome, maybe_error) = runner.call(
+        "cleanup_storage",
+        "cleaner".to_string(),
+        args.try_to_vec().unwrap(),
+    );
+    assert!(maybe_error.is_none());
+    CleanupStorageResult::try_from_slice(&outcome.unwrap().return_data.as_value().unwrap()).unwrap()
+}
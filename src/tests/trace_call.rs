@@ -0,0 +1,112 @@
+//! These tests need the engine to be built with the `tracing` feature.
+
+use crate::parameters::{TraceCallArgs, TracerKind, ViewCallArgs};
+use crate::prelude::{Address, H256};
+use crate::test_utils;
+use borsh::BorshSerialize;
+use serde_json::Value;
+
+/// Init code which deploys a contract with runtime code
+/// `PUSH1 0x2a PUSH1 0x00 MSTORE PUSH1 0x20 PUSH1 0x00 RETURN`, i.e. a contract returning 42.
+const CALLEE_INIT_CODE: &str = "69602a60005260206000f3600052600a6016f3";
+
+/// Init code which deploys a contract with runtime code `PUSH1 0x20 PUSH1 0x00 PUSH1 0x00
+/// PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 CALLDATALOAD GAS CALL POP PUSH1 0x20 PUSH1 0x00 RETURN`,
+/// i.e. a contract which calls the address given as input and returns the output of the call.
+const CALLER_INIT_CODE: &str = "74602060006000600060006000355af15060206000f36000526015600bf3";
+
+#[test]
+fn test_trace_call_struct_logger() {
+    let (mut runner, signer, caller, callee) = initialize();
+
+    let trace = trace_call(
+        &mut runner,
+        &signer,
+        caller,
+        callee,
+        TracerKind::StructLogger,
+    );
+
+    assert_eq!(trace["failed"], false);
+    assert_eq!(trace["returnValue"], hex::encode(H256::from_low_u64_be(42)));
+    let steps: Vec<(&str, u64)> = trace["structLogs"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|log| (log["op"].as_str().unwrap(), log["depth"].as_u64().unwrap()))
+        .collect();
+    let mut expected = vec![("PUSH1", 1); 6];
+    expected.extend_from_slice(&[("CALLDATALOAD", 1), ("GAS", 1), ("CALL", 1)]);
+    expected.extend_from_slice(&[
+        ("PUSH1", 2),
+        ("PUSH1", 2),
+        ("MSTORE", 2),
+        ("PUSH1", 2),
+        ("PUSH1", 2),
+        ("RETURN", 2),
+    ]);
+    expected.extend_from_slice(&[("POP", 1), ("PUSH1", 1), ("PUSH1", 1), ("RETURN", 1)]);
+    assert_eq!(steps, expected);
+}
+
+#[test]
+fn test_trace_call_call_tracer() {
+    let (mut runner, signer, caller, callee) = initialize();
+    let sender = test_utils::address_from_secret_key(&signer.secret_key);
+
+    let trace = trace_call(&mut runner, &signer, caller, callee, TracerKind::CallTracer);
+
+    let output = format!("0x{}", hex::encode(H256::from_low_u64_be(42)));
+    assert_eq!(trace["type"], "CALL");
+    assert_eq!(trace["from"], format!("0x{}", hex::encode(sender)));
+    assert_eq!(trace["to"], format!("0x{}", hex::encode(caller)));
+    assert_eq!(
+        trace["input"],
+        format!("0x{}", hex::encode(H256::from(callee)))
+    );
+    assert_eq!(trace["output"], output);
+    assert!(trace.get("error").is_none());
+
+    let calls = trace["calls"].as_array().unwrap();
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls[0]["type"], "CALL");
+    assert_eq!(calls[0]["from"], format!("0x{}", hex::encode(caller)));
+    assert_eq!(calls[0]["to"], format!("0x{}", hex::encode(callee)));
+    assert_eq!(calls[0]["input"], "0x");
+    assert_eq!(calls[0]["output"], output);
+    assert!(calls[0].get("calls").is_none());
+}
+
+fn initialize() -> (
+    test_utils::AuroraRunner,
+    test_utils::Signer,
+    Address,
+    Address,
+) {
+    let (mut runner, mut signer) = test_utils::deploy_evm_with_signer();
+    let callee = runner.deploy(&mut signer, CALLEE_INIT_CODE);
+    let caller = runner.deploy(&mut signer, CALLER_INIT_CODE);
+
+    (runner, signer, caller, callee)
+}
+
+/// Traces a call of `caller` asking it to call `callee`, and returns the JSON trace.
+fn trace_call(
+    runner: &mut test_utils::AuroraRunner,
+    signer: &test_utils::Signer,
+    caller: Address,
+    callee: Address,
+    tracer: TracerKind,
+) -> Value {
+    let args = TraceCallArgs {
+        call: ViewCallArgs {
+            sender: test_utils::address_from_secret_key(&signer.secret_key).0,
+            address: caller.0,
+            amount: [0; 32],
+            input: H256::from(callee).as_bytes().to_vec(),
+        },
+        tracer,
+    };
+    let trace = runner.view("trace_call", args.try_to_vec().unwrap());
+    serde_json::from_slice(&trace).unwrap()
+}
@@ -0,0 +1,42 @@
+use crate::parameters::{
+    ResultLog, StateOverride, SubmitResult, TransactionStatus, ViewCallArgsV2,
+};
+use crate::prelude::U256;
+use crate::test_utils;
+use crate::types::u256_to_arr;
+use borsh::BorshSerialize;
+
+/// Runtime code `PUSH1 0x2a PUSH1 0x00 MSTORE PUSH1 0x01 PUSH1 0x20 PUSH1 0x00 LOG1 STOP`,
+/// i.e. a contract which emits a log with topic 1 and data 42.
+const LOG_CODE: &str = "602a600052600160206000a100";
+
+#[test]
+fn test_view_result() {
+    let mut runner = test_utils::deploy_evm();
+    let args = ViewCallArgsV2 {
+        sender: [2; 20],
+        address: [1; 20],
+        amount: [0; 32],
+        input: Vec::new(),
+        state_override: vec![StateOverride {
+            address: [1; 20],
+            balance: None,
+            nonce: None,
+            code: Some(hex::decode(LOG_CODE).unwrap()),
+            storage: Vec::new(),
+        }],
+    };
+
+    let result: SubmitResult = runner.view_borsh("view_result", args.try_to_vec().unwrap());
+
+    assert_eq!(result.status, TransactionStatus::Succeed(Vec::new()));
+    // 21000 (base) + 5 * 3 (PUSH1) + 3 (MSTORE) + 3 (memory) + 1006 (LOG1 of 32 bytes)
+    assert_eq!(result.gas_used, 22_027);
+    assert_eq!(
+        result.logs,
+        vec![ResultLog {
+            topics: vec![u256_to_arr(&U256::one())],
+            data: u256_to_arr(&U256::from(42)).to_vec(),
+        }]
+    );
+}
@@ -0,0 +1,632 @@
+//! Execution tracers producing the output of geth's `structLogger` and `callTracer`.
+//!
+//! The tracers listen to the events emitted by sputnikvm when it is built with its `tracing`
+//! feature. Events come from three sources (the runtime, the gasometer and the executor),
+//! each with its own listener, so the listeners forward them to a shared tracer.
+
+use core::cell::RefCell;
+
+use evm::{CreateScheme, ExitReason};
+
+use crate::parameters::TracerKind;
+use crate::prelude::{format, Address, Rc, String, ToString, Vec, H256, U256};
+
+/// Receives the events emitted during an execution.
+pub trait Tracer {
+    fn runtime_event(&mut self, _event: evm_runtime::tracing::Event) {}
+
+    fn gasometer_event(&mut self, _event: evm_gasometer::tracing::Event) {}
+
+    fn evm_event(&mut self, _event: evm::tracing::Event) {}
+}
+
+struct RuntimeListener<T>(Rc<RefCell<T>>);
+
+impl<T: Tracer> evm_runtime::tracing::EventListener for RuntimeListener<T> {
+    fn event(&mut self, event: evm_runtime::tracing::Event) {
+        self.0.borrow_mut().runtime_event(event);
+    }
+}
+
+struct GasometerListener<T>(Rc<RefCell<T>>);
+
+impl<T: Tracer> evm_gasometer::tracing::EventListener for GasometerListener<T> {
+    fn event(&mut self, event: evm_gasometer::tracing::Event) {
+        self.0.borrow_mut().gasometer_event(event);
+    }
+}
+
+struct EvmListener<T>(Rc<RefCell<T>>);
+
+impl<T: Tracer> evm::tracing::EventListener for EvmListener<T> {
+    fn event(&mut self, event: evm::tracing::Event) {
+        self.0.borrow_mut().evm_event(event);
+    }
+}
+
+/// Runs `f` with `tracer` receiving the events it emits, then returns the tracer.
+pub fn trace<T: Tracer + 'static, R, F: FnOnce() -> R>(tracer: T, f: F) -> (T, R) {
+    let tracer = Rc::new(RefCell::new(tracer));
+    let mut runtime_listener = RuntimeListener(tracer.clone());
+    let mut gasometer_listener = GasometerListener(tracer.clone());
+    let mut evm_listener = EvmListener(tracer.clone());
+    let result = evm_runtime::tracing::using(&mut runtime_listener, || {
+        evm_gasometer::tracing::using(&mut gasometer_listener, || {
+            evm::tracing::using(&mut evm_listener, f)
+        })
+    });
+    drop((runtime_listener, gasometer_listener, evm_listener));
+    let tracer = match Rc::try_unwrap(tracer) {
+        Ok(tracer) => tracer.into_inner(),
+        Err(_) => unreachable!(),
+    };
+    (tracer, result)
+}
+
+/// Either of the tracers, as selected by a `TracerKind`.
+pub enum AnyTracer {
+    StructLogger(StructLogger),
+    CallTracer(CallTracer),
+}
+
+impl AnyTracer {
+    pub fn new(kind: TracerKind) -> Self {
+        match kind {
+            TracerKind::StructLogger => Self::StructLogger(StructLogger::default()),
+            TracerKind::CallTracer => Self::CallTracer(CallTracer::default()),
+        }
+    }
+
+    /// Returns the JSON encoded trace of an execution which ended with `reason`.
+    pub fn into_json(self, reason: &ExitReason, return_value: &[u8], gas_used: u64) -> String {
+        match self {
+            Self::StructLogger(tracer) => tracer.into_json(reason, return_value, gas_used),
+            Self::CallTracer(tracer) => tracer.into_json(gas_used),
+        }
+    }
+}
+
+impl Tracer for AnyTracer {
+    fn runtime_event(&mut self, event: evm_runtime::tracing::Event) {
+        match self {
+            Self::StructLogger(tracer) => tracer.runtime_event(event),
+            Self::CallTracer(tracer) => tracer.runtime_event(event),
+        }
+    }
+
+    fn gasometer_event(&mut self, event: evm_gasometer::tracing::Event) {
+        match self {
+            Self::StructLogger(tracer) => tracer.gasometer_event(event),
+            Self::CallTracer(tracer) => tracer.gasometer_event(event),
+        }
+    }
+
+    fn evm_event(&mut self, event: evm::tracing::Event) {
+        match self {
+            Self::StructLogger(tracer) => tracer.evm_event(event),
+            Self::CallTracer(tracer) => tracer.evm_event(event),
+        }
+    }
+}
+
+/// A step of the execution, as logged by geth's `structLogger` (without the memory).
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct StructLog {
+    pub pc: usize,
+    pub op: u8,
+    /// Gas left before executing the step.
+    pub gas: u64,
+    pub gas_cost: u64,
+    pub depth: u32,
+    pub stack: Vec<H256>,
+    /// Storage slots written by the step.
+    pub storage: Vec<(H256, H256)>,
+}
+
+/// Records every step of the execution.
+#[derive(Default)]
+pub struct StructLogger {
+    pub logs: Vec<StructLog>,
+    depth: u32,
+    /// Whether the gas of the last step is still to be recorded.
+    gas_pending: bool,
+}
+
+impl StructLogger {
+    fn record_gas(&mut self, gas: u64, gas_cost: u64) {
+        if !self.gas_pending {
+            return;
+        }
+        self.gas_pending = false;
+        if let Some(log) = self.logs.last_mut() {
+            log.gas = gas;
+            log.gas_cost = gas_cost;
+        }
+    }
+
+    pub fn into_json(self, reason: &ExitReason, return_value: &[u8], gas_used: u64) -> String {
+        let logs: Vec<String> = self.logs.iter().map(struct_log_to_json).collect();
+        format!(
+            r#"{{"gas":{},"failed":{},"returnValue":"{}","structLogs":[{}]}}"#,
+            gas_used,
+            !reason.is_succeed(),
+            hex::encode(return_value),
+            logs.join(","),
+        )
+    }
+}
+
+impl Tracer for StructLogger {
+    fn runtime_event(&mut self, event: evm_runtime::tracing::Event) {
+        use evm_runtime::tracing::Event;
+        match event {
+            Event::Step {
+                opcode,
+                position: Ok(pc),
+                stack,
+                ..
+            } => {
+                self.logs.push(StructLog {
+                    pc: *pc,
+                    op: opcode.as_u8(),
+                    depth: self.depth,
+                    stack: stack.data().clone(),
+                    ..Default::default()
+                });
+                self.gas_pending = true;
+            }
+            Event::SStore { index, value, .. } => {
+                if let Some(log) = self.logs.last_mut() {
+                    log.storage.push((index, value));
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn gasometer_event(&mut self, event: evm_gasometer::tracing::Event) {
+        use evm_gasometer::tracing::Event;
+        match event {
+            Event::RecordCost {
+                cost,
+                snapshot: Some(snapshot),
+            } => {
+                let gas = snapshot.gas_limit - snapshot.used_gas - snapshot.memory_gas;
+                self.record_gas(gas, cost);
+            }
+            Event::RecordDynamicCost {
+                gas_cost,
+                memory_gas,
+                snapshot: Some(snapshot),
+                ..
+            } => {
+                let gas = snapshot.gas_limit - snapshot.used_gas - snapshot.memory_gas;
+                let memory_cost = memory_gas.saturating_sub(snapshot.memory_gas);
+                self.record_gas(gas, gas_cost + memory_cost);
+            }
+            _ => (),
+        }
+    }
+
+    fn evm_event(&mut self, event: evm::tracing::Event) {
+        use evm::tracing::Event;
+        match event {
+            Event::Call { .. } | Event::Create { .. } => self.depth += 1,
+            Event::Exit { .. } => self.depth = self.depth.saturating_sub(1),
+            _ => (),
+        }
+    }
+}
+
+fn struct_log_to_json(log: &StructLog) -> String {
+    let stack: Vec<String> = log
+        .stack
+        .iter()
+        .map(|value| {
+            format!(
+                r#""{}""#,
+                u256_to_hex(U256::from_big_endian(value.as_bytes()))
+            )
+        })
+        .collect();
+    let storage: Vec<String> = log
+        .storage
+        .iter()
+        .map(|(index, value)| {
+            format!(
+                r#""{}":"{}""#,
+                hex::encode(index.as_bytes()),
+                hex::encode(value.as_bytes())
+            )
+        })
+        .collect();
+    format!(
+        r#"{{"pc":{},"op":"{}","gas":{},"gasCost":{},"depth":{},"stack":[{}],"storage":{{{}}}}}"#,
+        log.pc,
+        opcode_name(log.op),
+        log.gas,
+        log.gas_cost,
+        log.depth,
+        stack.join(","),
+        storage.join(","),
+    )
+}
+
+/// A call (or contract creation), as reported by geth's `callTracer`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct CallFrame {
+    pub call_type: &'static str,
+    pub from: Address,
+    pub to: Address,
+    pub value: U256,
+    /// Gas available to the call.
+    pub gas: u64,
+    pub input: Vec<u8>,
+    pub output: Vec<u8>,
+    pub error: Option<String>,
+    pub calls: Vec<CallFrame>,
+}
+
+/// Records the tree of calls made during the execution.
+///
+/// Unlike geth, the gas used is only reported for the top-level call.
+#[derive(Default)]
+pub struct CallTracer {
+    stack: Vec<CallFrame>,
+    pub root: Option<CallFrame>,
+}
+
+impl CallTracer {
+    fn enter(&mut self, frame: CallFrame) {
+        self.stack.push(frame);
+    }
+
+    fn exit(&mut self, reason: &ExitReason, return_value: &[u8]) {
+        let mut frame = match self.stack.pop() {
+            Some(frame) => frame,
+            None => return,
+        };
+        frame.output = return_value.to_vec();
+        frame.error = match reason {
+            ExitReason::Succeed(_) => None,
+            ExitReason::Revert(_) => Some("execution reverted".to_string()),
+            ExitReason::Error(e) => Some(format!("{:?}", e)),
+            ExitReason::Fatal(e) => Some(format!("{:?}", e)),
+        };
+        self.add_call(frame);
+    }
+
+    fn add_call(&mut self, frame: CallFrame) {
+        match self.stack.last_mut() {
+            Some(parent) => parent.calls.push(frame),
+            None => self.root = Some(frame),
+        }
+    }
+
+    pub fn into_json(self, gas_used: u64) -> String {
+        match self.root {
+            Some(root) => call_frame_to_json(&root, Some(gas_used)),
+            None => "{}".to_string(),
+        }
+    }
+}
+
+impl Tracer for CallTracer {
+    fn evm_event(&mut self, event: evm::tracing::Event) {
+        use evm::tracing::Event;
+        match event {
+            Event::Call {
+                code_address,
+                transfer,
+                input,
+                target_gas,
+                is_static,
+                context,
+            } => {
+                let (call_type, from) = if is_static {
+                    ("STATICCALL", context.caller)
+                } else if context.address == code_address {
+                    ("CALL", context.caller)
+                } else if transfer.is_some() {
+                    ("CALLCODE", context.caller)
+                } else {
+                    ("DELEGATECALL", context.address)
+                };
+                self.enter(CallFrame {
+                    call_type,
+                    from,
+                    to: code_address,
+                    value: context.apparent_value,
+                    gas: target_gas.unwrap_or_default(),
+                    input: input.to_vec(),
+                    output: Vec::new(),
+                    error: None,
+                    calls: Vec::new(),
+                });
+            }
+            Event::Create {
+                caller,
+                address,
+                scheme,
+                value,
+                init_code,
+                target_gas,
+            } => {
+                let call_type = match scheme {
+                    CreateScheme::Create2 { .. } => "CREATE2",
+                    _ => "CREATE",
+                };
+                self.enter(CallFrame {
+                    call_type,
+                    from: caller,
+                    to: address,
+                    value,
+                    gas: target_gas.unwrap_or_default(),
+                    input: init_code.to_vec(),
+                    output: Vec::new(),
+                    error: None,
+                    calls: Vec::new(),
+                });
+            }
+            Event::Suicide {
+                address,
+                target,
+                balance,
+            } => self.add_call(CallFrame {
+                call_type: "SELFDESTRUCT",
+                from: address,
+                to: target,
+                value: balance,
+                gas: 0,
+                input: Vec::new(),
+                output: Vec::new(),
+                error: None,
+                calls: Vec::new(),
+            }),
+            Event::Exit {
+                reason,
+                return_value,
+            } => self.exit(reason, return_value),
+            _ => (),
+        }
+    }
+}
+
+fn call_frame_to_json(frame: &CallFrame, gas_used: Option<u64>) -> String {
+    let mut json = format!(
+        r#"{{"type":"{}","from":"{}","to":"{}","value":"{}","gas":"{}","#,
+        frame.call_type,
+        bytes_to_hex(frame.from.as_bytes()),
+        bytes_to_hex(frame.to.as_bytes()),
+        u256_to_hex(frame.value),
+        u256_to_hex(U256::from(frame.gas)),
+    );
+    if let Some(gas_used) = gas_used {
+        json.push_str(&format!(
+            r#""gasUsed":"{}","#,
+            u256_to_hex(U256::from(gas_used))
+        ));
+    }
+    json.push_str(&format!(
+        r#""input":"{}","output":"{}""#,
+        bytes_to_hex(&frame.input),
+        bytes_to_hex(&frame.output),
+    ));
+    if let Some(error) = &frame.error {
+        json.push_str(&format!(r#","error":"{}""#, error));
+    }
+    if !frame.calls.is_empty() {
+        let calls: Vec<String> = frame
+            .calls
+            .iter()
+            .map(|call| call_frame_to_json(call, None))
+            .collect();
+        json.push_str(&format!(r#","calls":[{}]"#, calls.join(",")));
+    }
+    json.push('}');
+    json
+}
+
+fn bytes_to_hex(bytes: &[u8]) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
+
+fn u256_to_hex(value: U256) -> String {
+    format!("0x{:x}", value)
+}
+
+/// Returns the name geth uses for the opcode.
+pub fn opcode_name(opcode: u8) -> String {
+    const PUSH: [&str; 32] = [
+        "PUSH1", "PUSH2", "PUSH3", "PUSH4", "PUSH5", "PUSH6", "PUSH7", "PUSH8", "PUSH9", "PUSH10",
+        "PUSH11", "PUSH12", "PUSH13", "PUSH14", "PUSH15", "PUSH16", "PUSH17", "PUSH18", "PUSH19",
+        "PUSH20", "PUSH21", "PUSH22", "PUSH23", "PUSH24", "PUSH25", "PUSH26", "PUSH27", "PUSH28",
+        "PUSH29", "PUSH30", "PUSH31", "PUSH32",
+    ];
+    const DUP: [&str; 16] = [
+        "DUP1", "DUP2", "DUP3", "DUP4", "DUP5", "DUP6", "DUP7", "DUP8", "DUP9", "DUP10", "DUP11",
+        "DUP12", "DUP13", "DUP14", "DUP15", "DUP16",
+    ];
+    const SWAP: [&str; 16] = [
+        "SWAP1", "SWAP2", "SWAP3", "SWAP4", "SWAP5", "SWAP6", "SWAP7", "SWAP8", "SWAP9", "SWAP10",
+        "SWAP11", "SWAP12", "SWAP13", "SWAP14", "SWAP15", "SWAP16",
+    ];
+    const LOG: [&str; 5] = ["LOG0", "LOG1", "LOG2", "LOG3", "LOG4"];
+
+    let name = match opcode {
+        0x00 => "STOP",
+        0x01 => "ADD",
+        0x02 => "MUL",
+        0x03 => "SUB",
+        0x04 => "DIV",
+        0x05 => "SDIV",
+        0x06 => "MOD",
+        0x07 => "SMOD",
+        0x08 => "ADDMOD",
+        0x09 => "MULMOD",
+        0x0a => "EXP",
+        0x0b => "SIGNEXTEND",
+        0x10 => "LT",
+        0x11 => "GT",
+        0x12 => "SLT",
+        0x13 => "SGT",
+        0x14 => "EQ",
+        0x15 => "ISZERO",
+        0x16 => "AND",
+        0x17 => "OR",
+        0x18 => "XOR",
+        0x19 => "NOT",
+        0x1a => "BYTE",
+        0x1b => "SHL",
+        0x1c => "SHR",
+        0x1d => "SAR",
+        0x20 => "SHA3",
+        0x30 => "ADDRESS",
+        0x31 => "BALANCE",
+        0x32 => "ORIGIN",
+        0x33 => "CALLER",
+        0x34 => "CALLVALUE",
+        0x35 => "CALLDATALOAD",
+        0x36 => "CALLDATASIZE",
+        0x37 => "CALLDATACOPY",
+        0x38 => "CODESIZE",
+        0x39 => "CODECOPY",
+        0x3a => "GASPRICE",
+        0x3b => "EXTCODESIZE",
+        0x3c => "EXTCODECOPY",
+        0x3d => "RETURNDATASIZE",
+        0x3e => "RETURNDATACOPY",
+        0x3f => "EXTCODEHASH",
+        0x40 => "BLOCKHASH",
+        0x41 => "COINBASE",
+        0x42 => "TIMESTAMP",
+        0x43 => "NUMBER",
+        0x44 => "DIFFICULTY",
+        0x45 => "GASLIMIT",
+        0x46 => "CHAINID",
+        0x47 => "SELFBALANCE",
+        0x48 => "BASEFEE",
+        0x50 => "POP",
+        0x51 => "MLOAD",
+        0x52 => "MSTORE",
+        0x53 => "MSTORE8",
+        0x54 => "SLOAD",
+        0x55 => "SSTORE",
+        0x56 => "JUMP",
+        0x57 => "JUMPI",
+        0x58 => "PC",
+        0x59 => "MSIZE",
+        0x5a => "GAS",
+        0x5b => "JUMPDEST",
+        0x60..=0x7f => PUSH[(opcode - 0x60) as usize],
+        0x80..=0x8f => DUP[(opcode - 0x80) as usize],
+        0x90..=0x9f => SWAP[(opcode - 0x90) as usize],
+        0xa0..=0xa4 => LOG[(opcode - 0xa0) as usize],
+        0xf0 => "CREATE",
+        0xf1 => "CALL",
+        0xf2 => "CALLCODE",
+        0xf3 => "RETURN",
+        0xf4 => "DELEGATECALL",
+        0xf5 => "CREATE2",
+        0xfa => "STATICCALL",
+        0xfd => "REVERT",
+        0xfe => "INVALID",
+        0xff => "SELFDESTRUCT",
+        _ => return format!("opcode {:#04x} not defined", opcode),
+    };
+    name.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use evm::{Context, ExitRevert, ExitSucceed};
+
+    #[test]
+    fn test_opcode_name() {
+        assert_eq!(opcode_name(0x00), "STOP");
+        assert_eq!(opcode_name(0x7f), "PUSH32");
+        assert_eq!(opcode_name(0x8f), "DUP16");
+        assert_eq!(opcode_name(0xa4), "LOG4");
+        assert_eq!(opcode_name(0x0c), "opcode 0x0c not defined");
+    }
+
+    #[test]
+    fn test_call_tracer() {
+        let caller = Address([1; 20]);
+        let contract = Address([2; 20]);
+        let callee = Address([3; 20]);
+        let mut tracer = CallTracer::default();
+
+        let context = Context {
+            address: contract,
+            caller,
+            apparent_value: U256::zero(),
+        };
+        tracer.evm_event(evm::tracing::Event::Call {
+            code_address: contract,
+            transfer: &None,
+            input: &[0xab],
+            target_gas: Some(100_000),
+            is_static: false,
+            context: &context,
+        });
+        let inner_context = Context {
+            address: callee,
+            caller: contract,
+            apparent_value: U256::zero(),
+        };
+        tracer.evm_event(evm::tracing::Event::Call {
+            code_address: callee,
+            transfer: &None,
+            input: &[],
+            target_gas: Some(50_000),
+            is_static: true,
+            context: &inner_context,
+        });
+        tracer.evm_event(evm::tracing::Event::Exit {
+            reason: &ExitReason::Revert(ExitRevert::Reverted),
+            return_value: &[],
+        });
+        tracer.evm_event(evm::tracing::Event::Exit {
+            reason: &ExitReason::Succeed(ExitSucceed::Returned),
+            return_value: &[0xcd],
+        });
+
+        assert_eq!(
+            tracer.into_json(21_000),
+            concat!(
+                r#"{"type":"CALL","from":"0x0101010101010101010101010101010101010101","#,
+                r#""to":"0x0202020202020202020202020202020202020202","value":"0x0","#,
+                r#""gas":"0x186a0","gasUsed":"0x5208","input":"0xab","output":"0xcd","#,
+                r#""calls":[{"type":"STATICCALL","#,
+                r#""from":"0x0202020202020202020202020202020202020202","#,
+                r#""to":"0x0303030303030303030303030303030303030303","value":"0x0","#,
+                r#""gas":"0xc350","input":"0x","output":"0x","error":"execution reverted"}]}"#,
+            )
+        );
+    }
+
+    #[test]
+    fn test_struct_log_to_json() {
+        let log = StructLog {
+            pc: 2,
+            op: 0x55,
+            gas: 79_000,
+            gas_cost: 22_100,
+            depth: 1,
+            stack: vec![H256::from_low_u64_be(1), H256::zero()],
+            storage: vec![(H256::zero(), H256::from_low_u64_be(1))],
+        };
+
+        assert_eq!(
+            struct_log_to_json(&log),
+            concat!(
+                r#"{"pc":2,"op":"SSTORE","gas":79000,"gasCost":22100,"depth":1,"#,
+                r#""stack":["0x1","0x0"],"storage":{"#,
+                r#""0000000000000000000000000000000000000000000000000000000000000000":"#,
+                r#""0000000000000000000000000000000000000000000000000000000000000001"}}"#,
+            )
+        );
+    }
+}
@@ -0,0 +1,173 @@
+use crate::prelude::{Address, Vec, U256};
+use crate::transaction::access_list::AccessTuple;
+use crate::types::Wei;
+use rlp::{Decodable, DecoderError, Encodable, Rlp, RlpStream};
+
+/// Type indicator (per EIP-2718) for dynamic fee transactions
+pub const TYPE_BYTE: u8 = 0x02;
+
+/// See https://eips.ethereum.org/EIPS/eip-1559
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct DynamicFeeEthTransaction {
+    pub chain_id: u64,
+    pub nonce: U256,
+    /// The maximum fee per unit of gas the sender is willing to give to the relayer
+    pub max_priority_fee_per_gas: U256,
+    /// The maximum total fee per unit of gas the sender is willing to pay
+    pub max_fee_per_gas: U256,
+    pub gas_limit: U256,
+    pub to: Option<Address>,
+    pub value: Wei,
+    pub data: Vec<u8>,
+    pub access_list: Vec<AccessTuple>,
+}
+
+impl DynamicFeeEthTransaction {
+    /// RLP encoding of the data for an unsigned message (used to make signature)
+    pub fn rlp_append_unsigned(&self, s: &mut RlpStream) {
+        self.rlp_append(s, 9);
+    }
+
+    /// RLP encoding for a signed message (used to encode the transaction for sending to tx pool)
+    pub fn rlp_append_signed(&self, s: &mut RlpStream) {
+        self.rlp_append(s, 12);
+    }
+
+    #[inline]
+    pub fn intrinsic_gas(&self, config: &evm::Config) -> Option<u64> {
+        super::intrinsic_gas(self.to.is_none(), &self.data, &self.access_list, config)
+    }
+
+    /// Returns the price per unit of gas actually paid by the sender given the current
+    /// base fee, i.e. `min(max_fee_per_gas, base_fee_per_gas + max_priority_fee_per_gas)`.
+    /// Returns `None` if the transaction cannot cover the base fee.
+    pub fn effective_gas_price(&self, base_fee_per_gas: U256) -> Option<U256> {
+        if self.max_fee_per_gas < base_fee_per_gas {
+            return None;
+        }
+        let priority_fee_per_gas = self
+            .max_priority_fee_per_gas
+            .min(self.max_fee_per_gas - base_fee_per_gas);
+        Some(base_fee_per_gas + priority_fee_per_gas)
+    }
+
+    fn rlp_append(&self, s: &mut RlpStream, list_len: usize) {
+        s.begin_list(list_len);
+        s.append(&self.chain_id);
+        s.append(&self.nonce);
+        s.append(&self.max_priority_fee_per_gas);
+        s.append(&self.max_fee_per_gas);
+        s.append(&self.gas_limit);
+        match self.to.as_ref() {
+            None => s.append(&""),
+            Some(address) => s.append(address),
+        };
+        s.append(&self.value.raw());
+        s.append(&self.data);
+        s.begin_list(self.access_list.len());
+        for tuple in self.access_list.iter() {
+            s.begin_list(2);
+            s.append(&tuple.address);
+            s.begin_list(tuple.storage_keys.len());
+            for key in tuple.storage_keys.iter() {
+                s.append(key);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct DynamicFeeEthSignedTransaction {
+    pub transaction_data: DynamicFeeEthTransaction,
+    /// The parity (0 for even, 1 for odd) of the y-value of a secp256k1 signature.
+    pub parity: u8,
+    pub r: U256,
+    pub s: U256,
+}
+
+impl DynamicFeeEthSignedTransaction {
+    pub fn sender(&self) -> Option<Address> {
+        let mut rlp_stream = RlpStream::new();
+        rlp_stream.append(&TYPE_BYTE);
+        self.transaction_data.rlp_append_unsigned(&mut rlp_stream);
+        let message_hash = crate::types::keccak(rlp_stream.as_raw());
+        crate::precompiles::ecrecover(
+            message_hash,
+            &super::vrs_to_arr(self.parity, self.r, self.s),
+        )
+        .ok()
+    }
+}
+
+impl Encodable for DynamicFeeEthSignedTransaction {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        self.transaction_data.rlp_append_signed(s);
+        s.append(&self.parity);
+        s.append(&self.r);
+        s.append(&self.s);
+    }
+}
+
+impl Decodable for DynamicFeeEthSignedTransaction {
+    fn decode(rlp: &Rlp<'_>) -> Result<Self, DecoderError> {
+        if rlp.item_count() != Ok(12) {
+            return Err(rlp::DecoderError::RlpIncorrectListLen);
+        }
+        let chain_id = rlp.val_at(0)?;
+        let nonce = rlp.val_at(1)?;
+        let max_priority_fee_per_gas = rlp.val_at(2)?;
+        let max_fee_per_gas = rlp.val_at(3)?;
+        let gas_limit = rlp.val_at(4)?;
+        let to = super::rlp_extract_to(rlp, 5)?;
+        let value = Wei::new(rlp.val_at(6)?);
+        let data = rlp.val_at(7)?;
+        let access_list = rlp.list_at(8)?;
+        let parity = rlp.val_at(9)?;
+        let r = rlp.val_at(10)?;
+        let s = rlp.val_at(11)?;
+        Ok(Self {
+            transaction_data: DynamicFeeEthTransaction {
+                chain_id,
+                nonce,
+                max_priority_fee_per_gas,
+                max_fee_per_gas,
+                gas_limit,
+                to,
+                value,
+                data,
+                access_list,
+            },
+            parity,
+            r,
+            s,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_effective_gas_price() {
+        let mut tx = DynamicFeeEthTransaction {
+            chain_id: 1,
+            nonce: U256::zero(),
+            max_priority_fee_per_gas: U256::from(2),
+            max_fee_per_gas: U256::from(10),
+            gas_limit: U256::from(21_000),
+            to: None,
+            value: Wei::zero(),
+            data: Vec::new(),
+            access_list: Vec::new(),
+        };
+
+        assert_eq!(tx.effective_gas_price(U256::zero()), Some(U256::from(2)));
+        assert_eq!(tx.effective_gas_price(U256::from(7)), Some(U256::from(9)));
+        assert_eq!(tx.effective_gas_price(U256::from(9)), Some(U256::from(10)));
+        assert_eq!(tx.effective_gas_price(U256::from(11)), None);
+
+        tx.max_priority_fee_per_gas = U256::from(20);
+        assert_eq!(tx.effective_gas_price(U256::zero()), Some(U256::from(10)));
+    }
+}
@@ -2,6 +2,7 @@ use crate::prelude::{Address, TryFrom, Vec, U256};
 use rlp::{Decodable, DecoderError, Rlp};
 
 pub(crate) mod access_list;
+pub(crate) mod dynamic_fee;
 pub(crate) mod legacy;
 
 use access_list::AccessTuple;
@@ -12,6 +13,7 @@ pub use legacy::{LegacyEthSignedTransaction, LegacyEthTransaction};
 pub enum EthTransaction {
     Legacy(LegacyEthSignedTransaction),
     AccessList(access_list::AccessListEthSignedTransaction),
+    DynamicFee(dynamic_fee::DynamicFeeEthSignedTransaction),
 }
 
 impl EthTransaction {
@@ -19,6 +21,16 @@ impl EthTransaction {
         match self {
             Self::Legacy(tx) => tx.chain_id(),
             Self::AccessList(tx) => Some(tx.transaction_data.chain_id),
+            Self::DynamicFee(tx) => Some(tx.transaction_data.chain_id),
+        }
+    }
+
+    /// Returns the type of the transaction (see EIP-2718), or `None` for a legacy transaction.
+    pub fn transaction_type(&self) -> Option<u8> {
+        match self {
+            Self::Legacy(_) => None,
+            Self::AccessList(_) => Some(access_list::TYPE_BYTE),
+            Self::DynamicFee(_) => Some(dynamic_fee::TYPE_BYTE),
         }
     }
 
@@ -26,6 +38,7 @@ impl EthTransaction {
         match self {
             Self::Legacy(tx) => tx.sender(),
             Self::AccessList(tx) => tx.sender(),
+            Self::DynamicFee(tx) => tx.sender(),
         }
     }
 
@@ -33,6 +46,7 @@ impl EthTransaction {
         match self {
             Self::Legacy(tx) => &tx.transaction.nonce,
             Self::AccessList(tx) => &tx.transaction_data.nonce,
+            Self::DynamicFee(tx) => &tx.transaction_data.nonce,
         }
     }
 
@@ -40,6 +54,7 @@ impl EthTransaction {
         match self {
             Self::Legacy(tx) => tx.transaction.intrinsic_gas(config),
             Self::AccessList(tx) => tx.transaction_data.intrinsic_gas(config),
+            Self::DynamicFee(tx) => tx.transaction_data.intrinsic_gas(config),
         }
     }
 
@@ -47,13 +62,25 @@ impl EthTransaction {
         match self {
             Self::Legacy(tx) => tx.transaction.gas,
             Self::AccessList(tx) => tx.transaction_data.gas_limit,
+            Self::DynamicFee(tx) => tx.transaction_data.gas_limit,
         }
     }
 
-    pub fn gas_price(&self) -> U256 {
-        match self {
+    /// Returns the price per unit of gas the sender pays given the block's base fee
+    /// (see EIP-1559). For transactions with a fixed gas price this is the gas price itself.
+    /// Returns `None` if the transaction does not cover the base fee.
+    pub fn effective_gas_price(&self, base_fee_per_gas: U256) -> Option<U256> {
+        let gas_price = match self {
             Self::Legacy(tx) => tx.transaction.gas_price,
             Self::AccessList(tx) => tx.transaction_data.gas_price,
+            Self::DynamicFee(tx) => {
+                return tx.transaction_data.effective_gas_price(base_fee_per_gas)
+            }
+        };
+        if gas_price < base_fee_per_gas {
+            None
+        } else {
+            Some(gas_price)
         }
     }
 
@@ -82,6 +109,16 @@ impl EthTransaction {
                     tx.access_list,
                 )
             }
+            Self::DynamicFee(tx) => {
+                let tx = tx.transaction_data;
+                (
+                    tx.value,
+                    tx.gas_limit.try_into().ok(),
+                    tx.data,
+                    tx.to,
+                    tx.access_list,
+                )
+            }
         }
     }
 }
@@ -94,6 +131,14 @@ impl TryFrom<&[u8]> for EthTransaction {
             let access_list_tx =
                 access_list::AccessListEthSignedTransaction::decode(&Rlp::new(&bytes[1..]))?;
             Ok(Self::AccessList(access_list_tx))
+        } else if bytes[0] == dynamic_fee::TYPE_BYTE {
+            let dynamic_fee_tx =
+                dynamic_fee::DynamicFeeEthSignedTransaction::decode(&Rlp::new(&bytes[1..]))?;
+            let tx_data = &dynamic_fee_tx.transaction_data;
+            if tx_data.max_priority_fee_per_gas > tx_data.max_fee_per_gas {
+                return Err(ParseTransactionError::PriorityFeeGreaterThanMaxFee);
+            }
+            Ok(Self::DynamicFee(dynamic_fee_tx))
         } else if bytes[0] <= 0x7f {
             Err(ParseTransactionError::UnknownTransactionType)
         } else if bytes[0] == 0xff {
@@ -109,6 +154,8 @@ pub enum ParseTransactionError {
     UnknownTransactionType,
     // Per the EIP-2718 spec 0xff is a reserved value
     ReservedSentinel,
+    // Per the EIP-1559 spec the priority fee can never exceed the max fee
+    PriorityFeeGreaterThanMaxFee,
     RlpDecodeError(DecoderError),
 }
 
@@ -123,6 +170,7 @@ impl AsRef<[u8]> for ParseTransactionError {
         match self {
             Self::UnknownTransactionType => b"ERR_UNKNOWN_TX_TYPE",
             Self::ReservedSentinel => b"ERR_RESERVED_LEADING_TX_BYTE",
+            Self::PriorityFeeGreaterThanMaxFee => b"ERR_MAX_PRIORITY_FEE_GREATER",
             Self::RlpDecodeError(_) => b"ERR_TX_RLP_DECODE",
         }
     }
@@ -0,0 +1,690 @@
+//! Merkle Patricia tries, as specified in the yellow paper.
+//!
+//! Tries are persistent: their nodes are kept in a `NodeStore` by hash, and updating a trie
+//! returns the root of the updated trie. Nodes are counted by the number of references to
+//! them, from other nodes and from the holders of roots, and are removed from the store once
+//! they are no longer referenced. Updating a trie moves the reference held to its root to the
+//! updated root.
+use rlp::{Rlp, RlpStream};
+
+use crate::prelude::{vec, BTreeMap, Vec, H256};
+use crate::sdk;
+use crate::storage::{bytes_to_key, KeyPrefix};
+use crate::types::keccak;
+
+/// RLP encoding of the empty string, which stands for the absence of a node.
+const EMPTY_STRING_CODE: u8 = 0x80;
+
+/// Root of the empty trie, the hash of `EMPTY_STRING_CODE`.
+pub const EMPTY_TRIE_ROOT: H256 = H256([
+    0x56, 0xe8, 0x1f, 0x17, 0x1b, 0xcc, 0x55, 0xa6, 0xff, 0x83, 0x45, 0xe6, 0x92, 0xc0, 0xf8, 0x6e,
+    0x5b, 0x48, 0xe0, 0x1b, 0x99, 0x6c, 0xad, 0xc0, 0x01, 0x62, 0x2f, 0xb5, 0xe3, 0x63, 0xb4, 0x21,
+]);
+
+/// Read access to the nodes of tries, by hash.
+pub trait NodeStore {
+    fn get_node(&self, hash: &H256) -> Option<Vec<u8>>;
+}
+
+/// Storage of the nodes of tries, by hash, along with the number of references to them.
+pub trait NodeStoreMut: NodeStore {
+    fn has_node(&self, hash: &H256) -> bool {
+        self.get_node(hash).is_some()
+    }
+
+    fn put_node(&mut self, hash: &H256, node: &[u8]);
+
+    fn remove_node(&mut self, hash: &H256);
+
+    fn get_refcount(&self, hash: &H256) -> u64;
+
+    fn set_refcount(&mut self, hash: &H256, refcount: u64);
+}
+
+/// Nodes given by a proof, for looking up the values it proves.
+impl NodeStore for BTreeMap<H256, Vec<u8>> {
+    fn get_node(&self, hash: &H256) -> Option<Vec<u8>> {
+        self.get(hash).cloned()
+    }
+}
+
+/// Nodes kept in memory.
+#[derive(Default)]
+pub struct MemoryNodeStore {
+    nodes: BTreeMap<H256, (Vec<u8>, u64)>,
+}
+
+impl MemoryNodeStore {
+    /// Returns the number of nodes in the store.
+    #[allow(dead_code)]
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+}
+
+impl NodeStore for MemoryNodeStore {
+    fn get_node(&self, hash: &H256) -> Option<Vec<u8>> {
+        self.nodes.get(hash).map(|(node, _)| node.clone())
+    }
+}
+
+impl NodeStoreMut for MemoryNodeStore {
+    fn put_node(&mut self, hash: &H256, node: &[u8]) {
+        self.nodes.insert(*hash, (node.to_vec(), 0));
+    }
+
+    fn remove_node(&mut self, hash: &H256) {
+        self.nodes.remove(hash);
+    }
+
+    fn get_refcount(&self, hash: &H256) -> u64 {
+        self.nodes
+            .get(hash)
+            .map(|(_, refcount)| *refcount)
+            .unwrap_or(0)
+    }
+
+    fn set_refcount(&mut self, hash: &H256, refcount: u64) {
+        if let Some(entry) = self.nodes.get_mut(hash) {
+            entry.1 = refcount;
+        }
+    }
+}
+
+/// Nodes kept in the contract storage, under keys made of a `KeyPrefix` and of one of two ids,
+/// for the nodes and for their reference counts, followed by the hash of the node.
+pub struct StorageNodeStore {
+    pub prefix: KeyPrefix,
+    pub node_id: u8,
+    pub refcount_id: u8,
+}
+
+impl StorageNodeStore {
+    fn key(&self, id: u8, hash: &H256) -> Vec<u8> {
+        bytes_to_key(self.prefix, &[&[id], hash.as_bytes()].concat())
+    }
+}
+
+impl NodeStore for StorageNodeStore {
+    fn get_node(&self, hash: &H256) -> Option<Vec<u8>> {
+        sdk::read_storage(&self.key(self.node_id, hash))
+    }
+}
+
+impl NodeStoreMut for StorageNodeStore {
+    fn has_node(&self, hash: &H256) -> bool {
+        sdk::storage_has_key(&self.key(self.node_id, hash))
+    }
+
+    fn put_node(&mut self, hash: &H256, node: &[u8]) {
+        sdk::write_storage(&self.key(self.node_id, hash), node);
+    }
+
+    fn remove_node(&mut self, hash: &H256) {
+        sdk::remove_storage(&self.key(self.node_id, hash));
+        sdk::remove_storage(&self.key(self.refcount_id, hash));
+    }
+
+    fn get_refcount(&self, hash: &H256) -> u64 {
+        sdk::read_u64(&self.key(self.refcount_id, hash)).unwrap_or(0)
+    }
+
+    fn set_refcount(&mut self, hash: &H256, refcount: u64) {
+        sdk::write_storage(&self.key(self.refcount_id, hash), &refcount.to_le_bytes());
+    }
+}
+
+/// Reference to a node from its parent: nodes shorter than a hash are embedded in their parent.
+#[derive(Clone, Debug)]
+enum Reference {
+    Hash(H256),
+    Inline(Vec<u8>),
+}
+
+/// Paths are sequences of nibbles.
+#[derive(Clone, Debug)]
+enum Node {
+    Empty,
+    Leaf(Vec<u8>, Vec<u8>),
+    Extension(Vec<u8>, Reference),
+    Branch(Vec<Option<Reference>>, Option<Vec<u8>>),
+}
+
+/// Returns the value at `key` in the trie with the given root.
+#[cfg(test)]
+pub fn get<S: NodeStore>(store: &S, root: &H256, key: &[u8]) -> Option<Vec<u8>> {
+    walk(store, root, key, &mut Vec::new())
+}
+
+/// Returns the nodes on the path to `key` in the trie with the given root, starting with the
+/// root, as in the proofs returned by `eth_getProof`. Nodes embedded in their parent are not
+/// repeated. The proof is also valid if there is no value at `key`.
+#[cfg(any(feature = "contract", test))]
+pub fn prove<S: NodeStore>(store: &S, root: &H256, key: &[u8]) -> Vec<Vec<u8>> {
+    let mut proof = Vec::new();
+    walk(store, root, key, &mut proof);
+    proof
+}
+
+/// Returns the root of the trie with `value` (which must not be empty) set at `key`.
+pub fn insert<S: NodeStoreMut>(store: &mut S, root: &H256, key: &[u8], value: Vec<u8>) -> H256 {
+    let node = load_root(store, root);
+    let node = insert_node(store, node, &to_nibbles(key), value);
+    replace_root(store, root, &node)
+}
+
+/// Returns the root of the trie without the value at `key`.
+pub fn remove<S: NodeStoreMut>(store: &mut S, root: &H256, key: &[u8]) -> H256 {
+    let node = load_root(store, root);
+    let node = remove_node(store, node, &to_nibbles(key));
+    replace_root(store, root, &node)
+}
+
+/// Drops the reference held to the root of a trie, removing the nodes no longer referenced.
+pub fn clear<S: NodeStoreMut>(store: &mut S, root: &H256) {
+    if *root != EMPTY_TRIE_ROOT {
+        release(store, root);
+    }
+}
+
+/// Returns the root of the trie with the given keys and values.
+pub fn trie_root(items: Vec<(Vec<u8>, Vec<u8>)>) -> H256 {
+    let mut store = MemoryNodeStore::default();
+    items
+        .into_iter()
+        .fold(EMPTY_TRIE_ROOT, |root, (key, value)| {
+            insert(&mut store, &root, &key, value)
+        })
+}
+
+#[cfg(any(feature = "contract", test))]
+fn walk<S: NodeStore>(
+    store: &S,
+    root: &H256,
+    key: &[u8],
+    proof: &mut Vec<Vec<u8>>,
+) -> Option<Vec<u8>> {
+    if *root == EMPTY_TRIE_ROOT {
+        return None;
+    }
+    let mut node = load_hash(store, root, proof);
+    let nibbles = to_nibbles(key);
+    let mut path: &[u8] = &nibbles;
+    loop {
+        let next = match node {
+            Node::Empty => return None,
+            Node::Leaf(leaf_path, value) => {
+                return if leaf_path == path { Some(value) } else { None };
+            }
+            Node::Extension(extension_path, child) => {
+                if !path.starts_with(&extension_path) {
+                    return None;
+                }
+                path = &path[extension_path.len()..];
+                child
+            }
+            Node::Branch(mut children, value) => match path.split_first() {
+                None => return value,
+                Some((nibble, rest)) => {
+                    path = rest;
+                    children[*nibble as usize].take()?
+                }
+            },
+        };
+        node = match next {
+            Reference::Hash(hash) => load_hash(store, &hash, proof),
+            Reference::Inline(encoded) => decode_node(&encoded),
+        };
+    }
+}
+
+fn insert_node<S: NodeStoreMut>(store: &mut S, node: Node, path: &[u8], value: Vec<u8>) -> Node {
+    match node {
+        Node::Empty => Node::Leaf(path.to_vec(), value),
+        Node::Leaf(leaf_path, leaf_value) => {
+            if leaf_path == path {
+                return Node::Leaf(leaf_path, value);
+            }
+            let shared = shared_prefix_len(&leaf_path, path);
+            let branch = Node::Branch(vec![None; 16], None);
+            let branch = insert_node(store, branch, &leaf_path[shared..], leaf_value);
+            let branch = insert_node(store, branch, &path[shared..], value);
+            extend(store, &path[..shared], branch)
+        }
+        Node::Extension(extension_path, child) => {
+            let shared = shared_prefix_len(&extension_path, path);
+            if shared == extension_path.len() {
+                let child = load(store, &child);
+                let child = insert_node(store, child, &path[shared..], value);
+                return extend(store, &extension_path, child);
+            }
+            // The extension is split by a branch
+            let rest = &extension_path[shared + 1..];
+            let mut children = vec![None; 16];
+            children[extension_path[shared] as usize] = Some(if rest.is_empty() {
+                child
+            } else {
+                store_node(store, &Node::Extension(rest.to_vec(), child))
+            });
+            let branch = Node::Branch(children, None);
+            let branch = insert_node(store, branch, &path[shared..], value);
+            extend(store, &path[..shared], branch)
+        }
+        Node::Branch(mut children, branch_value) => match path.split_first() {
+            None => Node::Branch(children, Some(value)),
+            Some((nibble, rest)) => {
+                let i = *nibble as usize;
+                let child = match children[i].take() {
+                    Some(child) => load(store, &child),
+                    None => Node::Empty,
+                };
+                let child = insert_node(store, child, rest, value);
+                children[i] = Some(store_node(store, &child));
+                Node::Branch(children, branch_value)
+            }
+        },
+    }
+}
+
+fn remove_node<S: NodeStoreMut>(store: &mut S, node: Node, path: &[u8]) -> Node {
+    match node {
+        Node::Empty => Node::Empty,
+        Node::Leaf(leaf_path, leaf_value) => {
+            if leaf_path == path {
+                Node::Empty
+            } else {
+                Node::Leaf(leaf_path, leaf_value)
+            }
+        }
+        Node::Extension(extension_path, child) => {
+            if !path.starts_with(&extension_path) {
+                return Node::Extension(extension_path, child);
+            }
+            let child = load(store, &child);
+            let child = remove_node(store, child, &path[extension_path.len()..]);
+            extend(store, &extension_path, child)
+        }
+        Node::Branch(mut children, mut branch_value) => {
+            let mut updated = None;
+            match path.split_first() {
+                None => branch_value = None,
+                Some((nibble, rest)) => {
+                    let i = *nibble as usize;
+                    if let Some(child) = children[i].take() {
+                        let child = load(store, &child);
+                        match remove_node(store, child, rest) {
+                            Node::Empty => (),
+                            child => updated = Some((i, child)),
+                        }
+                    }
+                }
+            }
+            // The updated child is only stored if it stays a child of the branch, so that no
+            // unreferenced node is left in the store.
+            let others = children.iter().filter(|child| child.is_some()).count();
+            match (updated, others, branch_value) {
+                (Some((i, child)), 0, None) => extend(store, &[i as u8], child),
+                (Some((i, child)), _, value) => {
+                    children[i] = Some(store_node(store, &child));
+                    Node::Branch(children, value)
+                }
+                (None, _, value) => collapse_branch(store, children, value),
+            }
+        }
+    }
+}
+
+/// Returns the node at the end of `path` from the current node, with the same meaning as
+/// an extension to `node` but without extensions to or from other extensions or leaves.
+fn extend<S: NodeStoreMut>(store: &mut S, path: &[u8], node: Node) -> Node {
+    if path.is_empty() {
+        return node;
+    }
+    match node {
+        Node::Empty => Node::Empty,
+        Node::Leaf(rest, value) => Node::Leaf([path, &rest[..]].concat(), value),
+        Node::Extension(rest, child) => Node::Extension([path, &rest[..]].concat(), child),
+        branch => Node::Extension(path.to_vec(), store_node(store, &branch)),
+    }
+}
+
+/// Replaces a branch left with less than two entries.
+fn collapse_branch<S: NodeStoreMut>(
+    store: &mut S,
+    children: Vec<Option<Reference>>,
+    value: Option<Vec<u8>>,
+) -> Node {
+    let count = children.iter().filter(|child| child.is_some()).count();
+    match (count, value) {
+        (0, None) => Node::Empty,
+        (0, Some(value)) => Node::Leaf(Vec::new(), value),
+        (1, None) => {
+            let i = children.iter().position(Option::is_some).unwrap();
+            let child = load(store, children[i].as_ref().unwrap());
+            extend(store, &[i as u8], child)
+        }
+        (_, value) => Node::Branch(children, value),
+    }
+}
+
+fn load<S: NodeStore>(store: &S, reference: &Reference) -> Node {
+    match reference {
+        Reference::Hash(hash) => load_hash(store, hash, &mut Vec::new()),
+        Reference::Inline(encoded) => decode_node(encoded),
+    }
+}
+
+fn load_hash<S: NodeStore>(store: &S, hash: &H256, proof: &mut Vec<Vec<u8>>) -> Node {
+    let encoded = store.get_node(hash).expect("ERR_MISSING_TRIE_NODE");
+    let node = decode_node(&encoded);
+    proof.push(encoded);
+    node
+}
+
+fn load_root<S: NodeStore>(store: &S, root: &H256) -> Node {
+    if *root == EMPTY_TRIE_ROOT {
+        Node::Empty
+    } else {
+        load_hash(store, root, &mut Vec::new())
+    }
+}
+
+fn store_node<S: NodeStoreMut>(store: &mut S, node: &Node) -> Reference {
+    let encoded = encode_node(node);
+    if encoded.len() < 32 {
+        return Reference::Inline(encoded);
+    }
+    let hash = keccak(&encoded);
+    put(store, &hash, &encoded);
+    Reference::Hash(hash)
+}
+
+/// Stores the updated root of a trie, which is always referred to by hash unlike other nodes,
+/// in place of `root`.
+fn replace_root<S: NodeStoreMut>(store: &mut S, root: &H256, node: &Node) -> H256 {
+    let encoded = encode_node(node);
+    let hash = keccak(&encoded);
+    if hash != EMPTY_TRIE_ROOT {
+        put(store, &hash, &encoded);
+        store.set_refcount(&hash, store.get_refcount(&hash) + 1);
+    }
+    clear(store, root);
+    hash
+}
+
+/// Stores a node if it is new, which adds a reference to each of its children.
+fn put<S: NodeStoreMut>(store: &mut S, hash: &H256, encoded: &[u8]) {
+    if store.has_node(hash) {
+        return;
+    }
+    store.put_node(hash, encoded);
+    for child in child_hashes(encoded) {
+        store.set_refcount(&child, store.get_refcount(&child) + 1);
+    }
+}
+
+/// Drops a reference to a node, which is removed along with its references to its children
+/// once it is no longer referenced.
+fn release<S: NodeStoreMut>(store: &mut S, hash: &H256) {
+    let refcount = store.get_refcount(hash);
+    if refcount > 1 {
+        store.set_refcount(hash, refcount - 1);
+        return;
+    }
+    if let Some(encoded) = store.get_node(hash) {
+        store.remove_node(hash);
+        for child in child_hashes(&encoded) {
+            release(store, &child);
+        }
+    }
+}
+
+/// Returns the hashes of the children of a node, including those of nodes embedded in it.
+fn child_hashes(encoded: &[u8]) -> Vec<H256> {
+    let mut hashes = Vec::new();
+    let mut references = Vec::new();
+    match decode_node(encoded) {
+        Node::Extension(_, child) => references.push(child),
+        Node::Branch(children, _) => references.extend(children.into_iter().flatten()),
+        _ => (),
+    }
+    while let Some(reference) = references.pop() {
+        match reference {
+            Reference::Hash(hash) => hashes.push(hash),
+            Reference::Inline(encoded) => match decode_node(&encoded) {
+                Node::Extension(_, child) => references.push(child),
+                Node::Branch(children, _) => references.extend(children.into_iter().flatten()),
+                _ => (),
+            },
+        }
+    }
+    hashes
+}
+
+fn encode_node(node: &Node) -> Vec<u8> {
+    match node {
+        Node::Empty => vec![EMPTY_STRING_CODE],
+        Node::Leaf(path, value) => {
+            let mut stream = RlpStream::new_list(2);
+            stream.append(&hex_prefix(path, true));
+            stream.append(value);
+            stream.out().to_vec()
+        }
+        Node::Extension(path, child) => {
+            let mut stream = RlpStream::new_list(2);
+            stream.append(&hex_prefix(path, false));
+            append_reference(&mut stream, Some(child));
+            stream.out().to_vec()
+        }
+        Node::Branch(children, value) => {
+            let mut stream = RlpStream::new_list(17);
+            for child in children {
+                append_reference(&mut stream, child.as_ref());
+            }
+            match value {
+                Some(value) => stream.append(value),
+                None => stream.append_empty_data(),
+            };
+            stream.out().to_vec()
+        }
+    }
+}
+
+fn append_reference(stream: &mut RlpStream, reference: Option<&Reference>) {
+    match reference {
+        None => stream.append_empty_data(),
+        Some(Reference::Hash(hash)) => stream.append(hash),
+        Some(Reference::Inline(encoded)) => stream.append_raw(encoded, 1),
+    };
+}
+
+fn decode_node(encoded: &[u8]) -> Node {
+    let rlp = Rlp::new(encoded);
+    if rlp.is_empty() {
+        return Node::Empty;
+    }
+    match rlp.item_count().expect("ERR_INVALID_TRIE_NODE") {
+        2 => {
+            let path: Vec<u8> = rlp.val_at(0).expect("ERR_INVALID_TRIE_NODE");
+            let (path, is_leaf) = decode_hex_prefix(&path);
+            if is_leaf {
+                Node::Leaf(path, rlp.val_at(1).expect("ERR_INVALID_TRIE_NODE"))
+            } else {
+                let child = decode_reference(&rlp.at(1).expect("ERR_INVALID_TRIE_NODE"));
+                Node::Extension(path, child.expect("ERR_INVALID_TRIE_NODE"))
+            }
+        }
+        17 => {
+            let children = (0..16)
+                .map(|i| decode_reference(&rlp.at(i).expect("ERR_INVALID_TRIE_NODE")))
+                .collect();
+            let value: Vec<u8> = rlp.val_at(16).expect("ERR_INVALID_TRIE_NODE");
+            Node::Branch(children, if value.is_empty() { None } else { Some(value) })
+        }
+        _ => panic!("ERR_INVALID_TRIE_NODE"),
+    }
+}
+
+fn decode_reference(rlp: &Rlp) -> Option<Reference> {
+    if rlp.is_list() {
+        return Some(Reference::Inline(rlp.as_raw().to_vec()));
+    }
+    let data = rlp.data().expect("ERR_INVALID_TRIE_NODE");
+    if data.is_empty() {
+        None
+    } else {
+        Some(Reference::Hash(H256::from_slice(data)))
+    }
+}
+
+fn shared_prefix_len(a: &[u8], b: &[u8]) -> usize {
+    a.iter().zip(b.iter()).take_while(|(x, y)| x == y).count()
+}
+
+fn to_nibbles(bytes: &[u8]) -> Vec<u8> {
+    let mut nibbles = Vec::with_capacity(2 * bytes.len());
+    for b in bytes {
+        nibbles.push(b >> 4);
+        nibbles.push(b & 0xf);
+    }
+    nibbles
+}
+
+/// Hex-prefix encoding of a path, as specified in the yellow paper.
+fn hex_prefix(nibbles: &[u8], is_leaf: bool) -> Vec<u8> {
+    let flag = if is_leaf { 2 } else { 0 };
+    let mut result = Vec::with_capacity(nibbles.len() / 2 + 1);
+    let rest = if nibbles.len() % 2 == 1 {
+        result.push((flag + 1) << 4 | nibbles[0]);
+        &nibbles[1..]
+    } else {
+        result.push(flag << 4);
+        nibbles
+    };
+    for pair in rest.chunks(2) {
+        result.push(pair[0] << 4 | pair[1]);
+    }
+    result
+}
+
+fn decode_hex_prefix(bytes: &[u8]) -> (Vec<u8>, bool) {
+    let flag = bytes.first().map(|b| b >> 4).unwrap_or(0);
+    let mut nibbles = to_nibbles(bytes);
+    // Drop the flag nibble, and the padding nibble for even lengths
+    let skip = if flag % 2 == 1 { 1 } else { 2 };
+    nibbles.drain(..skip.min(nibbles.len()));
+    (nibbles, flag >= 2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_trie_root() {
+        assert_eq!(keccak(&[EMPTY_STRING_CODE]), EMPTY_TRIE_ROOT);
+        assert_eq!(trie_root(Vec::new()), EMPTY_TRIE_ROOT);
+    }
+
+    // Test taken from go-ethereum's trie package
+    #[test]
+    fn test_trie_root() {
+        let items = vec![
+            (b"doe".to_vec(), b"reindeer".to_vec()),
+            (b"dog".to_vec(), b"puppy".to_vec()),
+            (b"dogglesworth".to_vec(), b"cat".to_vec()),
+        ];
+        let expected = H256::from_slice(
+            &hex::decode("8aad789dff2f538bca5d8ea56e8abe10f4c7ba3a5dea95fea4cd6e7c3a1168d3")
+                .unwrap(),
+        );
+
+        assert_eq!(trie_root(items.clone()), expected);
+        // The root does not depend on the insertion order
+        assert_eq!(trie_root(items.into_iter().rev().collect()), expected);
+    }
+
+    #[test]
+    fn test_insert_and_remove() {
+        let items: Vec<(Vec<u8>, Vec<u8>)> = (0u32..100)
+            .map(|i| {
+                let key = keccak(&i.to_be_bytes()).as_bytes()[..3].to_vec();
+                (key, vec![i as u8 + 1])
+            })
+            .collect();
+        let mut store = MemoryNodeStore::default();
+        let mut root = EMPTY_TRIE_ROOT;
+        for (key, value) in items.iter() {
+            root = insert(&mut store, &root, key, value.clone());
+        }
+        assert_eq!(root, trie_root(items.clone()));
+        for (key, value) in items.iter() {
+            assert_eq!(get(&store, &root, key).as_ref(), Some(value));
+        }
+
+        for (key, _) in items[..50].iter() {
+            root = remove(&mut store, &root, key);
+        }
+        assert_eq!(root, trie_root(items[50..].to_vec()));
+        assert_eq!(get(&store, &root, &items[0].0), None);
+        // Only the nodes of the current trie are kept
+        assert_eq!(store.len(), build(&items[50..]).0.len());
+
+        for (key, _) in items[50..].iter() {
+            root = remove(&mut store, &root, key);
+        }
+        assert_eq!(root, EMPTY_TRIE_ROOT);
+        assert_eq!(store.len(), 0);
+    }
+
+    #[test]
+    fn test_shared_nodes() {
+        let items: Vec<(Vec<u8>, Vec<u8>)> = (0u32..20)
+            .map(|i| (keccak(&i.to_be_bytes()).0.to_vec(), vec![i as u8 + 1; 40]))
+            .collect();
+        let (mut store, root) = build(&items);
+        // A second trie in the same store, sharing most of its nodes with the first one
+        let other = items[1..]
+            .iter()
+            .fold(EMPTY_TRIE_ROOT, |root, (key, value)| {
+                insert(&mut store, &root, key, value.clone())
+            });
+        assert!(store.len() < build(&items).0.len() + build(&items[1..]).0.len());
+
+        clear(&mut store, &root);
+        for (key, value) in items[1..].iter() {
+            assert_eq!(get(&store, &other, key).as_ref(), Some(value));
+        }
+        assert_eq!(store.len(), build(&items[1..]).0.len());
+
+        clear(&mut store, &other);
+        assert_eq!(store.len(), 0);
+    }
+
+    #[test]
+    fn test_prove() {
+        let items: Vec<(Vec<u8>, Vec<u8>)> = (0u32..20)
+            .map(|i| (keccak(&i.to_be_bytes()).0.to_vec(), vec![i as u8 + 1; 40]))
+            .collect();
+        let (store, root) = build(&items);
+
+        // The proof contains all the nodes needed to look up the key
+        let proof = prove(&store, &root, &items[3].0);
+        let proof_store: BTreeMap<H256, Vec<u8>> = proof
+            .into_iter()
+            .map(|node| (keccak(&node), node))
+            .collect();
+        assert_eq!(
+            get(&proof_store, &root, &items[3].0),
+            Some(items[3].1.clone())
+        );
+    }
+
+    fn build(items: &[(Vec<u8>, Vec<u8>)]) -> (MemoryNodeStore, H256) {
+        let mut store = MemoryNodeStore::default();
+        let root = items.iter().fold(EMPTY_TRIE_ROOT, |root, (key, value)| {
+            insert(&mut store, &root, key, value.clone())
+        });
+        (store, root)
+    }
+}
@@ -302,6 +302,12 @@ pub fn keccak(data: &[u8]) -> H256 {
     H256::from_slice(Keccak256::digest(data).as_slice())
 }
 
+/// Hash of the empty code, which is the code hash of accounts without code.
+pub const EMPTY_CODE_HASH: H256 = H256([
+    0xc5, 0xd2, 0x46, 0x01, 0x86, 0xf7, 0x23, 0x3c, 0x92, 0x7e, 0x7d, 0xb2, 0xdc, 0xc7, 0x03, 0xc0,
+    0xe5, 0x00, 0xb6, 0x53, 0xca, 0x82, 0x27, 0x3b, 0x7b, 0xfa, 0xd8, 0x04, 0x5d, 0x85, 0xa4, 0x70,
+]);
+
 #[allow(dead_code)]
 pub fn near_account_to_evm_address(addr: &[u8]) -> Address {
     Address::from_slice(&keccak(addr)[12..])
@@ -518,6 +524,11 @@ mod tests {
         assert_eq!(stack.into_vec(), vec![1, 2, 6, 7]);
     }
 
+    #[test]
+    fn test_empty_code_hash() {
+        assert_eq!(keccak(&[]), EMPTY_CODE_HASH);
+    }
+
     #[test]
     fn test_wei_from_u64() {
         let x: u64 = rand::random();